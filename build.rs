@@ -0,0 +1,13 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/automation.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc is unavailable");
+        // SAFETY: 构建脚本是单线程的，设置环境变量不会与其他线程竞争
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/automation.proto"], &["proto"])
+            .expect("failed to compile proto/automation.proto");
+    }
+}
@@ -0,0 +1,30 @@
+use std::{env, fs, path::Path};
+
+use automation::schema::{input_action_schema_json, INPUT_ACTION_SCHEMA_PATH};
+
+/// 导出 InputAction 的 JSON Schema 到 schema/ 目录
+///
+/// 使用 `--check` 时只比较已提交的文件是否与当前类型定义一致，不一致则返回非零退出码
+fn main() -> anyhow::Result<()> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(INPUT_ACTION_SCHEMA_PATH);
+    let schema = input_action_schema_json();
+
+    if env::args().any(|arg| arg == "--check") {
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        if existing != schema {
+            anyhow::bail!(
+                "{} is out of date, run `just schema` to regenerate it",
+                INPUT_ACTION_SCHEMA_PATH
+            );
+        }
+        println!("{} is up to date", INPUT_ACTION_SCHEMA_PATH);
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, schema)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use std::{env, thread, time::Duration};
+
+use automation::recorder::{coalesce, CoalesceOptions, Recorder};
+
+/// 录制指定秒数（默认 10 秒）的真实输入，并输出合并后的 InputAction 脚本
+fn main() -> anyhow::Result<()> {
+    let seconds = env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(10);
+
+    let recorder = Recorder::start(None)?;
+    eprintln!("Recording for {}s...", seconds);
+    thread::sleep(Duration::from_secs(seconds));
+    let events = recorder.stop()?;
+    eprintln!("Captured {} events", events.len());
+
+    let script = coalesce(&events, &CoalesceOptions::default());
+    println!("{}", serde_json::to_string_pretty(&script)?);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use std::env;
+
+use automation::screen::{list_displays, save_png, ScreenCapturer};
+
+/// 截取整个屏幕（或通过参数指定显示器编号）并保存为 PNG
+fn main() -> anyhow::Result<()> {
+    let output = env::args().nth(1).unwrap_or_else(|| "screenshot.png".to_string());
+    let display = env::args().nth(2).and_then(|arg| arg.parse().ok());
+
+    for info in list_displays()? {
+        println!("display {}: {} {:?} primary={}", info.id, info.name, info.bounds, info.primary);
+    }
+
+    let capturer = ScreenCapturer::connect(None)?;
+    let image = match display {
+        Some(id) => capturer.capture_display(id)?,
+        None => capturer.capture_screen()?,
+    };
+    save_png(&image, &output)?;
+    println!("Saved {}x{} screenshot to {}", image.width(), image.height(), output);
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::env;
+
+use automation::window::{WindowManager, WindowSelector};
+
+/// 列出所有顶层窗口（或通过参数按标题过滤）
+fn main() -> anyhow::Result<()> {
+    let manager = WindowManager::connect(None)?;
+    let windows = match env::args().nth(1) {
+        Some(title) => manager.find(&WindowSelector::title(title))?,
+        None => manager.list()?,
+    };
+    for window in windows {
+        println!(
+            "{:#010x} pid={:?} {:?} [{}] {:?} {:?}",
+            window.id, window.pid, window.title, window.class, window.geometry, window.state
+        );
+    }
+    Ok(())
+}
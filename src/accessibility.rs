@@ -0,0 +1,638 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::screen::Rect;
+
+/// 无障碍元素选择条件，所有给出的条件都需满足
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct ElementSelector {
+    /// 应用名称（忽略大小写），如 "gedit"
+    pub application: Option<String>,
+    /// 角色名称（忽略大小写），如 "push button"、"text"、"menu item"
+    pub role: Option<String>,
+    /// 元素名称包含该文本（忽略大小写）
+    pub name: Option<String>,
+    /// 从应用根节点开始的子节点序号路径，如 "0/2/1"
+    pub path: Option<String>,
+}
+
+impl ElementSelector {
+    /// 是否没有给出任何条件
+    pub fn is_empty(&self) -> bool {
+        self.application.is_none() && self.role.is_none() && self.name.is_none() && self.path.is_none()
+    }
+
+    /// 解析 path，格式错误时返回 None
+    pub fn path_indices(&self) -> Option<Vec<i32>> {
+        let path = self.path.as_deref()?;
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.trim().parse().ok())
+            .collect()
+    }
+
+    /// 应用名称是否满足条件
+    pub fn matches_application(&self, name: &str) -> bool {
+        self.application
+            .as_ref()
+            .is_none_or(|application| application.eq_ignore_ascii_case(name))
+    }
+
+    /// 元素的角色和名称是否满足条件
+    pub fn matches_element(&self, role: &str, name: &str) -> bool {
+        self.role.as_ref().is_none_or(|expected| expected.eq_ignore_ascii_case(role))
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|expected| name.to_lowercase().contains(&expected.to_lowercase()))
+    }
+}
+
+/// 无障碍元素信息
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ElementInfo {
+    pub application: String,
+    pub role: String,
+    pub name: String,
+    /// 从应用根节点开始的子节点序号路径
+    pub path: String,
+    /// 元素在屏幕上的位置和尺寸（不支持 Component 接口时为空）
+    pub bounds: Option<Rect>,
+    /// 状态名称，如 "enabled"、"focused"、"checked"
+    pub states: Vec<String>,
+    /// 元素支持的动作名称，如 "click"、"press"
+    pub actions: Vec<String>,
+}
+
+impl ElementInfo {
+    /// 是否处于指定状态
+    pub fn has_state(&self, state: &str) -> bool {
+        self.states.iter().any(|s| s == state)
+    }
+
+    /// 可用于点击的动作序号
+    pub fn click_action(&self) -> Option<usize> {
+        self.actions
+            .iter()
+            .position(|action| CLICK_ACTIONS.contains(&action.to_lowercase().as_str()))
+    }
+}
+
+/// 对无障碍元素执行的操作
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(tag = "type", content = "params")]
+pub enum ElementCommand {
+    /// 执行元素的点击类动作（click、press、activate 等）
+    Click,
+    /// 替换可编辑元素的全部文本
+    SetText { text: String },
+}
+
+/// 视为“点击”的 AT-SPI 动作名称
+const CLICK_ACTIONS: &[&str] = &["click", "press", "activate", "jump", "toggle"];
+
+#[cfg(all(target_os = "linux", feature = "accessibility"))]
+pub use self::atspi::{click_element, find_elements, set_element_text, AccessibilityClient};
+
+#[cfg(all(target_os = "linux", feature = "accessibility"))]
+mod atspi {
+    use std::collections::VecDeque;
+
+    use tokio::sync::OnceCell;
+    use zbus::proxy::CacheProperties;
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::Connection;
+
+    use super::{ElementCommand, ElementInfo, ElementSelector};
+    use crate::error::AutomationError;
+    use crate::screen::Rect;
+
+    const REGISTRY: &str = "org.a11y.atspi.Registry";
+    const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+    /// GetExtents 使用屏幕坐标
+    const SCREEN_COORDS: u32 = 0;
+    /// 每个应用最多遍历的节点数，避免在超大的树上卡住
+    const MAX_NODES_PER_APPLICATION: usize = 20_000;
+
+    /// AT-SPI 状态位名称，顺序与 AtspiStateType 一致
+    const STATE_NAMES: &[&str] = &[
+        "invalid",
+        "active",
+        "armed",
+        "busy",
+        "checked",
+        "collapsed",
+        "defunct",
+        "editable",
+        "enabled",
+        "expandable",
+        "expanded",
+        "focusable",
+        "focused",
+        "has_tooltip",
+        "horizontal",
+        "iconified",
+        "modal",
+        "multi_line",
+        "multiselectable",
+        "opaque",
+        "pressed",
+        "resizable",
+        "selectable",
+        "selected",
+        "sensitive",
+        "showing",
+        "single_line",
+        "stale",
+        "transient",
+        "vertical",
+        "visible",
+        "manages_descendants",
+        "indeterminate",
+        "required",
+        "truncated",
+        "animated",
+        "invalid_entry",
+        "supports_autocompletion",
+        "selectable_text",
+        "is_default",
+        "visited",
+        "checkable",
+        "has_popup",
+        "read_only",
+    ];
+
+    /// 将 GetState 返回的位集合转换为状态名称
+    fn state_names(bits: &[u32]) -> Vec<String> {
+        STATE_NAMES
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                bits.get(index / 32)
+                    .is_some_and(|word| word & (1 << (index % 32)) != 0)
+            })
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    #[zbus::proxy(interface = "org.a11y.Bus", default_service = "org.a11y.Bus", default_path = "/org/a11y/bus")]
+    trait Bus {
+        fn get_address(&self) -> zbus::Result<String>;
+    }
+
+    #[zbus::proxy(interface = "org.a11y.atspi.Accessible")]
+    trait Accessible {
+        fn get_children(&self) -> zbus::Result<Vec<(String, OwnedObjectPath)>>;
+        fn get_child_at_index(&self, index: i32) -> zbus::Result<(String, OwnedObjectPath)>;
+        fn get_role_name(&self) -> zbus::Result<String>;
+        fn get_state(&self) -> zbus::Result<Vec<u32>>;
+        fn get_interfaces(&self) -> zbus::Result<Vec<String>>;
+        #[zbus(property)]
+        fn name(&self) -> zbus::Result<String>;
+    }
+
+    #[zbus::proxy(interface = "org.a11y.atspi.Component")]
+    trait Component {
+        fn get_extents(&self, coord_type: u32) -> zbus::Result<(i32, i32, i32, i32)>;
+    }
+
+    #[zbus::proxy(interface = "org.a11y.atspi.Action")]
+    trait Action {
+        fn get_actions(&self) -> zbus::Result<Vec<(String, String, String)>>;
+        fn do_action(&self, index: i32) -> zbus::Result<bool>;
+    }
+
+    #[zbus::proxy(interface = "org.a11y.atspi.EditableText")]
+    trait EditableText {
+        fn set_text_contents(&self, text: &str) -> zbus::Result<bool>;
+    }
+
+    impl From<zbus::Error> for AutomationError {
+        fn from(err: zbus::Error) -> Self {
+            AutomationError::Platform(format!("AT-SPI: {}", err))
+        }
+    }
+
+    /// 树中的一个节点：所在连接名、对象路径以及相对于应用根节点的序号路径
+    #[derive(Clone)]
+    struct Node {
+        destination: String,
+        path: OwnedObjectPath,
+        indices: Vec<i32>,
+    }
+
+    /// AT-SPI2 客户端，连接到无障碍总线并遍历各应用的无障碍树
+    #[derive(Clone)]
+    pub struct AccessibilityClient {
+        conn: Connection,
+    }
+
+    impl AccessibilityClient {
+        /// 连接无障碍总线（优先使用 AT_SPI_BUS_ADDRESS，否则通过会话总线上的 org.a11y.Bus 查询地址）
+        pub async fn connect() -> Result<Self, AutomationError> {
+            let address = match std::env::var("AT_SPI_BUS_ADDRESS") {
+                Ok(address) if !address.is_empty() => address,
+                _ => {
+                    let session = Connection::session().await?;
+                    BusProxy::new(&session).await?.get_address().await?
+                }
+            };
+            let conn = zbus::connection::Builder::address(address.as_str())?.build().await?;
+            Ok(AccessibilityClient { conn })
+        }
+
+        /// 查找满足条件的元素（按遍历顺序）
+        pub async fn find(&self, selector: &ElementSelector) -> Result<Vec<ElementInfo>, AutomationError> {
+            let indices = match &selector.path {
+                Some(path) => Some(selector.path_indices().ok_or_else(|| {
+                    AutomationError::Platform(format!("invalid element path {:?}", path))
+                })?),
+                None => None,
+            };
+
+            let mut found = Vec::new();
+            let root = self.accessible(REGISTRY, &OwnedObjectPath::try_from(ROOT_PATH).map_err(zbus::Error::from)?).await?;
+            for (destination, path) in root.get_children().await? {
+                let application = Node {
+                    destination,
+                    path,
+                    indices: Vec::new(),
+                };
+                // 应用可能已经退出，跳过即可
+                let Ok(app_name) = self.name(&application).await else {
+                    continue;
+                };
+                if !selector.matches_application(&app_name) {
+                    continue;
+                }
+                match &indices {
+                    Some(indices) => {
+                        if let Some(node) = self.descend(&application, indices).await?
+                            && let Some(info) = self.matching_info(&node, &app_name, selector).await?
+                        {
+                            found.push(info);
+                        }
+                    }
+                    None => self.search(&application, &app_name, selector, &mut found).await?,
+                }
+            }
+            Ok(found)
+        }
+
+        /// 查找满足条件的第一个元素并执行操作，返回被操作的元素
+        pub async fn apply(&self, selector: &ElementSelector, command: &ElementCommand) -> Result<ElementInfo, AutomationError> {
+            let element = self
+                .find(selector)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| AutomationError::Platform(format!("no element matches {:?}", selector)))?;
+            let node = self.node_for(&element).await?;
+
+            let done = match command {
+                ElementCommand::Click => {
+                    let index = element.click_action().ok_or_else(|| {
+                        AutomationError::Platform(format!("element {:?} has no click action", element.name))
+                    })?;
+                    self.proxy::<ActionProxy>(&node).await?.do_action(index as i32).await?
+                }
+                ElementCommand::SetText { text } => {
+                    self.proxy::<EditableTextProxy>(&node).await?.set_text_contents(text).await?
+                }
+            };
+            if !done {
+                return Err(AutomationError::Platform(format!(
+                    "element {:?} rejected {:?}",
+                    element.name, command
+                )));
+            }
+            Ok(element)
+        }
+
+        /// 广度优先遍历一个应用的无障碍树
+        async fn search(
+            &self,
+            application: &Node,
+            app_name: &str,
+            selector: &ElementSelector,
+            found: &mut Vec<ElementInfo>,
+        ) -> Result<(), AutomationError> {
+            let mut queue = VecDeque::from([application.clone()]);
+            let mut visited = 0;
+            while let Some(node) = queue.pop_front() {
+                visited += 1;
+                if visited > MAX_NODES_PER_APPLICATION {
+                    break;
+                }
+                // 遍历过程中元素可能被销毁，跳过即可
+                let Ok(accessible) = self.accessible(&node.destination, &node.path).await else {
+                    continue;
+                };
+                if let Ok(Some(info)) = self.matching_info(&node, app_name, selector).await {
+                    found.push(info);
+                }
+                let Ok(children) = accessible.get_children().await else {
+                    continue;
+                };
+                for (index, (destination, path)) in children.into_iter().enumerate() {
+                    let mut indices = node.indices.clone();
+                    indices.push(index as i32);
+                    queue.push_back(Node {
+                        destination,
+                        path,
+                        indices,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        /// 沿序号路径找到节点
+        async fn descend(&self, application: &Node, indices: &[i32]) -> Result<Option<Node>, AutomationError> {
+            let mut node = application.clone();
+            for &index in indices {
+                let accessible = self.accessible(&node.destination, &node.path).await?;
+                let Ok((destination, path)) = accessible.get_child_at_index(index).await else {
+                    return Ok(None);
+                };
+                if path.as_str() == "/org/a11y/atspi/null" {
+                    return Ok(None);
+                }
+                node.indices.push(index);
+                node = Node {
+                    destination,
+                    path,
+                    indices: node.indices,
+                };
+            }
+            Ok(Some(node))
+        }
+
+        /// 根据 find 返回的元素重新定位节点
+        async fn node_for(&self, element: &ElementInfo) -> Result<Node, AutomationError> {
+            let selector = ElementSelector {
+                application: Some(element.application.clone()),
+                ..Default::default()
+            };
+            let root = self.accessible(REGISTRY, &OwnedObjectPath::try_from(ROOT_PATH).map_err(zbus::Error::from)?).await?;
+            let indices = ElementSelector {
+                path: Some(element.path.clone()),
+                ..Default::default()
+            }
+            .path_indices()
+            .unwrap_or_default();
+            for (destination, path) in root.get_children().await? {
+                let application = Node {
+                    destination,
+                    path,
+                    indices: Vec::new(),
+                };
+                if let Ok(name) = self.name(&application).await
+                    && selector.matches_application(&name)
+                    && let Some(node) = self.descend(&application, &indices).await?
+                {
+                    return Ok(node);
+                }
+            }
+            Err(AutomationError::Platform(format!("element {:?} disappeared", element.name)))
+        }
+
+        /// 节点满足条件时读取完整信息
+        async fn matching_info(
+            &self,
+            node: &Node,
+            app_name: &str,
+            selector: &ElementSelector,
+        ) -> Result<Option<ElementInfo>, AutomationError> {
+            let accessible = self.accessible(&node.destination, &node.path).await?;
+            let role = accessible.get_role_name().await?;
+            let name = accessible.name().await.unwrap_or_default();
+            if !selector.matches_element(&role, &name) {
+                return Ok(None);
+            }
+
+            let interfaces = accessible.get_interfaces().await.unwrap_or_default();
+            let bounds = if interfaces.iter().any(|i| i == "org.a11y.atspi.Component") {
+                let (x, y, width, height) = self
+                    .proxy::<ComponentProxy>(node)
+                    .await?
+                    .get_extents(SCREEN_COORDS)
+                    .await?;
+                Some(Rect::new(x, y, width.max(0) as u32, height.max(0) as u32))
+            } else {
+                None
+            };
+            let actions = if interfaces.iter().any(|i| i == "org.a11y.atspi.Action") {
+                self.proxy::<ActionProxy>(node)
+                    .await?
+                    .get_actions()
+                    .await?
+                    .into_iter()
+                    .map(|(name, _, _)| name)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let states = state_names(&accessible.get_state().await.unwrap_or_default());
+
+            Ok(Some(ElementInfo {
+                application: app_name.to_string(),
+                role,
+                name,
+                path: node
+                    .indices
+                    .iter()
+                    .map(|index| index.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                bounds,
+                states,
+                actions,
+            }))
+        }
+
+        async fn name(&self, node: &Node) -> Result<String, AutomationError> {
+            Ok(self.accessible(&node.destination, &node.path).await?.name().await?)
+        }
+
+        async fn accessible(&self, destination: &str, path: &OwnedObjectPath) -> Result<AccessibleProxy<'static>, AutomationError> {
+            Ok(AccessibleProxy::builder(&self.conn)
+                .destination(destination.to_string())?
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?)
+        }
+
+        async fn proxy<P>(&self, node: &Node) -> Result<P, AutomationError>
+        where
+            P: From<zbus::Proxy<'static>> + zbus::proxy::Defaults,
+        {
+            let proxy = zbus::proxy::Builder::<P>::new(&self.conn)
+                .destination(node.destination.clone())?
+                .path(node.path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+            Ok(proxy)
+        }
+    }
+
+    /// 进程内共享的客户端，首次使用时连接
+    static CLIENT: OnceCell<AccessibilityClient> = OnceCell::const_new();
+
+    async fn client() -> Result<&'static AccessibilityClient, AutomationError> {
+        CLIENT.get_or_try_init(AccessibilityClient::connect).await
+    }
+
+    /// 查找满足条件的无障碍元素
+    pub async fn find_elements(selector: &ElementSelector) -> Result<Vec<ElementInfo>, AutomationError> {
+        client().await?.find(selector).await
+    }
+
+    /// 执行元素的点击类动作
+    pub async fn click_element(selector: &ElementSelector) -> Result<ElementInfo, AutomationError> {
+        client().await?.apply(selector, &ElementCommand::Click).await
+    }
+
+    /// 替换可编辑元素的文本
+    pub async fn set_element_text(selector: &ElementSelector, text: &str) -> Result<ElementInfo, AutomationError> {
+        client()
+            .await?
+            .apply(selector, &ElementCommand::SetText { text: text.to_string() })
+            .await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::{Duration, Instant};
+
+        use super::*;
+
+        #[test]
+        fn decodes_state_bits() {
+            assert!(state_names(&[]).is_empty());
+            assert_eq!(state_names(&[1 << 8 | 1 << 12]), ["enabled", "focused"]);
+            // 第二个字从 32 号状态（indeterminate）开始
+            assert_eq!(state_names(&[1 << 30, 1 << 11]), ["visible", "read_only"]);
+            // 超出已知状态的位被忽略
+            assert_eq!(state_names(&[0, 1 << 31, u32::MAX]), Vec::<String>::new());
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn finds_and_clicks_gtk_buttons() {
+            if !crate::testing::display_available() || !crate::testing::session_bus_available() {
+                return;
+            }
+            let title = format!("automation accessibility test {}", std::process::id());
+            let Ok(mut dialog) = tokio::process::Command::new("zenity")
+                .args(["--info", "--text", &title])
+                .kill_on_drop(true)
+                .spawn()
+            else {
+                eprintln!("zenity is not installed, skipping test that needs a GTK application");
+                return;
+            };
+
+            let client = AccessibilityClient::connect().await.unwrap();
+            let selector = ElementSelector {
+                application: Some("zenity".into()),
+                name: Some("ok".into()),
+                ..Default::default()
+            };
+            // 应用启动后才会出现在无障碍树中
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let found = loop {
+                let found = client.find(&selector).await.unwrap();
+                if !found.is_empty() || Instant::now() > deadline {
+                    break found;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            };
+            let button = found.first().expect("OK button in the zenity dialog");
+            assert_eq!(button.application, "zenity");
+            assert!(button.click_action().is_some());
+            assert!(button.has_state("showing"));
+
+            // 按路径查找得到同一个元素
+            let by_path = ElementSelector {
+                application: Some("zenity".into()),
+                path: Some(button.path.clone()),
+                ..Default::default()
+            };
+            assert_eq!(client.find(&by_path).await.unwrap().first(), Some(button));
+
+            // 点击 OK 后对话框退出
+            let clicked = client.apply(&selector, &ElementCommand::Click).await.unwrap();
+            assert_eq!(clicked.path, button.path);
+            let status = tokio::time::timeout(Duration::from_secs(5), dialog.wait()).await.unwrap().unwrap();
+            assert!(status.success());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(actions: &[&str], states: &[&str]) -> ElementInfo {
+        ElementInfo {
+            application: "gedit".into(),
+            role: "push button".into(),
+            name: "Save".into(),
+            path: "0/1".into(),
+            bounds: None,
+            states: states.iter().map(|state| state.to_string()).collect(),
+            actions: actions.iter().map(|action| action.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_paths() {
+        let path = |path: &str| ElementSelector {
+            path: Some(path.into()),
+            ..Default::default()
+        };
+        assert_eq!(ElementSelector::default().path_indices(), None);
+        assert_eq!(path("0/2/1").path_indices(), Some(vec![0, 2, 1]));
+        assert_eq!(path("/0/ 3 //1/").path_indices(), Some(vec![0, 3, 1]));
+        assert_eq!(path("").path_indices(), Some(vec![]));
+        assert_eq!(path("0/x/1").path_indices(), None);
+        assert_eq!(path("0.5").path_indices(), None);
+    }
+
+    #[test]
+    fn selector_matches_all_given_conditions() {
+        let any = ElementSelector::default();
+        assert!(any.is_empty());
+        assert!(any.matches_application("gedit"));
+        assert!(any.matches_element("push button", "Save"));
+
+        let selector = ElementSelector {
+            application: Some("GEdit".into()),
+            role: Some("Push Button".into()),
+            name: Some("SAVE".into()),
+            path: None,
+        };
+        assert!(!selector.is_empty());
+        assert!(selector.matches_application("gedit"));
+        assert!(!selector.matches_application("gedit-plugins"));
+        assert!(selector.matches_element("push button", "Save As…"));
+        assert!(!selector.matches_element("push button", "Open"));
+        assert!(!selector.matches_element("toggle button", "Save"));
+        assert!(!ElementSelector { path: Some("0".into()), ..Default::default() }.is_empty());
+    }
+
+    #[test]
+    fn finds_states_and_click_actions() {
+        let button = element(&["focus", "Press"], &["enabled", "focused"]);
+        assert!(button.has_state("focused"));
+        assert!(!button.has_state("Focused"));
+        assert!(!button.has_state("checked"));
+        assert_eq!(button.click_action(), Some(1));
+        assert_eq!(element(&["toggle", "click"], &[]).click_action(), Some(0));
+        assert_eq!(element(&["focus", "expand or contract"], &[]).click_action(), None);
+        assert_eq!(element(&[], &[]).click_action(), None);
+    }
+}
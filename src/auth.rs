@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::enums::{InputAction, Origin};
+use crate::screen::Rect;
+
+/// 远程接口的认证配置；没有配置任何 token 和客户端证书时不做认证
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// 通过 `Authorization: Bearer <token>` 认证的客户端
+    pub tokens: Vec<TokenConfig>,
+    /// 通过客户端证书认证的客户端（需要 tls.client_ca）
+    pub clients: Vec<ClientConfig>,
+    /// 默认的限流设置，token 和客户端可以单独覆盖；不指定时不限流
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl AuthConfig {
+    /// 是否要求认证
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty() || !self.clients.is_empty()
+    }
+}
+
+/// 一个 token 及其权限
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    /// 日志和错误信息中使用的名称
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// 按客户端证书的 Common Name 识别的客户端及其权限
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    pub common_name: String,
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// 令牌桶限流：平均每秒 requests_per_second 个请求，最多连续 burst 个
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+/// TLS 终止配置（需要 tls feature），证书和私钥为 PEM 文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// 服务端证书链
+    pub cert: PathBuf,
+    /// 服务端私钥
+    pub key: PathBuf,
+    /// 签发客户端证书的 CA，设置后校验客户端出示的证书并可用于认证；
+    /// 未出示证书的客户端仍可使用 token
+    #[serde(default)]
+    pub client_ca: Option<PathBuf>,
+}
+
+/// 允许执行的操作
+///
+/// allow 和 deny 中可以写动作类型名（如 `"TypeText"`），也可以写分组：
+/// `"*"`、`"mouse"`、`"keyboard"`、`"wait"`、`"screen"`、`"window"`、`"element"`；
+/// Sequence 和 Parallel 本身总是允许，只检查其中的动作
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Permissions {
+    pub allow: Vec<String>,
+    /// 优先于 allow
+    pub deny: Vec<String>,
+    /// 鼠标只能在这些屏幕区域内操作，为空时不限制；
+    /// 设置后不允许使用相对坐标以及在运行时才确定位置的点击（ClickText、ClickImage、ClickElement）
+    pub regions: Vec<Rect>,
+    /// 是否允许截图和读取窗口信息、屏幕布局和光标位置
+    pub capture: bool,
+    /// 是否允许提交、查询和取消任务；只能看到自己提交的任务
+    pub jobs: bool,
+    /// 是否允许订阅实时事件；只推送自己提交的任务的事件
+    pub events: bool,
+    /// 是否允许管理操作：查看、触发和解除紧急停止，查看、启用和停用触发器，释放按住的输入，
+    /// 以及查看和取消所有任务、接收所有事件
+    pub admin: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions {
+            allow: vec!["*".to_string()],
+            deny: Vec::new(),
+            regions: Vec::new(),
+            capture: true,
+            jobs: true,
+            events: true,
+            admin: true,
+        }
+    }
+}
+
+const GROUPS: &[(&str, &[&str])] = &[
+    (
+        "mouse",
+        &[
+            "MouseMove",
+            "MouseClick",
+            "MouseDoubleClick",
+            "MousePress",
+            "MouseDown",
+            "MouseUp",
+            "MouseDrag",
+            "MouseScroll",
+        ],
+    ),
+    ("keyboard", &["KeyPress", "KeyDown", "KeyUp", "KeySequence", "TypeText", "Hotkey"]),
+    ("wait", &["Delay"]),
+    (
+        "screen",
+        &[
+            "WaitForPixel",
+            "WaitForScreenStable",
+            "WaitForChange",
+            "WaitForText",
+            "ClickText",
+            "WaitForImage",
+            "ClickImage",
+        ],
+    ),
+    (
+        "window",
+        &["FocusWindow", "MoveWindow", "ResizeWindow", "MinimizeWindow", "CloseWindow", "WaitForWindow"],
+    ),
+    ("element", &["WaitForElement", "ClickElement", "SetElementText"]),
+];
+
+impl Permissions {
+    /// 检查动作树中的每个动作是否允许执行
+    pub fn check(&self, action: &InputAction) -> Result<(), AuthError> {
+        // 光标位置未知，MouseDown、MouseUp、MouseScroll 之前需要先移动到允许的区域
+        self.visit(action, &mut None)
+    }
+
+    /// 检查是否允许截图和读取窗口信息
+    pub fn check_capture(&self) -> Result<(), AuthError> {
+        scope(self.capture, "screen capture")
+    }
+
+    /// 检查是否允许使用任务队列
+    pub fn check_jobs(&self) -> Result<(), AuthError> {
+        scope(self.jobs, "the job queue")
+    }
+
+    /// 检查是否允许订阅实时事件
+    pub fn check_events(&self) -> Result<(), AuthError> {
+        scope(self.events, "subscribing to events")
+    }
+
+    /// 检查是否允许管理操作
+    pub fn check_admin(&self) -> Result<(), AuthError> {
+        scope(self.admin, "this administrative operation")
+    }
+
+    /// 规则中的名称都能识别时返回 Ok
+    fn check_rules(&self) -> Result<(), String> {
+        check_rules(self.allow.iter().chain(&self.deny))
+    }
+
+    fn allows(&self, name: &str) -> bool {
+        let matches = |rule: &String| rule_matches(rule, name);
+        !self.deny.iter().any(matches) && self.allow.iter().any(matches)
+    }
+
+    fn visit(&self, action: &InputAction, cursor: &mut Option<(i32, i32)>) -> Result<(), AuthError> {
+        match action {
+            InputAction::Sequence { actions } => {
+                return actions.iter().try_for_each(|action| self.visit(action, cursor));
+            }
+            InputAction::Parallel { actions } => {
+                // 只有移动了光标的分支都停在同一位置时（例如只有一个分支移动光标）才能继续确定位置
+                let mut moved = Vec::new();
+                for action in actions {
+                    let mut end = *cursor;
+                    self.visit(action, &mut end)?;
+                    if end != *cursor && !moved.contains(&end) {
+                        moved.push(end);
+                    }
+                }
+                match moved.as_slice() {
+                    [] => {}
+                    [end] => *cursor = *end,
+                    _ => *cursor = None,
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        let name = action.name();
+        if !self.allows(name) {
+            return Err(AuthError::Forbidden(format!("{} is not permitted", name)));
+        }
+        if self.regions.is_empty() {
+            return Ok(());
+        }
+        match action {
+            InputAction::MouseMove { x, y, origin, .. }
+            | InputAction::MouseClick { x, y, origin, .. }
+            | InputAction::MouseDoubleClick { x, y, origin, .. }
+            | InputAction::MousePress { x, y, origin, .. } => {
+                self.check_origin(name, origin.as_ref())?;
+                *cursor = Some(self.check_point(name, *x, *y)?);
+            }
+            InputAction::MouseDrag { start_x, start_y, end_x, end_y, origin, .. } => {
+                self.check_origin(name, origin.as_ref())?;
+                // 区域是矩形，起点和终点在同一个区域内时整条拖动路径都在区域内
+                let inside = self.regions.iter().any(|region| {
+                    contains(region, *start_x, *start_y) && contains(region, *end_x, *end_y)
+                });
+                if !inside {
+                    return Err(AuthError::Forbidden(format!(
+                        "{} from ({}, {}) to ({}, {}) leaves the allowed regions",
+                        name, start_x, start_y, end_x, end_y
+                    )));
+                }
+                *cursor = Some((*end_x, *end_y));
+            }
+            InputAction::MouseDown { .. } | InputAction::MouseUp { .. } | InputAction::MouseScroll { .. } => {
+                let Some((x, y)) = *cursor else {
+                    return Err(AuthError::Forbidden(format!(
+                        "{} at an unknown cursor position; move the mouse into an allowed region first",
+                        name
+                    )));
+                };
+                self.check_point(name, x, y)?;
+            }
+            InputAction::ClickText { .. } | InputAction::ClickImage { .. } | InputAction::ClickElement { .. } => {
+                return Err(AuthError::Forbidden(format!(
+                    "{} clicks at a position found at run time, which cannot be checked against the allowed regions",
+                    name
+                )));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn check_origin(&self, name: &str, origin: Option<&Origin>) -> Result<(), AuthError> {
+        match origin {
+            None | Some(Origin::Screen) => Ok(()),
+            Some(_) => Err(AuthError::Forbidden(format!(
+                "{} uses relative coordinates, which cannot be checked against the allowed regions",
+                name
+            ))),
+        }
+    }
+
+    fn check_point(&self, name: &str, x: i32, y: i32) -> Result<(i32, i32), AuthError> {
+        if self.regions.iter().any(|region| contains(region, x, y)) {
+            Ok((x, y))
+        } else {
+            Err(AuthError::Forbidden(format!(
+                "{} at ({}, {}) is outside the allowed regions",
+                name, x, y
+            )))
+        }
+    }
+}
+
+fn scope(allowed: bool, what: &str) -> Result<(), AuthError> {
+    if allowed {
+        Ok(())
+    } else {
+        Err(AuthError::Forbidden(format!("{} is not permitted", what)))
+    }
+}
+
+/// 规则（"*"、动作名称或分组名称）中的名称都能识别时返回 Ok
+pub(crate) fn check_rules<'a>(rules: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    for rule in rules {
+        if rule != "*" && !GROUPS.iter().any(|(group, names)| group == rule || names.contains(&rule.as_str())) {
+            return Err(format!("unknown action or group {:?}", rule));
+        }
+    }
+    Ok(())
+}
+
+/// 规则是否匹配动作名称
+pub(crate) fn rule_matches(rule: &str, name: &str) -> bool {
+    rule == "*"
+        || rule == name
+        || GROUPS
+            .iter()
+            .any(|(group, names)| *group == rule && names.contains(&name))
+}
+
+fn contains(region: &Rect, x: i32, y: i32) -> bool {
+    x >= region.x
+        && y >= region.y
+        && (x as i64) < region.x as i64 + region.width as i64
+        && (y as i64) < region.y as i64 + region.height as i64
+}
+
+/// 认证、授权和限流错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    /// 没有提供凭据或凭据无效
+    Unauthorized(String),
+    /// 凭据有效但没有权限
+    Forbidden(String),
+    /// 请求过于频繁，retry_after 后可以重试
+    RateLimited { retry_after: Duration },
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Unauthorized(message) => write!(f, "unauthorized: {}", message),
+            AuthError::Forbidden(message) => write!(f, "forbidden: {}", message),
+            AuthError::RateLimited { retry_after } => {
+                write!(f, "rate limit exceeded, retry after {}ms", retry_after.as_millis())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// 通过认证的调用方
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// token 或客户端证书的名称；未启用认证时为对端地址
+    pub name: String,
+    pub permissions: Arc<Permissions>,
+    rate_limit: Option<RateLimit>,
+}
+
+impl Principal {
+    /// 未启用认证时的调用方，拥有全部权限
+    pub fn anonymous(name: impl Into<String>) -> Self {
+        Principal {
+            name: name.into(),
+            permissions: Arc::new(Permissions::default()),
+            rate_limit: None,
+        }
+    }
+
+    /// 是否可以查看和取消 owner 提交的任务（admin 可以查看所有任务）
+    pub fn owns(&self, owner: Option<&str>) -> bool {
+        self.permissions.admin || owner == Some(self.name.as_str())
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 按配置识别调用方并限流，HTTP 和 gRPC 接口共用
+pub struct Authenticator {
+    enabled: bool,
+    tokens: Vec<(String, Principal)>,
+    clients: HashMap<String, Principal>,
+    default_limit: Option<RateLimit>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl Authenticator {
+    /// 检查配置并创建，配置有误时返回 InvalidInput
+    pub fn new(config: &AuthConfig) -> std::io::Result<Self> {
+        let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        let positive = |limit: Option<RateLimit>| {
+            limit.is_none_or(|limit| limit.requests_per_second > 0.0 && limit.burst > 0)
+        };
+        // 未启用认证时按对端地址使用默认限流
+        if !positive(config.rate_limit) {
+            return Err(invalid("auth: rate_limit must be positive".to_string()));
+        }
+        let principal = |name: &str, permissions: &Permissions, rate_limit: Option<RateLimit>| {
+            permissions.check_rules().map_err(|err| invalid(format!("auth {:?}: {}", name, err)))?;
+            if !positive(rate_limit) {
+                return Err(invalid(format!("auth {:?}: rate_limit must be positive", name)));
+            }
+            Ok(Principal {
+                name: name.to_string(),
+                permissions: Arc::new(permissions.clone()),
+                rate_limit: rate_limit.or(config.rate_limit),
+            })
+        };
+
+        let mut tokens = Vec::new();
+        for token in &config.tokens {
+            if token.token.len() < 16 {
+                return Err(invalid(format!("auth token {:?} must be at least 16 characters", token.name)));
+            }
+            if tokens.iter().any(|(existing, _)| existing == &token.token) {
+                return Err(invalid(format!("auth token {:?} is configured twice", token.name)));
+            }
+            tokens.push((token.token.clone(), principal(&token.name, &token.permissions, token.rate_limit)?));
+        }
+        let mut clients = HashMap::new();
+        for client in &config.clients {
+            let value = principal(&client.common_name, &client.permissions, client.rate_limit)?;
+            if clients.insert(client.common_name.clone(), value).is_some() {
+                return Err(invalid(format!("auth client {:?} is configured twice", client.common_name)));
+            }
+        }
+        Ok(Authenticator {
+            enabled: config.is_enabled(),
+            tokens,
+            clients,
+            default_limit: config.rate_limit,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 是否要求认证
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 按客户端证书的 Common Name 或 bearer token 识别调用方；
+    /// 未启用认证时返回以对端地址命名、拥有全部权限的调用方
+    pub fn authenticate(
+        &self,
+        token: Option<&str>,
+        common_name: Option<&str>,
+        peer: Option<IpAddr>,
+    ) -> Result<Principal, AuthError> {
+        if !self.enabled {
+            let name = peer.map_or_else(|| "anonymous".to_string(), |peer| peer.to_string());
+            return Ok(Principal {
+                rate_limit: self.default_limit,
+                ..Principal::anonymous(name)
+            });
+        }
+        if let Some(principal) = common_name.and_then(|name| self.clients.get(name)) {
+            return Ok(principal.clone());
+        }
+        let Some(token) = token else {
+            return Err(AuthError::Unauthorized("missing bearer token or client certificate".to_string()));
+        };
+        // 比较所有 token 且不提前返回，避免通过响应时间猜测 token
+        let mut found = None;
+        for (expected, principal) in &self.tokens {
+            if constant_time_eq(expected.as_bytes(), token.as_bytes()) {
+                found = Some(principal);
+            }
+        }
+        found
+            .cloned()
+            .ok_or_else(|| AuthError::Unauthorized("invalid bearer token".to_string()))
+    }
+
+    /// 消耗调用方的一次请求额度
+    pub fn throttle(&self, principal: &Principal) -> Result<(), AuthError> {
+        let Some(limit) = principal.rate_limit else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // 未启用认证时按对端地址限流，定期丢弃已经回满的桶以免无限增长
+        if buckets.len() > 4096 {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * limit.requests_per_second
+                    < limit.burst as f64
+            });
+        }
+        let bucket = buckets.entry(principal.name.clone()).or_insert(Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.requests_per_second).min(limit.burst as f64);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(AuthError::RateLimited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / limit.requests_per_second),
+            })
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn action(value: serde_json::Value) -> InputAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn forbidden(result: Result<(), AuthError>) -> bool {
+        matches!(result, Err(AuthError::Forbidden(_)))
+    }
+
+    fn token(name: &str, token: &str, permissions: Permissions) -> TokenConfig {
+        TokenConfig {
+            name: name.to_string(),
+            token: token.to_string(),
+            permissions,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn allow_and_deny_rules_apply_to_every_nested_action() {
+        let permissions = Permissions {
+            allow: vec!["mouse".into(), "Delay".into()],
+            deny: vec!["MouseDrag".into()],
+            ..Permissions::default()
+        };
+        let click = json!({"type": "MouseClick", "params": {"button": "Left", "x": 1, "y": 1}});
+        let typing = json!({"type": "TypeText", "params": {"text": "hi"}});
+        let drag = json!({"type": "MouseDrag", "params": {
+            "button": "Left", "start_x": 0, "start_y": 0, "end_x": 5, "end_y": 5, "duration_ms": 100
+        }});
+        assert!(permissions.check(&action(click.clone())).is_ok());
+        assert!(forbidden(permissions.check(&action(typing.clone()))));
+        assert!(forbidden(permissions.check(&action(drag))));
+        assert!(forbidden(permissions.check(&action(json!({"type": "Sequence", "params": {"actions": [
+            click,
+            {"type": "Parallel", "params": {"actions": [typing]}}
+        ]}})))));
+        assert!(permissions.check_rules().is_ok());
+        assert!(Permissions { allow: vec!["mice".into()], ..Permissions::default() }.check_rules().is_err());
+    }
+
+    #[test]
+    fn regions_require_known_absolute_positions() {
+        let permissions = Permissions {
+            regions: vec![Rect::new(0, 0, 100, 100)],
+            ..Permissions::default()
+        };
+        let check = |value| permissions.check(&action(value));
+        assert!(check(json!({"type": "MouseClick", "params": {"button": "Left", "x": 99, "y": 0}})).is_ok());
+        assert!(forbidden(check(json!({"type": "MouseClick", "params": {"button": "Left", "x": 100, "y": 0}}))));
+        assert!(forbidden(check(json!({"type": "MouseDoubleClick", "params": {
+            "button": "Left", "x": 1, "y": 1, "origin": {"Anchor": "ok"}
+        }}))));
+        assert!(forbidden(check(json!({"type": "ClickImage", "params": {
+            "image": {"path": "ok.png"}, "threshold": 0.9, "button": "Left", "timeout_ms": 1000
+        }}))));
+
+        // 按下按键前需要先把光标移到区域内
+        let down = json!({"type": "MouseDown", "params": {"button": "Left"}});
+        assert!(forbidden(check(down.clone())));
+        assert!(check(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseMove", "params": {"x": 10, "y": 10}},
+            {"type": "Parallel", "params": {"actions": [
+                {"type": "Sequence", "params": {"actions": [down.clone()]}},
+                {"type": "KeyPress", "params": {"key": "Shift"}}
+            ]}}
+        ]}})).is_ok());
+        // 两个分支把光标移到不同位置后位置未知
+        assert!(forbidden(check(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "Parallel", "params": {"actions": [
+                {"type": "MouseMove", "params": {"x": 10, "y": 10}},
+                {"type": "MouseMove", "params": {"x": 20, "y": 20}}
+            ]}},
+            down
+        ]}}))));
+    }
+
+    #[test]
+    fn scopes_and_job_ownership() {
+        let full = Permissions::default();
+        assert!(full.check_capture().is_ok() && full.check_jobs().is_ok());
+        assert!(full.check_events().is_ok() && full.check_admin().is_ok());
+
+        let restricted: Permissions =
+            serde_json::from_value(json!({"capture": false, "jobs": false, "events": false, "admin": false})).unwrap();
+        assert_eq!(restricted.allow, vec!["*".to_string()]);
+        assert!(forbidden(restricted.check_capture()));
+        assert!(forbidden(restricted.check_jobs()));
+        assert!(forbidden(restricted.check_events()));
+        assert!(forbidden(restricted.check_admin()));
+
+        let user = Principal {
+            permissions: Arc::new(Permissions { admin: false, ..Permissions::default() }),
+            ..Principal::anonymous("ci")
+        };
+        assert!(user.owns(Some("ci")));
+        assert!(!user.owns(Some("other")));
+        assert!(!user.owns(None));
+        assert!(Principal::anonymous("admin").owns(Some("ci")));
+    }
+
+    #[test]
+    fn authenticates_tokens() {
+        let config = AuthConfig {
+            tokens: vec![
+                token("ci", "ci-token-0123456789", Permissions::default()),
+                token("kiosk", "kiosk-token-0123456789", Permissions { capture: false, ..Permissions::default() }),
+            ],
+            ..AuthConfig::default()
+        };
+        let auth = Authenticator::new(&config).unwrap();
+        assert!(auth.is_enabled());
+        let kiosk = auth.authenticate(Some("kiosk-token-0123456789"), None, None).unwrap();
+        assert_eq!(kiosk.name, "kiosk");
+        assert!(!kiosk.permissions.capture);
+        assert!(matches!(auth.authenticate(Some("ci-token"), None, None), Err(AuthError::Unauthorized(_))));
+        assert!(matches!(auth.authenticate(None, Some("robot"), None), Err(AuthError::Unauthorized(_))));
+
+        let short = AuthConfig { tokens: vec![token("short", "short", Permissions::default())], ..AuthConfig::default() };
+        assert!(Authenticator::new(&short).is_err());
+        let twice = AuthConfig {
+            tokens: vec![config.tokens[0].clone(), token("again", "ci-token-0123456789", Permissions::default())],
+            ..AuthConfig::default()
+        };
+        assert!(Authenticator::new(&twice).is_err());
+
+        let open = Authenticator::new(&AuthConfig::default()).unwrap();
+        let anonymous = open.authenticate(None, None, Some("10.0.0.1".parse().unwrap())).unwrap();
+        assert_eq!(anonymous.name, "10.0.0.1");
+        assert!(anonymous.permissions.admin);
+    }
+
+    #[test]
+    fn throttles_each_principal_separately() {
+        let limit = RateLimit { requests_per_second: 1.0, burst: 2 };
+        let auth = Authenticator::new(&AuthConfig { rate_limit: Some(limit), ..AuthConfig::default() }).unwrap();
+        let first = auth.authenticate(None, None, Some("10.0.0.1".parse().unwrap())).unwrap();
+        let second = auth.authenticate(None, None, Some("10.0.0.2".parse().unwrap())).unwrap();
+        assert!(auth.throttle(&first).is_ok());
+        assert!(auth.throttle(&first).is_ok());
+        match auth.throttle(&first) {
+            Err(AuthError::RateLimited { retry_after }) => {
+                assert!(retry_after > Duration::from_millis(900) && retry_after <= Duration::from_secs(1));
+            }
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+        assert!(auth.throttle(&second).is_ok());
+        assert!(auth.throttle(&Principal::anonymous("unlimited")).is_ok());
+
+        let invalid = RateLimit { requests_per_second: 0.0, burst: 1 };
+        assert!(Authenticator::new(&AuthConfig { rate_limit: Some(invalid), ..AuthConfig::default() }).is_err());
+    }
+}
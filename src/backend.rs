@@ -0,0 +1,309 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, InputError, InputResult, Key as EnigoKey, Keyboard, Mouse};
+use image::RgbaImage;
+use tokio::time::{sleep, Duration};
+
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::error::AutomationError;
+use crate::screen::Rect;
+use crate::window::{WindowCommand, WindowEvent, WindowInfo, WindowSelector};
+
+/// 输入后端：模拟器通过它发出底层事件、等待和观察屏幕
+///
+/// `Enigo` 直接注入到系统，`DryRunBackend` 只记录事件时间线
+pub trait InputBackend: Keyboard + Mouse {
+    /// 等待指定时长
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send;
+
+    /// 键盘鼠标动作开始前调用，后端可在此等待以保证动作之间的最小间隔
+    fn begin_input(&mut self) -> impl Future<Output = ()> + Send {
+        std::future::ready(())
+    }
+
+    /// 键盘鼠标动作结束后调用
+    fn end_input(&mut self) {}
+
+    /// 之后的等待从当前时刻重新计时，在耗时不可预计的操作（如等待确认）之后调用
+    fn reset_timeline(&mut self) {}
+
+    /// 截取屏幕区域；返回 None 表示后端无法观察屏幕（如演练模式），等待类动作视为立即满足
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError>;
+
+    /// 列出顶层窗口（最上层在前）；返回 None 表示后端无法观察窗口
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError>;
+
+    /// 对满足条件的最上层窗口执行操作，返回被操作的窗口；返回 None 表示后端只记录不执行
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError>;
+
+    /// 将事件直接发送给指定窗口，不经过系统输入队列（不改变焦点和真实光标）
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError>;
+
+    /// 在无障碍树中查找元素；返回 None 表示后端无法观察无障碍树
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send;
+
+    /// 对满足条件的第一个元素执行操作，返回被操作的元素；返回 None 表示后端只记录不执行
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send;
+}
+
+impl InputBackend for Enigo {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        sleep(duration)
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        send_window_event(window, event)
+    }
+
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        element_command(selector, command)
+    }
+}
+
+/// 多个调用方共享的输入设备：每个事件单独加锁，执行过程中查询光标、释放按住的输入等操作不会被阻塞
+#[derive(Clone, Default)]
+pub struct SharedDevice {
+    device: Arc<Mutex<Option<Enigo>>>,
+}
+
+impl SharedDevice {
+    /// 共享已连接的输入设备；Default 创建的实例没有设备
+    pub fn new(enigo: Enigo) -> Self {
+        SharedDevice {
+            device: Arc::new(Mutex::new(Some(enigo))),
+        }
+    }
+
+    /// 是否连接了输入设备
+    pub fn is_connected(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// 加锁后使用设备，没有设备时返回 None
+    pub fn with<R>(&self, f: impl FnOnce(&mut Enigo) -> R) -> Option<R> {
+        self.lock().as_mut().map(f)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Enigo>> {
+        self.device.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn input<R>(&self, f: impl FnOnce(&mut Enigo) -> InputResult<R>) -> InputResult<R> {
+        self.with(f).unwrap_or(Err(InputError::Simulate("no input device")))
+    }
+}
+
+impl Keyboard for SharedDevice {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.input(|enigo| enigo.fast_text(text))
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.input(|enigo| enigo.key(key, direction))
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.input(|enigo| enigo.raw(keycode, direction))
+    }
+}
+
+impl Mouse for SharedDevice {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.input(|enigo| enigo.button(button, direction))
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.input(|enigo| enigo.move_mouse(x, y, coordinate))
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.input(|enigo| enigo.scroll(length, axis))
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        self.input(|enigo| enigo.main_display())
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.input(|enigo| enigo.location())
+    }
+}
+
+impl InputBackend for SharedDevice {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        sleep(duration)
+    }
+
+    // 截图、窗口和无障碍操作不使用输入设备，不需要加锁
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        send_window_event(window, event)
+    }
+
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        element_command(selector, command)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn capture(rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+    crate::screen::capture_region(rect).map(Some)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture(_rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+    Err(AutomationError::Platform(
+        "screen capture is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn windows() -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+    crate::window::list_windows().map(Some)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn windows() -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+    Err(AutomationError::Platform(
+        "window management is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn window_command(selector: &WindowSelector, command: WindowCommand) -> Result<Option<WindowInfo>, AutomationError> {
+    crate::window::WindowManager::connect(None)?
+        .apply_to(selector, command)
+        .map(Some)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn window_command(_selector: &WindowSelector, _command: WindowCommand) -> Result<Option<WindowInfo>, AutomationError> {
+    Err(AutomationError::Platform(
+        "window management is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn send_window_event(window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+    crate::window::send_window_event(window, event)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_window_event(_window: u32, _event: WindowEvent) -> Result<(), AutomationError> {
+    Err(AutomationError::Platform(
+        "sending events to windows is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(all(target_os = "linux", feature = "accessibility"))]
+fn find_elements(
+    selector: &ElementSelector,
+) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send + 'static {
+    let selector = selector.clone();
+    async move { crate::accessibility::find_elements(&selector).await.map(Some) }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "accessibility")))]
+fn find_elements(
+    _selector: &ElementSelector,
+) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send + 'static {
+    std::future::ready(Err(accessibility_unsupported()))
+}
+
+#[cfg(all(target_os = "linux", feature = "accessibility"))]
+fn element_command(
+    selector: &ElementSelector,
+    command: ElementCommand,
+) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send + 'static {
+    let selector = selector.clone();
+    async move {
+        match command {
+            ElementCommand::Click => crate::accessibility::click_element(&selector).await,
+            ElementCommand::SetText { text } => {
+                crate::accessibility::set_element_text(&selector, &text).await
+            }
+        }
+        .map(Some)
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "accessibility")))]
+fn element_command(
+    _selector: &ElementSelector,
+    _command: ElementCommand,
+) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send + 'static {
+    std::future::ready(Err(accessibility_unsupported()))
+}
+
+#[cfg(not(all(target_os = "linux", feature = "accessibility")))]
+fn accessibility_unsupported() -> AutomationError {
+    AutomationError::Platform(
+        "accessibility automation requires the `accessibility` feature on Linux".to_string(),
+    )
+}
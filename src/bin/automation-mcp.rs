@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use automation::executor::ActionExecutor;
+use automation::mcp;
+use automation::validator::ValidationOptions;
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// 用法：automation-mcp [--http 127.0.0.1:8090]
+///
+/// 不指定 --http 时通过标准输入输出通信，日志写到标准错误
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    let executor = Arc::new(ActionExecutor::new().with_validation(ValidationOptions::default()));
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (None, _) => mcp::serve_stdio(executor).await,
+        (Some("--http"), Some(bind)) => {
+            let listener = TcpListener::bind(&bind).await?;
+            info!("MCP endpoint listening on http://{}/mcp", bind);
+            mcp::serve_http(listener, executor).await
+        }
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "usage: automation-mcp [--http ADDR]",
+        )),
+    }
+}
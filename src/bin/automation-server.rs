@@ -0,0 +1,12 @@
+use std::path::PathBuf;
+
+use automation::server::{self, ServerConfig};
+
+/// 用法：automation-server [config.toml]
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config = ServerConfig::load(std::env::args_os().nth(1).map(PathBuf::from))?;
+    server::run(config).await
+}
@@ -0,0 +1,451 @@
+use std::future::{ready, Future};
+use std::sync::{Arc, Mutex};
+
+use enigo::{Axis, Button, Coordinate, Direction, InputResult, Key as EnigoKey, Keyboard, Mouse};
+use image::RgbaImage;
+use serde::Serialize;
+use tokio::time::Duration;
+
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::backend::InputBackend;
+use crate::error::AutomationError;
+use crate::held::HeldInputs;
+use crate::screen::Rect;
+use crate::window::{WindowCommand, WindowEvent, WindowInfo, WindowSelector};
+
+/// 演练模式下记录的底层输入事件
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", content = "params")]
+pub enum InputEvent {
+    MouseMove {
+        x: i32,
+        y: i32,
+        coordinate: Coordinate,
+    },
+    MouseButton {
+        button: Button,
+        direction: Direction,
+    },
+    MouseScroll {
+        length: i32,
+        axis: Axis,
+    },
+    Key {
+        key: EnigoKey,
+        direction: Direction,
+    },
+    RawKey {
+        keycode: u16,
+        direction: Direction,
+    },
+    Text {
+        text: String,
+    },
+    Wait {
+        milliseconds: u64,
+    },
+    /// 观察屏幕区域（演练时不会真正截图，未假定屏幕内容时等待条件视为立即满足）
+    Capture {
+        region: Rect,
+    },
+    /// 对窗口的操作（演练时不会真正执行）
+    Window {
+        window: WindowSelector,
+        command: WindowCommand,
+    },
+    /// 直接发送给窗口的事件
+    WindowEvent {
+        window: u32,
+        event: WindowEvent,
+    },
+    /// 对无障碍元素的操作（演练时不会真正执行）
+    Element {
+        element: ElementSelector,
+        command: ElementCommand,
+    },
+}
+
+impl InputEvent {
+    /// 将键盘鼠标事件发送到另一个后端；等待事件由调用方按时间线处理，不会重复等待
+    pub fn replay<B: InputBackend>(&self, backend: &mut B) -> Result<(), AutomationError> {
+        match self {
+            InputEvent::MouseMove { x, y, coordinate } => {
+                let _ = backend.move_mouse(*x, *y, *coordinate);
+            }
+            InputEvent::MouseButton { button, direction } => {
+                let _ = backend.button(*button, *direction);
+            }
+            InputEvent::MouseScroll { length, axis } => {
+                let _ = backend.scroll(*length, *axis);
+            }
+            InputEvent::Key { key, direction } => {
+                let _ = backend.key(*key, *direction);
+            }
+            InputEvent::RawKey { keycode, direction } => {
+                let _ = backend.raw(*keycode, *direction);
+            }
+            InputEvent::Text { text } => {
+                let _ = backend.text(text);
+            }
+            InputEvent::Wait { .. } => {}
+            InputEvent::WindowEvent { window, event } => backend.send_window_event(*window, *event)?,
+            InputEvent::Capture { .. } | InputEvent::Window { .. } | InputEvent::Element { .. } => {
+                return Err(AutomationError::Platform(format!("{:?} cannot be replayed", self)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 带时间偏移的事件
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TimedEvent {
+    /// 相对于脚本开始的偏移（毫秒）
+    pub at_ms: u64,
+    pub event: InputEvent,
+}
+
+/// 演练结果
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DryRunReport {
+    /// 模拟器返回的执行结果描述
+    pub result: String,
+    /// 按时间排列的底层事件
+    pub events: Vec<TimedEvent>,
+    /// 预计总耗时（毫秒）
+    pub estimated_duration_ms: u64,
+}
+
+/// 演练后端：不触碰真实设备，只记录事件时间线
+pub struct DryRunBackend {
+    screen_size: (i32, i32),
+    cursor: (i32, i32),
+    elapsed: Duration,
+    events: Vec<TimedEvent>,
+    min_action_gap: Duration,
+    /// 上一个键盘鼠标动作结束的时间
+    last_input: Option<Duration>,
+    /// 记录按住的输入，供执行策略检查组合键
+    held: Option<Arc<Mutex<HeldInputs>>>,
+    /// 假定存在的窗口，为空时无法观察窗口
+    windows: Option<Vec<WindowInfo>>,
+    /// 假定的屏幕内容，为空时无法观察屏幕
+    screen: Option<RgbaImage>,
+}
+
+impl DryRunBackend {
+    /// 创建演练后端，光标初始位置为屏幕原点
+    pub fn new(screen_size: (u32, u32)) -> Self {
+        DryRunBackend {
+            screen_size: (screen_size.0 as i32, screen_size.1 as i32),
+            cursor: (0, 0),
+            elapsed: Duration::ZERO,
+            events: Vec::new(),
+            min_action_gap: Duration::ZERO,
+            last_input: None,
+            held: None,
+            windows: None,
+            screen: None,
+        }
+    }
+
+    /// 指定光标的初始位置
+    pub fn with_cursor(mut self, cursor: (i32, i32)) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// 假定存在这些窗口，指定目标窗口的动作按窗口发送事件而不是假定目标存在
+    pub fn with_windows(mut self, windows: Vec<WindowInfo>) -> Self {
+        self.windows = Some(windows);
+        self
+    }
+
+    /// 假定屏幕内容为 screen，屏幕等待条件按截图判断而不是视为立即满足
+    pub fn with_screen(mut self, screen: RgbaImage) -> Self {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// 在 held 中记录按下和释放的键与鼠标按键
+    pub fn with_held(mut self, held: Arc<Mutex<HeldInputs>>) -> Self {
+        self.held = Some(held);
+        self
+    }
+
+    /// 相邻键盘鼠标动作之间至少间隔 gap（用于生成 Parallel 分支的时间线）
+    pub fn with_min_action_gap(mut self, gap: Duration) -> Self {
+        self.min_action_gap = gap;
+        self
+    }
+
+    /// 已记录的事件
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// 生成演练结果
+    pub fn into_report(self, result: String) -> DryRunReport {
+        DryRunReport {
+            result,
+            events: self.events,
+            estimated_duration_ms: self.elapsed.as_millis() as u64,
+        }
+    }
+
+    fn advance(&mut self, duration: Duration) {
+        self.record(InputEvent::Wait {
+            milliseconds: duration.as_millis() as u64,
+        });
+        self.elapsed += duration;
+    }
+
+    fn hold(&self, update: impl FnOnce(&mut HeldInputs)) {
+        if let Some(held) = &self.held {
+            update(&mut held.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        }
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        self.events.push(TimedEvent {
+            at_ms: self.elapsed.as_millis() as u64,
+            event,
+        });
+    }
+}
+
+impl Keyboard for DryRunBackend {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.record(InputEvent::Text {
+            text: text.to_string(),
+        });
+        Ok(Some(()))
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.record(InputEvent::Key { key, direction });
+        self.hold(|held| HeldInputs::update(&mut held.keys, key, direction));
+        Ok(())
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.record(InputEvent::RawKey { keycode, direction });
+        self.hold(|held| HeldInputs::update(&mut held.raw_keys, keycode, direction));
+        Ok(())
+    }
+}
+
+impl Mouse for DryRunBackend {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.record(InputEvent::MouseButton { button, direction });
+        self.hold(|held| HeldInputs::update(&mut held.buttons, button, direction));
+        Ok(())
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        self.record(InputEvent::MouseMove { x, y, coordinate });
+        Ok(())
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.record(InputEvent::MouseScroll { length, axis });
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Ok(self.screen_size)
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok(self.cursor)
+    }
+}
+
+impl InputBackend for DryRunBackend {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        self.advance(duration);
+        ready(())
+    }
+
+    fn begin_input(&mut self) -> impl Future<Output = ()> + Send {
+        if let Some(last_input) = self.last_input {
+            let remaining = self.min_action_gap.saturating_sub(self.elapsed - last_input);
+            if !remaining.is_zero() {
+                self.advance(remaining);
+            }
+        }
+        ready(())
+    }
+
+    fn end_input(&mut self) {
+        self.last_input = Some(self.elapsed);
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        self.record(InputEvent::Capture { region: rect });
+        let Some(screen) = &self.screen else {
+            return Ok(None);
+        };
+        let bounds = Rect::new(0, 0, screen.width(), screen.height());
+        if bounds.intersect(&rect) != Some(rect) {
+            return Err(AutomationError::Platform(format!("region {:?} is outside the screen", rect)));
+        }
+        Ok(Some(
+            image::imageops::crop_imm(screen, rect.x as u32, rect.y as u32, rect.width, rect.height).to_image(),
+        ))
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        Ok(self.windows.clone())
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        self.record(InputEvent::Window {
+            window: selector.clone(),
+            command,
+        });
+        Ok(None)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        self.record(InputEvent::WindowEvent { window, event });
+        Ok(())
+    }
+
+    fn find_elements(
+        &mut self,
+        _selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        ready(Ok(None))
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        self.record(InputEvent::Element {
+            element: selector.clone(),
+            command,
+        });
+        ready(Ok(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use enigo::{Button, Coordinate, Direction, Key as EnigoKey, Mouse};
+    use serde_json::json;
+
+    use super::*;
+    use crate::enums::InputAction;
+    use crate::simulator::InputSimulator;
+    use crate::timing::TimingPolicy;
+
+    fn action(value: serde_json::Value) -> InputAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn at(at_ms: u64, event: InputEvent) -> TimedEvent {
+        TimedEvent { at_ms, event }
+    }
+
+    #[tokio::test]
+    async fn backend_tracks_cursor_and_elapsed_time() {
+        let mut backend = DryRunBackend::new((800, 600));
+        backend.move_mouse(100, 50, Coordinate::Abs).unwrap();
+        backend.move_mouse(-10, 5, Coordinate::Rel).unwrap();
+        assert_eq!(backend.location().unwrap(), (90, 55));
+        assert_eq!(backend.main_display().unwrap(), (800, 600));
+
+        backend.wait(Duration::from_millis(30)).await;
+        backend.button(Button::Left, Direction::Click).unwrap();
+        assert_eq!(backend.events().last().unwrap().at_ms, 30);
+        assert_eq!(backend.into_report(String::new()).estimated_duration_ms, 30);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_timeline_and_duration() {
+        let simulator = InputSimulator::detached((1920, 1080));
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseClick", "params": {"button": "Left", "x": 10, "y": 20}},
+            {"type": "Delay", "params": {"milliseconds": 100}},
+            {"type": "TypeText", "params": {"text": "ab", "char_delay_ms": 10}}
+        ]}}));
+        let report = simulator.dry_run(&script).await.unwrap();
+        assert_eq!(
+            report.events,
+            vec![
+                at(0, InputEvent::MouseMove { x: 10, y: 20, coordinate: Coordinate::Abs }),
+                at(0, InputEvent::MouseButton { button: Button::Left, direction: Direction::Click }),
+                at(0, InputEvent::Wait { milliseconds: 100 }),
+                at(100, InputEvent::Key { key: EnigoKey::Unicode('a'), direction: Direction::Click }),
+                at(100, InputEvent::Wait { milliseconds: 10 }),
+                at(110, InputEvent::Key { key: EnigoKey::Unicode('b'), direction: Direction::Click }),
+                at(110, InputEvent::Wait { milliseconds: 10 }),
+            ]
+        );
+        assert_eq!(report.estimated_duration_ms, 120);
+    }
+
+    #[tokio::test]
+    async fn dry_run_applies_speed() {
+        let mut simulator = InputSimulator::detached((1920, 1080));
+        simulator.set_timing_policy(TimingPolicy {
+            speed: 2.0,
+            ..TimingPolicy::default()
+        });
+        let script = action(json!({"type": "Delay", "params": {"milliseconds": 300}}));
+        let report = simulator.dry_run(&script).await.unwrap();
+        assert_eq!(report.estimated_duration_ms, 150);
+    }
+
+    #[tokio::test]
+    async fn dry_run_merges_parallel_branches_by_time() {
+        let simulator = InputSimulator::detached((1920, 1080));
+        let script = action(json!({"type": "Parallel", "params": {"actions": [
+            {"type": "Sequence", "params": {"actions": [
+                {"type": "KeyDown", "params": {"key": "Shift"}},
+                {"type": "Delay", "params": {"milliseconds": 50}},
+                {"type": "KeyUp", "params": {"key": "Shift"}}
+            ]}},
+            {"type": "Sequence", "params": {"actions": [
+                {"type": "Delay", "params": {"milliseconds": 20}},
+                {"type": "MouseMove", "params": {"x": 5, "y": 5}},
+                {"type": "Delay", "params": {"milliseconds": 60}}
+            ]}}
+        ]}}));
+        let report = simulator.dry_run(&script).await.unwrap();
+        let inputs: Vec<_> = report
+            .events
+            .iter()
+            .filter(|event| !matches!(event.event, InputEvent::Wait { .. }))
+            .cloned()
+            .collect();
+        assert_eq!(
+            inputs,
+            vec![
+                at(0, InputEvent::Key { key: EnigoKey::Shift, direction: Direction::Press }),
+                at(20, InputEvent::MouseMove { x: 5, y: 5, coordinate: Coordinate::Abs }),
+                at(50, InputEvent::Key { key: EnigoKey::Shift, direction: Direction::Release }),
+            ]
+        );
+        // 总耗时取最长的分支
+        assert_eq!(report.estimated_duration_ms, 80);
+    }
+
+    #[tokio::test]
+    async fn detached_simulator_cannot_execute() {
+        let simulator = InputSimulator::detached((1920, 1080));
+        let script = action(json!({"type": "Delay", "params": {"milliseconds": 1}}));
+        assert!(matches!(simulator.execute_action(&script).await, Err(AutomationError::Platform(_))));
+    }
+}
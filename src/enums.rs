@@ -1,7 +1,13 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::accessibility::ElementSelector;
+use crate::screen::{Color, Rect};
+use crate::vision::ImageSource;
+use crate::window::{InputTarget, WindowSelector};
+
 /// 鼠标按键枚举
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Hash, Eq)]
 pub enum MouseButton {
     Left,
     Right,
@@ -12,7 +18,7 @@ pub enum MouseButton {
 }
 
 /// 键盘按键位置（用于区分左右修饰键）
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum KeyLocation {
     Standard, // 标准位置
     Left,     // 左侧修饰键
@@ -21,7 +27,7 @@ pub enum KeyLocation {
 }
 
 /// 键盘按键枚举（扩展）
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
 pub enum Key {
     Backspace,
     Tab,
@@ -130,30 +136,61 @@ pub enum Key {
     Other(u32), // 其他键
 }
 
+/// 鼠标坐标的原点
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// 屏幕左上角
+    Screen,
+    /// 满足条件的窗口的客户区左上角
+    Window(WindowSelector),
+    /// 之前通过 anchor 保存的查找结果（图片、文字）的中心
+    Anchor(String),
+}
+
 /// 输入操作枚举
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 #[serde(tag = "type", content = "params")]
 pub enum InputAction {
     // 鼠标操作
     MouseMove {
         x: i32,
         y: i32,
+        /// 坐标原点，不指定时为屏幕左上角
+        origin: Option<Origin>,
+        /// 目标窗口，不指定时通过系统输入队列发送给当前焦点窗口
+        target: Option<InputTarget>,
     },
     MouseClick {
         button: MouseButton,
         x: i32,
         y: i32,
+        origin: Option<Origin>,
+        target: Option<InputTarget>,
     },
     MouseDoubleClick {
         button: MouseButton,
         x: i32,
         y: i32,
+        origin: Option<Origin>,
+        target: Option<InputTarget>,
     },
     MousePress {
         button: MouseButton,
         x: i32,
         y: i32,
         duration_ms: u64,
+        origin: Option<Origin>,
+        target: Option<InputTarget>,
+    },
+    /// 在当前光标位置按下鼠标按键（不释放）
+    MouseDown {
+        button: MouseButton,
+        target: Option<InputTarget>,
+    },
+    /// 在当前光标位置释放鼠标按键
+    MouseUp {
+        button: MouseButton,
+        target: Option<InputTarget>,
     },
     MouseDrag {
         button: MouseButton,
@@ -162,49 +199,281 @@ pub enum InputAction {
         end_x: i32,
         end_y: i32,
         duration_ms: u64,
+        origin: Option<Origin>,
+        target: Option<InputTarget>,
     },
     MouseScroll {
         delta_x: i32,
         delta_y: i32,
+        target: Option<InputTarget>,
     },
     
     // 键盘操作
     KeyPress {
         key: Key,
         location: Option<KeyLocation>,
+        target: Option<InputTarget>,
     },
     KeyDown {
         key: Key,
         location: Option<KeyLocation>,
+        target: Option<InputTarget>,
     },
     KeyUp {
         key: Key,
         location: Option<KeyLocation>,
+        target: Option<InputTarget>,
     },
     KeySequence {
         keys: Vec<Key>,
         key_delay_ms: Option<u64>,
+        target: Option<InputTarget>,
     },
     TypeText {
         text: String,
         char_delay_ms: Option<u64>,
+        target: Option<InputTarget>,
     },
     Hotkey {
         modifiers: Vec<Key>,
         key: Key,
         location: Option<KeyLocation>,
+        target: Option<InputTarget>,
     },
     
     // 延时操作
     Delay {
         milliseconds: u64,
     },
+
+    // 屏幕条件
+    /// 轮询指定像素直到颜色匹配（每个通道差值不超过 tolerance），超时则失败
+    WaitForPixel {
+        x: i32,
+        y: i32,
+        color: Color,
+        tolerance: u8,
+        timeout_ms: u64,
+    },
+    /// 等待屏幕（或 region 区域）连续 quiet_ms 毫秒没有变化，用于等待动画或加载结束
+    WaitForScreenStable {
+        region: Option<Rect>,
+        quiet_ms: u64,
+        timeout_ms: u64,
+    },
+    /// 等待屏幕（或 region 区域）相对于开始等待时发生变化
+    WaitForChange {
+        region: Option<Rect>,
+        timeout_ms: u64,
+    },
+    /// 等待屏幕（或 region 区域）上出现指定文字（需要 ocr feature）
+    WaitForText {
+        text: String,
+        region: Option<Rect>,
+        timeout_ms: u64,
+        /// 将找到的位置保存为锚点，供后续鼠标操作的 origin 引用
+        anchor: Option<String>,
+    },
+    /// 等待指定文字出现后点击其中心（需要 ocr feature）
+    ClickText {
+        text: String,
+        region: Option<Rect>,
+        button: MouseButton,
+        timeout_ms: u64,
+        anchor: Option<String>,
+    },
+    /// 等待模板图片出现在屏幕（或 region 区域）上，threshold 为最低匹配得分（0~1）
+    WaitForImage {
+        image: ImageSource,
+        region: Option<Rect>,
+        threshold: f32,
+        timeout_ms: u64,
+        anchor: Option<String>,
+    },
+    /// 等待模板图片出现后点击其中心
+    ClickImage {
+        image: ImageSource,
+        region: Option<Rect>,
+        threshold: f32,
+        button: MouseButton,
+        timeout_ms: u64,
+        anchor: Option<String>,
+    },
+
+    // 窗口管理（window 选中满足条件的最上层窗口）
+    FocusWindow {
+        window: WindowSelector,
+    },
+    /// 将窗口左上角移动到屏幕坐标 (x, y)
+    MoveWindow {
+        window: WindowSelector,
+        x: i32,
+        y: i32,
+    },
+    ResizeWindow {
+        window: WindowSelector,
+        width: u32,
+        height: u32,
+    },
+    MinimizeWindow {
+        window: WindowSelector,
+    },
+    CloseWindow {
+        window: WindowSelector,
+    },
+    /// 等待满足条件的窗口出现
+    WaitForWindow {
+        window: WindowSelector,
+        timeout_ms: u64,
+    },
+
+    // 无障碍元素（需要 accessibility feature，selector 选中遍历顺序中的第一个元素）
+    /// 等待满足条件的元素出现，state 不为空时还需处于该状态（如 "enabled"、"focused"）
+    WaitForElement {
+        selector: ElementSelector,
+        state: Option<String>,
+        timeout_ms: u64,
+        /// 将元素的位置保存为锚点，供后续鼠标操作的 origin 引用
+        anchor: Option<String>,
+    },
+    /// 等待元素出现后执行它的点击动作；元素没有点击动作时用鼠标左键点击其中心
+    ClickElement {
+        selector: ElementSelector,
+        timeout_ms: u64,
+    },
+    /// 等待可编辑元素出现后替换其全部文本
+    SetElementText {
+        selector: ElementSelector,
+        text: String,
+        timeout_ms: u64,
+    },
     
     // 组合操作
     Sequence {
         actions: Vec<InputAction>,
     },
+    /// 同时开始执行各个分支，全部分支结束后完成
+    ///
+    /// 分支中只能包含不指定目标窗口的键盘鼠标操作、Delay 以及由它们组成的 Sequence 和 Parallel
     Parallel {
         actions: Vec<InputAction>,
     },
-}
\ No newline at end of file
+}
+
+impl InputAction {
+    /// 动作类型名称（与 JSON 中的 type 字段一致）
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::MouseMove { .. } => "MouseMove",
+            InputAction::MouseClick { .. } => "MouseClick",
+            InputAction::MouseDoubleClick { .. } => "MouseDoubleClick",
+            InputAction::MousePress { .. } => "MousePress",
+            InputAction::MouseDown { .. } => "MouseDown",
+            InputAction::MouseUp { .. } => "MouseUp",
+            InputAction::MouseDrag { .. } => "MouseDrag",
+            InputAction::MouseScroll { .. } => "MouseScroll",
+            InputAction::KeyPress { .. } => "KeyPress",
+            InputAction::KeyDown { .. } => "KeyDown",
+            InputAction::KeyUp { .. } => "KeyUp",
+            InputAction::KeySequence { .. } => "KeySequence",
+            InputAction::TypeText { .. } => "TypeText",
+            InputAction::Hotkey { .. } => "Hotkey",
+            InputAction::Delay { .. } => "Delay",
+            InputAction::WaitForPixel { .. } => "WaitForPixel",
+            InputAction::WaitForScreenStable { .. } => "WaitForScreenStable",
+            InputAction::WaitForChange { .. } => "WaitForChange",
+            InputAction::WaitForText { .. } => "WaitForText",
+            InputAction::ClickText { .. } => "ClickText",
+            InputAction::WaitForImage { .. } => "WaitForImage",
+            InputAction::ClickImage { .. } => "ClickImage",
+            InputAction::FocusWindow { .. } => "FocusWindow",
+            InputAction::MoveWindow { .. } => "MoveWindow",
+            InputAction::ResizeWindow { .. } => "ResizeWindow",
+            InputAction::MinimizeWindow { .. } => "MinimizeWindow",
+            InputAction::CloseWindow { .. } => "CloseWindow",
+            InputAction::WaitForWindow { .. } => "WaitForWindow",
+            InputAction::WaitForElement { .. } => "WaitForElement",
+            InputAction::ClickElement { .. } => "ClickElement",
+            InputAction::SetElementText { .. } => "SetElementText",
+            InputAction::Sequence { .. } => "Sequence",
+            InputAction::Parallel { .. } => "Parallel",
+        }
+    }
+
+    /// 等待类动作的超时时间
+    pub fn timeout_ms(&self) -> Option<u64> {
+        match self {
+            InputAction::WaitForPixel { timeout_ms, .. }
+            | InputAction::WaitForScreenStable { timeout_ms, .. }
+            | InputAction::WaitForChange { timeout_ms, .. }
+            | InputAction::WaitForText { timeout_ms, .. }
+            | InputAction::ClickText { timeout_ms, .. }
+            | InputAction::WaitForImage { timeout_ms, .. }
+            | InputAction::ClickImage { timeout_ms, .. }
+            | InputAction::WaitForWindow { timeout_ms, .. }
+            | InputAction::WaitForElement { timeout_ms, .. }
+            | InputAction::ClickElement { timeout_ms, .. }
+            | InputAction::SetElementText { timeout_ms, .. } => Some(*timeout_ms),
+            _ => None,
+        }
+    }
+
+    /// 鼠标操作的坐标原点
+    pub fn origin(&self) -> Option<&Origin> {
+        match self {
+            InputAction::MouseMove { origin, .. }
+            | InputAction::MouseClick { origin, .. }
+            | InputAction::MouseDoubleClick { origin, .. }
+            | InputAction::MousePress { origin, .. }
+            | InputAction::MouseDrag { origin, .. } => origin.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// 键盘鼠标操作指定的目标窗口
+    pub fn target(&self) -> Option<&InputTarget> {
+        match self {
+            InputAction::MouseMove { target, .. }
+            | InputAction::MouseClick { target, .. }
+            | InputAction::MouseDoubleClick { target, .. }
+            | InputAction::MousePress { target, .. }
+            | InputAction::MouseDown { target, .. }
+            | InputAction::MouseUp { target, .. }
+            | InputAction::MouseDrag { target, .. }
+            | InputAction::MouseScroll { target, .. }
+            | InputAction::KeyPress { target, .. }
+            | InputAction::KeyDown { target, .. }
+            | InputAction::KeyUp { target, .. }
+            | InputAction::KeySequence { target, .. }
+            | InputAction::TypeText { target, .. }
+            | InputAction::Hotkey { target, .. } => target.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// 返回第一个不能放在 Parallel 分支中的动作（见 `InputAction::Parallel`）
+    pub fn find_unparallelizable(&self) -> Option<&InputAction> {
+        match self {
+            InputAction::Sequence { actions } | InputAction::Parallel { actions } => {
+                actions.iter().find_map(InputAction::find_unparallelizable)
+            }
+            InputAction::Delay { .. } => None,
+            _ if self.target().is_some() || matches!(self.origin(), Some(Origin::Window(_))) => Some(self),
+            InputAction::MouseMove { .. }
+            | InputAction::MouseClick { .. }
+            | InputAction::MouseDoubleClick { .. }
+            | InputAction::MousePress { .. }
+            | InputAction::MouseDown { .. }
+            | InputAction::MouseUp { .. }
+            | InputAction::MouseDrag { .. }
+            | InputAction::MouseScroll { .. }
+            | InputAction::KeyPress { .. }
+            | InputAction::KeyDown { .. }
+            | InputAction::KeyUp { .. }
+            | InputAction::KeySequence { .. }
+            | InputAction::TypeText { .. }
+            | InputAction::Hotkey { .. } => None,
+            _ => Some(self),
+        }
+    }
+}
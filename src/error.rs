@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::validator::Diagnostic;
+
+/// 自动化操作错误
+#[derive(Debug, Clone)]
+pub enum AutomationError {
+    /// 执行前的静态校验未通过
+    Validation(Vec<Diagnostic>),
+    /// 平台接口（如 X11）调用失败
+    Platform(String),
+    /// 图像编码或处理失败
+    Image(String),
+    /// 等待条件在超时前未满足
+    Timeout(String),
+    /// 执行策略拒绝了动作
+    Policy(String),
+    /// 紧急停止中止了执行
+    Aborted(String),
+}
+
+impl fmt::Display for AutomationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutomationError::Validation(diagnostics) => {
+                write!(f, "validation failed with {} diagnostic(s)", diagnostics.len())?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {}", diagnostic)?;
+                }
+                Ok(())
+            }
+            AutomationError::Platform(message) => write!(f, "platform error: {}", message),
+            AutomationError::Image(message) => write!(f, "image error: {}", message),
+            AutomationError::Timeout(message) => write!(f, "timed out: {}", message),
+            AutomationError::Policy(message) => write!(f, "rejected by policy: {}", message),
+            AutomationError::Aborted(message) => write!(f, "aborted by fail-safe: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AutomationError {}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::errors::{ConnectError, ConnectionError, ParseError, ReplyError, ReplyOrIdError};
+
+    use super::AutomationError;
+
+    macro_rules! impl_from_x11_error {
+        ($($error:ty),*) => {
+            $(
+                impl From<$error> for AutomationError {
+                    fn from(err: $error) -> Self {
+                        AutomationError::Platform(format!("X11: {}", err))
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_from_x11_error!(ConnectError, ConnectionError, ParseError, ReplyError, ReplyOrIdError);
+}
@@ -0,0 +1,155 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::jobs::{JobId, JobInfo};
+
+/// 执行过程中按动作树遍历顺序发出的事件
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", content = "params")]
+pub enum ExecutionEvent {
+    /// 开始执行动作，path 为动作在树中的路径（如 `$.actions[2]`），action 为动作类型
+    ActionStarted { path: String, action: String },
+    /// 动作执行完成
+    ActionFinished {
+        path: String,
+        action: String,
+        result: String,
+        duration_ms: u64,
+    },
+    /// 动作执行失败，之后不会再有该次执行的其他事件
+    ActionFailed {
+        path: String,
+        action: String,
+        error: String,
+    },
+    /// 执行过程中的说明信息（开始等待、保存锚点、投递到目标窗口等）
+    Log { path: String, message: String },
+}
+
+/// 接收执行事件的通道
+pub type EventSender = UnboundedSender<ExecutionEvent>;
+
+/// 推送给实时订阅者的事件
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", content = "params")]
+pub enum LiveEvent {
+    /// 动作执行事件，job 为空表示直接执行（非任务队列）的动作
+    Action {
+        job: Option<JobId>,
+        event: ExecutionEvent,
+    },
+    /// 任务状态变化（排队、开始、结束）
+    Job(JobInfo),
+}
+
+impl LiveEvent {
+    /// 事件所属的任务
+    pub fn job(&self) -> Option<JobId> {
+        match self {
+            LiveEvent::Action { job, .. } => *job,
+            LiveEvent::Job(info) => Some(info.id),
+        }
+    }
+}
+
+/// 实时事件广播
+///
+/// 订阅者处理太慢时会丢失最早的事件（见 `broadcast::error::RecvError::Lagged`）
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl EventHub {
+    /// capacity 为每个订阅者最多缓存的事件数
+    pub fn new(capacity: usize) -> Self {
+        EventHub {
+            sender: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    /// 发布事件，没有订阅者时直接丢弃
+    pub fn publish(&self, event: LiveEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// 订阅之后发布的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 创建把执行事件转发为 `LiveEvent::Action` 的通道，发送端全部关闭后转发任务结束
+    pub fn forward(&self, job: Option<JobId>) -> EventSender {
+        let (events, mut received) = mpsc::unbounded_channel();
+        let hub = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = received.recv().await {
+                hub.publish(LiveEvent::Action { job, event });
+            }
+        });
+        events
+    }
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast::error::RecvError;
+
+    use super::*;
+
+    fn log(message: &str) -> ExecutionEvent {
+        ExecutionEvent::Log {
+            path: "$".into(),
+            message: message.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_execution_events_to_subscribers() {
+        let hub = EventHub::default();
+        let mut live = hub.subscribe();
+        let job = hub.forward(Some(7));
+        let direct = hub.forward(None);
+
+        job.send(log("first")).unwrap();
+        assert_eq!(
+            live.recv().await.unwrap(),
+            LiveEvent::Action {
+                job: Some(7),
+                event: log("first")
+            }
+        );
+        direct.send(log("second")).unwrap();
+        let event = live.recv().await.unwrap();
+        assert_eq!(event.job(), None);
+        assert_eq!(
+            event,
+            LiveEvent::Action {
+                job: None,
+                event: log("second")
+            }
+        );
+
+        // 发送端全部关闭后转发任务结束，不再持有广播发送端
+        drop((job, direct, hub));
+        assert!(matches!(live.recv().await, Err(RecvError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn slow_subscribers_lose_the_oldest_events() {
+        let hub = EventHub::new(1);
+        let mut live = hub.subscribe();
+        hub.publish(LiveEvent::Action { job: None, event: log("first") });
+        hub.publish(LiveEvent::Action { job: None, event: log("second") });
+        assert!(matches!(live.recv().await, Err(RecvError::Lagged(1))));
+        assert_eq!(live.recv().await.unwrap(), LiveEvent::Action { job: None, event: log("second") });
+    }
+}
@@ -1,28 +1,167 @@
 use std::time::Instant;
 
-use crate::{enums::InputAction, simulator::InputSimulator};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dry_run::DryRunReport,
+    enums::InputAction,
+    error::AutomationError,
+    events::EventSender,
+    fail_safe::FailSafe,
+    policy::{Confirmation, Policy},
+    simulator::InputSimulator,
+    timing::{StepTiming, TimingPolicy},
+    validator::{self, Diagnostic, ValidationOptions},
+};
+
+/// 执行报告
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ExecutionReport {
+    /// 模拟器返回的执行结果描述
+    pub result: String,
+    /// 总耗时（毫秒）
+    pub duration_ms: u128,
+    /// 每次等待的计划与实际时间
+    pub timings: Vec<StepTiming>,
+}
 
 /// 动作执行器
 pub struct ActionExecutor {
     simulator: InputSimulator,
+    validation: Option<ValidationOptions>,
 }
 
 impl ActionExecutor {
     /// 创建新的动作执行器
     pub fn new() -> Self {
+        Self::with_simulator(InputSimulator::new())
+    }
+
+    /// 使用指定的模拟器创建执行器（如 `InputSimulator::detached` 创建的只能演练的模拟器）
+    pub fn with_simulator(simulator: InputSimulator) -> Self {
         ActionExecutor {
-            simulator: InputSimulator::new(),
+            simulator,
+            validation: None,
         }
     }
-    
+
+    /// 启用执行前校验，存在 Error 级别诊断时拒绝执行
+    ///
+    /// 未指定屏幕尺寸时使用当前主屏幕尺寸
+    pub fn with_validation(mut self, mut options: ValidationOptions) -> Self {
+        if options.screen_size.is_none() {
+            options.screen_size = self.screen_size();
+        }
+        self.validation = Some(options);
+        self
+    }
+
+    /// 设置时间策略（速度倍率、最小间隔、默认延时和随机抖动）
+    pub fn with_timing(mut self, policy: TimingPolicy) -> Self {
+        self.simulator.set_timing_policy(policy);
+        self
+    }
+
+    /// 设置执行策略，每个动作执行前检查，被拒绝时返回 Policy 错误
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.simulator.set_policy(policy);
+        self
+    }
+
+    /// 设置确认回调，策略要求确认的动作只有回调返回 true 时才执行
+    pub fn with_confirmation(mut self, confirmation: Confirmation) -> Self {
+        self.simulator.set_confirmation(confirmation);
+        self
+    }
+
+    /// 设置紧急停止开关，触发后中止执行、释放按住的输入并拒绝之后的执行
+    pub fn with_fail_safe(mut self, fail_safe: FailSafe) -> Self {
+        self.simulator.set_fail_safe(fail_safe);
+        self
+    }
+
+    /// 获取紧急停止开关
+    pub fn fail_safe(&self) -> Option<&FailSafe> {
+        self.simulator.fail_safe()
+    }
+
+    /// 校验动作树（未启用校验时使用默认选项和当前屏幕尺寸）
+    pub fn validate(&self, action: &InputAction) -> Vec<Diagnostic> {
+        match &self.validation {
+            Some(options) => validator::validate_with(action, options),
+            None => validator::validate_with(
+                action,
+                &ValidationOptions {
+                    screen_size: self.screen_size(),
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+
     /// 执行单个动作并返回结果和耗时
-    pub async fn execute(&self, action: &InputAction) -> (String, u128) {
+    pub async fn execute(&self, action: &InputAction) -> Result<(String, u128), AutomationError> {
+        let report = self.execute_with_report(action).await?;
+        Ok((report.result, report.duration_ms))
+    }
+
+    /// 执行单个动作并返回包含计时细节的执行报告
+    pub async fn execute_with_report(&self, action: &InputAction) -> Result<ExecutionReport, AutomationError> {
+        self.run(action, None).await
+    }
+
+    /// 执行单个动作，并把每个动作的开始、完成和失败事件发送到 events
+    pub async fn execute_observed(
+        &self,
+        action: &InputAction,
+        events: EventSender,
+    ) -> Result<ExecutionReport, AutomationError> {
+        self.run(action, Some(events)).await
+    }
+
+    /// 启用了执行前校验时检查动作树，存在 Error 级别诊断时返回 Validation 错误
+    pub fn check(&self, action: &InputAction) -> Result<(), AutomationError> {
+        if let Some(options) = &self.validation {
+            let diagnostics = validator::validate_with(action, options);
+            if validator::has_errors(&diagnostics) {
+                return Err(AutomationError::Validation(diagnostics));
+            }
+        }
+        Ok(())
+    }
+
+    async fn run(&self, action: &InputAction, events: Option<EventSender>) -> Result<ExecutionReport, AutomationError> {
+        self.check(action)?;
+
         let start_time = Instant::now();
-        let result = self.simulator.execute_action(action).await;
-        let duration = start_time.elapsed().as_millis();
-        (result, duration)
+        let (result, timings) = match events {
+            Some(events) => self.simulator.execute_action_observed(action, events).await?,
+            None => self.simulator.execute_action_timed(action).await?,
+        };
+        let duration_ms = start_time.elapsed().as_millis();
+        Ok(ExecutionReport {
+            result,
+            duration_ms,
+            timings,
+        })
     }
     
+    /// 演练执行：返回事件时间线和预计耗时，不触碰真实设备
+    pub async fn dry_run(&self, action: &InputAction) -> Result<DryRunReport, AutomationError> {
+        self.simulator.dry_run(action).await
+    }
+
+    /// 释放执行过程中按下后仍未释放的键和鼠标按键
+    pub async fn release_all(&self) -> Result<Vec<String>, AutomationError> {
+        self.simulator.release_all().await
+    }
+
+    /// 获取鼠标光标的当前位置
+    pub async fn cursor_position(&self) -> Result<(i32, i32), AutomationError> {
+        self.simulator.cursor_position().await
+    }
+
     /// 获取屏幕尺寸
     pub fn get_screen_size(&self) -> (u32, u32) {
         self.simulator.get_screen_size()
@@ -32,4 +171,15 @@ impl ActionExecutor {
     pub fn simulator(&self) -> &InputSimulator {
         &self.simulator
     }
-}
\ No newline at end of file
+
+    /// 屏幕尺寸未知时返回 None
+    fn screen_size(&self) -> Option<(u32, u32)> {
+        Some(self.get_screen_size()).filter(|&(width, height)| width > 0 && height > 0)
+    }
+}
+
+impl Default for ActionExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::error::AutomationError;
+use crate::hotkey::Hotkey;
+use crate::screen::Rect;
+
+/// 屏幕角落
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// 屏幕中该角落边长为 size 的区域
+    pub fn rect(self, width: u32, height: u32, size: u32) -> Rect {
+        let size = size.max(1);
+        let right = width.saturating_sub(size) as i32;
+        let bottom = height.saturating_sub(size) as i32;
+        match self {
+            Corner::TopLeft => Rect::new(0, 0, size, size),
+            Corner::TopRight => Rect::new(right, 0, size, size),
+            Corner::BottomLeft => Rect::new(0, bottom, size, size),
+            Corner::BottomRight => Rect::new(right, bottom, size, size),
+        }
+    }
+}
+
+/// 紧急停止的触发条件
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailSafeConfig {
+    /// 光标进入这些屏幕角落时中止，默认为空，不检查光标；
+    /// 脚本自己把光标移到角落同样会触发，脚本中应避开这些位置
+    pub corners: Vec<Corner>,
+    /// 角落区域的边长（像素）
+    pub corner_size: u32,
+    /// 紧急停止热键，如 { modifiers = ["Ctrl", "Alt"], key = "Escape" }；
+    /// 同时按住其他修饰键（如 Ctrl+Alt+Shift+Escape）同样触发
+    pub panic_hotkey: Option<Hotkey>,
+    /// 检查光标位置的间隔（毫秒）
+    pub poll_interval_ms: u64,
+}
+
+impl Default for FailSafeConfig {
+    fn default() -> Self {
+        FailSafeConfig {
+            corners: Vec::new(),
+            corner_size: 1,
+            panic_hotkey: None,
+            poll_interval_ms: 20,
+        }
+    }
+}
+
+/// 紧急停止开关
+///
+/// 触发后正在进行的执行立即中止并释放按住的输入，之后的执行一律拒绝，直到调用 `reset`
+#[derive(Clone)]
+pub struct FailSafe {
+    state: Arc<watch::Sender<Option<String>>>,
+    _watcher: Option<Arc<Watcher>>,
+}
+
+/// 最后一个 FailSafe 被释放时通知监视线程退出
+struct Watcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl FailSafe {
+    /// 创建只能通过 `trip` 手动触发的开关
+    pub fn new() -> Self {
+        FailSafe {
+            state: Arc::new(watch::Sender::new(None)),
+            _watcher: None,
+        }
+    }
+
+    /// 连接到指定显示（None 表示使用 DISPLAY 环境变量），开始监视光标位置和紧急停止热键
+    #[cfg(target_os = "linux")]
+    pub fn start(config: &FailSafeConfig, display: Option<&str>) -> Result<Self, AutomationError> {
+        let fail_safe = FailSafe::new();
+        let stop = Arc::new(AtomicBool::new(false));
+        x11::spawn(config, display, fail_safe.clone(), stop.clone())?;
+        Ok(FailSafe {
+            _watcher: Some(Arc::new(Watcher { stop })),
+            ..fail_safe
+        })
+    }
+
+    /// 连接到指定显示，开始监视光标位置和紧急停止热键
+    #[cfg(not(target_os = "linux"))]
+    pub fn start(_config: &FailSafeConfig, _display: Option<&str>) -> Result<Self, AutomationError> {
+        Err(AutomationError::Platform(
+            "fail-safe corners and hotkeys are only supported on X11".to_string(),
+        ))
+    }
+
+    /// 触发紧急停止，已触发时保留最初的原因
+    pub fn trip(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.state.send_if_modified(|state| {
+            if state.is_some() {
+                return false;
+            }
+            *state = Some(reason);
+            true
+        });
+    }
+
+    /// 已触发时返回触发原因
+    pub fn tripped(&self) -> Option<String> {
+        self.state.borrow().clone()
+    }
+
+    /// 解除紧急停止；光标仍在角落时会立即再次触发
+    pub fn reset(&self) {
+        self.state.send_replace(None);
+    }
+
+    /// 等待触发并返回原因
+    pub async fn wait(&self) -> String {
+        let mut receiver = self.state.subscribe();
+        let reason = match receiver.wait_for(Option::is_some).await {
+            Ok(reason) => reason.clone(),
+            // 发送端与接收端都由 self 持有，不会提前关闭
+            Err(_) => None,
+        };
+        reason.unwrap_or_default()
+    }
+}
+
+impl Default for FailSafe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt as _;
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+
+    use super::{Corner, FailSafe, FailSafeConfig};
+    use crate::error::AutomationError;
+    use crate::hotkey::KeyGrabs;
+    use crate::screen::Rect;
+
+    pub(super) fn spawn(
+        config: &FailSafeConfig,
+        display: Option<&str>,
+        fail_safe: FailSafe,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), AutomationError> {
+        let (conn, screen_num) = x11rb::connect(display)?;
+        let mut grabs = KeyGrabs::new(&conn, screen_num)?;
+        if let Some(hotkey) = &config.panic_hotkey {
+            grabs.grab_any_modifiers(&conn, hotkey, ())?;
+        }
+        let screen = &conn.setup().roots[screen_num];
+        let corners: Vec<(Corner, Rect)> = config
+            .corners
+            .iter()
+            .map(|corner| {
+                let rect = corner.rect(screen.width_in_pixels as u32, screen.height_in_pixels as u32, config.corner_size);
+                (*corner, rect)
+            })
+            .collect();
+        let interval = Duration::from_millis(config.poll_interval_ms.max(1));
+        let config = config.clone();
+        std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                if let Err(err) = poll(&conn, &grabs, &config, &corners, &fail_safe) {
+                    // 无法继续监视时宁可停止执行
+                    fail_safe.trip(format!("fail-safe watcher stopped: {}", err));
+                    return;
+                }
+                std::thread::sleep(interval);
+            }
+        });
+        Ok(())
+    }
+
+    fn poll(
+        conn: &RustConnection,
+        grabs: &KeyGrabs<()>,
+        config: &FailSafeConfig,
+        corners: &[(Corner, Rect)],
+        fail_safe: &FailSafe,
+    ) -> Result<(), AutomationError> {
+        while let Some(event) = conn.poll_for_event()? {
+            if let Event::KeyPress(event) = event
+                && grabs.matches(&event).next().is_some()
+                && let Some(hotkey) = &config.panic_hotkey
+            {
+                fail_safe.trip(format!("panic hotkey {} pressed", hotkey));
+            }
+        }
+        if corners.is_empty() {
+            return Ok(());
+        }
+        let pointer = conn.query_pointer(grabs.root())?.reply()?;
+        let (x, y) = (pointer.root_x as i32, pointer.root_y as i32);
+        if let Some((corner, _)) = corners.iter().find(|(_, rect)| rect.contains(x, y)) {
+            fail_safe.trip(format!("cursor moved into the {:?} corner at ({}, {})", corner, x, y));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_are_opt_in() {
+        assert!(FailSafeConfig::default().corners.is_empty());
+        let config: FailSafeConfig = serde_json::from_value(serde_json::json!({"corners": ["TopLeft"]})).unwrap();
+        assert_eq!(config.corners, vec![Corner::TopLeft]);
+        assert_eq!(config.poll_interval_ms, 20);
+    }
+
+    #[test]
+    fn corner_rects_sit_inside_the_screen() {
+        assert_eq!(Corner::TopLeft.rect(800, 600, 5), Rect::new(0, 0, 5, 5));
+        assert_eq!(Corner::BottomRight.rect(800, 600, 5), Rect::new(795, 595, 5, 5));
+        assert_eq!(Corner::TopRight.rect(800, 600, 0), Rect::new(799, 0, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn trip_keeps_the_first_reason_until_reset() {
+        let fail_safe = FailSafe::new();
+        fail_safe.trip("first");
+        fail_safe.trip("second");
+        assert_eq!(fail_safe.wait().await, "first");
+        fail_safe.reset();
+        assert_eq!(fail_safe.tripped(), None);
+    }
+}
@@ -0,0 +1,1312 @@
+use std::sync::Arc;
+
+use base64::Engine;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::server::Router;
+use tonic::{Request, Response, Status};
+
+use crate::accessibility::ElementSelector;
+use crate::auth::{AuthError, Authenticator, Permissions, Principal, TlsConfig};
+use crate::enums::{InputAction, Key, KeyLocation, MouseButton, Origin};
+use crate::error::AutomationError;
+use crate::events::{EventHub, ExecutionEvent, LiveEvent};
+use crate::executor::{ActionExecutor, ExecutionReport};
+use crate::held::HeldInputs;
+use crate::jobs::{JobError, JobId, JobInfo, JobQueue, JobStatus};
+use crate::screen::{Color, DisplayInfo, Rect};
+use crate::timing::StepTiming;
+use crate::vision::ImageSource;
+use crate::window::{InputDelivery, InputTarget, WindowSelector};
+
+use self::proto::automation_server::{Automation, AutomationServer};
+
+/// 由 proto/automation.proto 生成的消息和服务定义
+pub mod proto {
+    tonic::include_proto!("automation");
+}
+
+/// 创建 gRPC 服务，与 HTTP 接口共用执行器、任务队列和认证配置；tls 不为空时启用 TLS（需要 tls feature）
+pub fn router(
+    executor: Arc<ActionExecutor>,
+    jobs: JobQueue,
+    auth: Arc<Authenticator>,
+    tls: Option<&TlsConfig>,
+) -> std::io::Result<Router> {
+    let mut builder = tonic::transport::Server::builder();
+    if let Some(tls) = tls {
+        builder = with_tls(builder, tls)?;
+    }
+    Ok(builder.add_service(AutomationService::new(executor, jobs).into_authenticated_server(auth)))
+}
+
+/// 在 listener 上提供 gRPC 服务
+pub async fn serve(listener: TcpListener, router: Router) -> Result<(), tonic::transport::Error> {
+    router.serve_with_incoming(TcpListenerStream::new(listener)).await
+}
+
+#[cfg(feature = "tls")]
+fn with_tls(builder: tonic::transport::Server, config: &TlsConfig) -> std::io::Result<tonic::transport::Server> {
+    use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))
+    };
+    let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(read(&config.cert)?, read(&config.key)?));
+    if let Some(client_ca) = &config.client_ca {
+        // 与 HTTP 接口相同，客户端证书是可选的
+        tls = tls
+            .client_ca_root(Certificate::from_pem(read(client_ca)?))
+            .client_auth_optional(true);
+    }
+    builder
+        .tls_config(tls)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err.to_string()))
+}
+
+#[cfg(not(feature = "tls"))]
+fn with_tls(_builder: tonic::transport::Server, _config: &TlsConfig) -> std::io::Result<tonic::transport::Server> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "tls requires the `tls` feature",
+    ))
+}
+
+/// 认证并限流的拦截器，通过后把 `Principal` 放入请求扩展中；token 通过 `authorization: Bearer` 元数据传递
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    auth: Arc<Authenticator>,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+        let principal = self
+            .auth
+            .authenticate(
+                token.as_deref(),
+                client_common_name(&request).as_deref(),
+                request.remote_addr().map(|addr| addr.ip()),
+            )
+            .and_then(|principal| self.auth.throttle(&principal).map(|_| principal))?;
+        request.extensions_mut().insert(principal);
+        Ok(request)
+    }
+}
+
+/// proto 中 Automation 服务的实现
+#[derive(Clone)]
+pub struct AutomationService {
+    executor: Arc<ActionExecutor>,
+    jobs: JobQueue,
+}
+
+impl AutomationService {
+    pub fn new(executor: Arc<ActionExecutor>, jobs: JobQueue) -> Self {
+        AutomationService { executor, jobs }
+    }
+
+    /// 包装为可以添加到 tonic `Server` 上的服务，不做认证，调用方拥有全部权限
+    pub fn into_server(self) -> AutomationServer<Self> {
+        AutomationServer::new(self)
+    }
+
+    /// 包装为先经过认证和限流的服务
+    pub fn into_authenticated_server(self, auth: Arc<Authenticator>) -> InterceptedService<AutomationServer<Self>, AuthInterceptor> {
+        InterceptedService::new(AutomationServer::new(self), AuthInterceptor { auth })
+    }
+}
+
+#[cfg(feature = "tls")]
+fn client_common_name(request: &Request<()>) -> Option<String> {
+    let certs = request.peer_certs()?;
+    crate::tls::common_name(certs.first()?)
+}
+
+#[cfg(not(feature = "tls"))]
+fn client_common_name(_request: &Request<()>) -> Option<String> {
+    None
+}
+
+/// 拦截器放入的调用方权限；没有经过拦截器时拥有全部权限
+fn permissions<T>(request: &Request<T>) -> Arc<Permissions> {
+    principal(request).permissions
+}
+
+/// 拦截器放入的调用方；没有经过拦截器时拥有全部权限
+fn principal<T>(request: &Request<T>) -> Principal {
+    request
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(|| Principal::anonymous("anonymous"))
+}
+
+impl AutomationService {
+    /// 查询调用方可以查看的任务，其他调用方的任务视为不存在
+    fn owned_job(&self, principal: &Principal, id: JobId) -> Result<JobInfo, Status> {
+        principal.permissions.check_jobs()?;
+        match self.jobs.get(id)? {
+            job if principal.owns(job.owner.as_deref()) => Ok(job),
+            _ => Err(JobError::NotFound(id).into()),
+        }
+    }
+
+    /// 调用方可以查看的任务
+    fn owned_jobs(&self, principal: &Principal) -> Vec<proto::Job> {
+        self.jobs
+            .list()
+            .into_iter()
+            .filter(|job| principal.owns(job.owner.as_deref()))
+            .map(proto::Job::from)
+            .collect()
+    }
+}
+
+#[tonic::async_trait]
+impl Automation for AutomationService {
+    type StreamExecuteStream = ReceiverStream<Result<proto::StreamExecuteResponse, Status>>;
+
+    async fn execute(&self, request: Request<proto::ExecuteRequest>) -> Result<Response<proto::ExecutionReport>, Status> {
+        let permissions = permissions(&request);
+        let action = InputAction::try_from(required(request.into_inner().action, "action")?)?;
+        permissions.check(&action)?;
+        let report = self.executor.execute_observed(&action, self.jobs.events().forward(None)).await?;
+        Ok(Response::new(report.into()))
+    }
+
+    async fn stream_execute(
+        &self,
+        request: Request<proto::ExecuteRequest>,
+    ) -> Result<Response<Self::StreamExecuteStream>, Status> {
+        let permissions = permissions(&request);
+        let action = InputAction::try_from(required(request.into_inner().action, "action")?)?;
+        permissions.check(&action)?;
+        // 校验失败时直接返回错误状态，而不是先建立流
+        self.executor.check(&action)?;
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::spawn(stream_execution(
+            self.executor.clone(),
+            self.jobs.events().clone(),
+            action,
+            sender,
+        ));
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn submit(&self, request: Request<proto::SubmitRequest>) -> Result<Response<proto::Job>, Status> {
+        let principal = principal(&request);
+        principal.permissions.check_jobs()?;
+        let request = request.into_inner();
+        let action = InputAction::try_from(required(request.action, "action")?)?;
+        principal.permissions.check(&action)?;
+        self.executor.check(&action)?;
+        Ok(Response::new(self.jobs.submit_as(&principal.name, action, request.priority)?.into()))
+    }
+
+    async fn get_job(&self, request: Request<proto::JobRequest>) -> Result<Response<proto::Job>, Status> {
+        let job = self.owned_job(&principal(&request), request.into_inner().id)?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn list_jobs(&self, request: Request<proto::ListJobsRequest>) -> Result<Response<proto::ListJobsResponse>, Status> {
+        let principal = principal(&request);
+        principal.permissions.check_jobs()?;
+        Ok(Response::new(proto::ListJobsResponse {
+            jobs: self.owned_jobs(&principal),
+        }))
+    }
+
+    async fn cancel(&self, request: Request<proto::JobRequest>) -> Result<Response<proto::Job>, Status> {
+        let job = self.owned_job(&principal(&request), request.into_inner().id)?;
+        Ok(Response::new(self.jobs.cancel(job.id)?.into()))
+    }
+
+    async fn release_all(
+        &self,
+        request: Request<proto::ReleaseAllRequest>,
+    ) -> Result<Response<proto::ReleaseAllResponse>, Status> {
+        permissions(&request).check_admin()?;
+        let released = self.executor.release_all().await?;
+        Ok(Response::new(proto::ReleaseAllResponse { released }))
+    }
+
+    async fn capture_screen(&self, request: Request<proto::CaptureRequest>) -> Result<Response<proto::Image>, Status> {
+        permissions(&request).check_capture()?;
+        let region = request.into_inner().region.map(rect);
+        let image = tokio::task::spawn_blocking(move || capture_png(region))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))??;
+        Ok(Response::new(image))
+    }
+
+    async fn get_state(&self, request: Request<proto::StateRequest>) -> Result<Response<proto::State>, Status> {
+        let principal = principal(&request);
+        principal.permissions.check_capture()?;
+        let (screen_width, screen_height) = self.executor.get_screen_size();
+        let (cursor_x, cursor_y) = self.executor.cursor_position().await?;
+        let displays = tokio::task::spawn_blocking(list_displays)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))??;
+        Ok(Response::new(proto::State {
+            screen_width,
+            screen_height,
+            cursor_x,
+            cursor_y,
+            displays: displays.into_iter().map(proto::Display::from).collect(),
+            held_inputs: held_inputs(&self.executor.simulator().held_inputs()),
+            jobs: if principal.permissions.jobs { self.owned_jobs(&principal) } else { Vec::new() },
+        }))
+    }
+}
+
+/// 执行动作并把事件写入 sender，最后写入执行报告或错误状态；
+/// 客户端断开时中断执行并释放仍被按住的键和鼠标按键
+async fn stream_execution(
+    executor: Arc<ActionExecutor>,
+    hub: EventHub,
+    action: InputAction,
+    sender: mpsc::Sender<Result<proto::StreamExecuteResponse, Status>>,
+) {
+    let (events, mut received) = mpsc::unbounded_channel();
+    let mut run = Box::pin(executor.execute_observed(&action, events));
+    let result = loop {
+        tokio::select! {
+            biased;
+            Some(event) = received.recv() => {
+                if !send_event(&sender, &hub, event).await {
+                    break None;
+                }
+            }
+            result = &mut run => break Some(result),
+            _ = sender.closed() => break None,
+        }
+    };
+    drop(run);
+    let Some(result) = result else {
+        let _ = executor.release_all().await;
+        return;
+    };
+    while let Ok(event) = received.try_recv() {
+        if !send_event(&sender, &hub, event).await {
+            return;
+        }
+    }
+    let message = result.map(|report| proto::StreamExecuteResponse {
+        message: Some(proto::stream_execute_response::Message::Report(report.into())),
+    });
+    let _ = sender.send(message.map_err(Status::from)).await;
+}
+
+/// 同时发布到实时事件广播，返回客户端是否仍在接收
+async fn send_event(
+    sender: &mpsc::Sender<Result<proto::StreamExecuteResponse, Status>>,
+    hub: &EventHub,
+    event: ExecutionEvent,
+) -> bool {
+    hub.publish(LiveEvent::Action {
+        job: None,
+        event: event.clone(),
+    });
+    let message = proto::StreamExecuteResponse {
+        message: Some(proto::stream_execute_response::Message::Event(event.into())),
+    };
+    sender.send(Ok(message)).await.is_ok()
+}
+
+impl From<AuthError> for Status {
+    fn from(err: AuthError) -> Self {
+        let message = err.to_string();
+        match err {
+            AuthError::Unauthorized(_) => Status::unauthenticated(message),
+            AuthError::Forbidden(_) => Status::permission_denied(message),
+            AuthError::RateLimited { .. } => Status::resource_exhausted(message),
+        }
+    }
+}
+
+impl From<AutomationError> for Status {
+    fn from(err: AutomationError) -> Self {
+        let message = err.to_string();
+        match err {
+            AutomationError::Validation(_) => Status::invalid_argument(message),
+            AutomationError::Platform(_) | AutomationError::Image(_) => Status::internal(message),
+            AutomationError::Timeout(_) => Status::deadline_exceeded(message),
+            AutomationError::Policy(_) => Status::permission_denied(message),
+            AutomationError::Aborted(_) => Status::aborted(message),
+        }
+    }
+}
+
+impl From<JobError> for Status {
+    fn from(err: JobError) -> Self {
+        let message = err.to_string();
+        match err {
+            JobError::NotFound(_) => Status::not_found(message),
+            JobError::AlreadyFinished(_) => Status::failed_precondition(message),
+            JobError::QueueFull(_) => Status::resource_exhausted(message),
+        }
+    }
+}
+
+// ---------- proto -> Rust ----------
+
+fn required<T>(value: Option<T>, field: &str) -> Result<T, Status> {
+    value.ok_or_else(|| Status::invalid_argument(format!("missing field `{}`", field)))
+}
+
+fn invalid(field: &str, value: impl std::fmt::Display) -> Status {
+    Status::invalid_argument(format!("invalid `{}`: {}", field, value))
+}
+
+macro_rules! key_codes {
+    ($($key:ident),* $(,)?) => {
+        fn key_from_code(code: proto::KeyCode) -> Option<Key> {
+            match code {
+                proto::KeyCode::Unspecified => None,
+                $(proto::KeyCode::$key => Some(Key::$key),)*
+            }
+        }
+    };
+}
+
+key_codes!(
+    Backspace, Tab, Enter, Shift, Ctrl, Alt, CapsLock, Escape, Space, PageUp, PageDown, End, Home,
+    ArrowLeft, ArrowUp, ArrowRight, ArrowDown, PrintScreen, Insert, Delete, Num0, Num1, Num2, Num3,
+    Num4, Num5, Num6, Num7, Num8, Num9, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T,
+    U, V, W, X, Y, Z, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, Semicolon, Equal, Comma,
+    Minus, Period, Slash, Backquote, LeftBracket, Backslash, RightBracket, Quote, Numpad0, Numpad1,
+    Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, NumpadMultiply,
+    NumpadAdd, NumpadSubtract, NumpadDecimal, NumpadDivide, NumpadEnter, Meta, ContextMenu,
+    VolumeMute, VolumeDown, VolumeUp, MediaPlayPause, MediaStop, MediaNextTrack, MediaPreviousTrack,
+);
+
+impl TryFrom<proto::Key> for Key {
+    type Error = Status;
+
+    fn try_from(key: proto::Key) -> Result<Self, Status> {
+        match required(key.key, "key")? {
+            proto::key::Key::Code(code) => proto::KeyCode::try_from(code)
+                .ok()
+                .and_then(key_from_code)
+                .ok_or_else(|| invalid("key.code", code)),
+            proto::key::Key::Other(code) => Ok(Key::Other(code)),
+        }
+    }
+}
+
+impl TryFrom<proto::MouseButton> for MouseButton {
+    type Error = Status;
+
+    fn try_from(button: proto::MouseButton) -> Result<Self, Status> {
+        match required(button.button, "button")? {
+            proto::mouse_button::Button::Code(code) => match proto::MouseButtonCode::try_from(code) {
+                Ok(proto::MouseButtonCode::Left) => Ok(MouseButton::Left),
+                Ok(proto::MouseButtonCode::Right) => Ok(MouseButton::Right),
+                Ok(proto::MouseButtonCode::Middle) => Ok(MouseButton::Middle),
+                Ok(proto::MouseButtonCode::Back) => Ok(MouseButton::Back),
+                Ok(proto::MouseButtonCode::Forward) => Ok(MouseButton::Forward),
+                Ok(proto::MouseButtonCode::Unspecified) | Err(_) => Err(invalid("button.code", code)),
+            },
+            proto::mouse_button::Button::Other(code) => u8::try_from(code)
+                .map(MouseButton::Other)
+                .map_err(|_| invalid("button.other", code)),
+        }
+    }
+}
+
+fn key(key: Option<proto::Key>) -> Result<Key, Status> {
+    Key::try_from(required(key, "key")?)
+}
+
+fn keys(keys: Vec<proto::Key>) -> Result<Vec<Key>, Status> {
+    keys.into_iter().map(Key::try_from).collect()
+}
+
+fn location(location: i32) -> Result<Option<KeyLocation>, Status> {
+    match proto::KeyLocation::try_from(location) {
+        Ok(proto::KeyLocation::Unspecified) => Ok(None),
+        Ok(proto::KeyLocation::Standard) => Ok(Some(KeyLocation::Standard)),
+        Ok(proto::KeyLocation::Left) => Ok(Some(KeyLocation::Left)),
+        Ok(proto::KeyLocation::Right) => Ok(Some(KeyLocation::Right)),
+        Ok(proto::KeyLocation::Numpad) => Ok(Some(KeyLocation::Numpad)),
+        Err(_) => Err(invalid("location", location)),
+    }
+}
+
+fn button(button: Option<proto::MouseButton>) -> Result<MouseButton, Status> {
+    MouseButton::try_from(required(button, "button")?)
+}
+
+fn rect(rect: proto::Rect) -> Rect {
+    Rect::new(rect.x, rect.y, rect.width, rect.height)
+}
+
+fn channel(field: &str, value: u32) -> Result<u8, Status> {
+    u8::try_from(value).map_err(|_| invalid(field, value))
+}
+
+fn color(color: Option<proto::Color>) -> Result<Color, Status> {
+    let color = required(color, "color")?;
+    Ok(Color::new(
+        channel("color.r", color.r)?,
+        channel("color.g", color.g)?,
+        channel("color.b", color.b)?,
+    ))
+}
+
+fn image(image: Option<proto::ImageSource>) -> Result<ImageSource, Status> {
+    Ok(match required(required(image, "image")?.source, "image.source")? {
+        proto::image_source::Source::Path(path) => ImageSource::Path(path),
+        proto::image_source::Source::Base64(data) => ImageSource::Base64(data),
+        proto::image_source::Source::Png(png) => {
+            ImageSource::Base64(base64::engine::general_purpose::STANDARD.encode(png))
+        }
+    })
+}
+
+fn window_selector(window: proto::WindowSelector) -> WindowSelector {
+    WindowSelector {
+        id: window.id,
+        title: window.title,
+        class: window.class,
+        pid: window.pid,
+    }
+}
+
+fn window(window: Option<proto::WindowSelector>) -> Result<WindowSelector, Status> {
+    required(window, "window").map(window_selector)
+}
+
+fn target(target: Option<proto::InputTarget>) -> Result<Option<InputTarget>, Status> {
+    target
+        .map(|target| {
+            let delivery = match proto::InputDelivery::try_from(target.delivery) {
+                Ok(proto::InputDelivery::SendEvent) => InputDelivery::SendEvent,
+                Ok(proto::InputDelivery::Focus) => InputDelivery::Focus,
+                Err(_) => return Err(invalid("target.delivery", target.delivery)),
+            };
+            Ok(InputTarget {
+                window: required(target.window, "target.window").map(window_selector)?,
+                delivery,
+            })
+        })
+        .transpose()
+}
+
+fn origin(origin: Option<proto::Origin>) -> Result<Option<Origin>, Status> {
+    origin
+        .map(|origin| {
+            Ok(match required(origin.origin, "origin")? {
+                proto::origin::Origin::Screen(_) => Origin::Screen,
+                proto::origin::Origin::Window(window) => Origin::Window(window_selector(window)),
+                proto::origin::Origin::Anchor(anchor) => Origin::Anchor(anchor),
+            })
+        })
+        .transpose()
+}
+
+fn selector(selector: Option<proto::ElementSelector>) -> Result<ElementSelector, Status> {
+    let selector = required(selector, "selector")?;
+    Ok(ElementSelector {
+        application: selector.application,
+        role: selector.role,
+        name: selector.name,
+        path: selector.path,
+    })
+}
+
+fn actions(actions: Vec<proto::InputAction>) -> Result<Vec<InputAction>, Status> {
+    actions.into_iter().map(InputAction::try_from).collect()
+}
+
+impl TryFrom<proto::InputAction> for InputAction {
+    type Error = Status;
+
+    fn try_from(action: proto::InputAction) -> Result<Self, Status> {
+        use proto::input_action::Action;
+
+        Ok(match required(action.action, "action")? {
+            Action::MouseMove(a) => InputAction::MouseMove {
+                x: a.x,
+                y: a.y,
+                origin: origin(a.origin)?,
+                target: target(a.target)?,
+            },
+            Action::MouseClick(a) => InputAction::MouseClick {
+                button: button(a.button)?,
+                x: a.x,
+                y: a.y,
+                origin: origin(a.origin)?,
+                target: target(a.target)?,
+            },
+            Action::MouseDoubleClick(a) => InputAction::MouseDoubleClick {
+                button: button(a.button)?,
+                x: a.x,
+                y: a.y,
+                origin: origin(a.origin)?,
+                target: target(a.target)?,
+            },
+            Action::MousePress(a) => InputAction::MousePress {
+                button: button(a.button)?,
+                x: a.x,
+                y: a.y,
+                duration_ms: a.duration_ms,
+                origin: origin(a.origin)?,
+                target: target(a.target)?,
+            },
+            Action::MouseDown(a) => InputAction::MouseDown {
+                button: button(a.button)?,
+                target: target(a.target)?,
+            },
+            Action::MouseUp(a) => InputAction::MouseUp {
+                button: button(a.button)?,
+                target: target(a.target)?,
+            },
+            Action::MouseDrag(a) => InputAction::MouseDrag {
+                button: button(a.button)?,
+                start_x: a.start_x,
+                start_y: a.start_y,
+                end_x: a.end_x,
+                end_y: a.end_y,
+                duration_ms: a.duration_ms,
+                origin: origin(a.origin)?,
+                target: target(a.target)?,
+            },
+            Action::MouseScroll(a) => InputAction::MouseScroll {
+                delta_x: a.delta_x,
+                delta_y: a.delta_y,
+                target: target(a.target)?,
+            },
+            Action::KeyPress(a) => InputAction::KeyPress {
+                key: key(a.key)?,
+                location: location(a.location)?,
+                target: target(a.target)?,
+            },
+            Action::KeyDown(a) => InputAction::KeyDown {
+                key: key(a.key)?,
+                location: location(a.location)?,
+                target: target(a.target)?,
+            },
+            Action::KeyUp(a) => InputAction::KeyUp {
+                key: key(a.key)?,
+                location: location(a.location)?,
+                target: target(a.target)?,
+            },
+            Action::KeySequence(a) => InputAction::KeySequence {
+                keys: keys(a.keys)?,
+                key_delay_ms: a.key_delay_ms,
+                target: target(a.target)?,
+            },
+            Action::TypeText(a) => InputAction::TypeText {
+                text: a.text,
+                char_delay_ms: a.char_delay_ms,
+                target: target(a.target)?,
+            },
+            Action::Hotkey(a) => InputAction::Hotkey {
+                modifiers: keys(a.modifiers)?,
+                key: key(a.key)?,
+                location: location(a.location)?,
+                target: target(a.target)?,
+            },
+            Action::Delay(a) => InputAction::Delay {
+                milliseconds: a.milliseconds,
+            },
+            Action::WaitForPixel(a) => InputAction::WaitForPixel {
+                x: a.x,
+                y: a.y,
+                color: color(a.color)?,
+                tolerance: channel("tolerance", a.tolerance)?,
+                timeout_ms: a.timeout_ms,
+            },
+            Action::WaitForScreenStable(a) => InputAction::WaitForScreenStable {
+                region: a.region.map(rect),
+                quiet_ms: a.quiet_ms,
+                timeout_ms: a.timeout_ms,
+            },
+            Action::WaitForChange(a) => InputAction::WaitForChange {
+                region: a.region.map(rect),
+                timeout_ms: a.timeout_ms,
+            },
+            Action::WaitForText(a) => InputAction::WaitForText {
+                text: a.text,
+                region: a.region.map(rect),
+                timeout_ms: a.timeout_ms,
+                anchor: a.anchor,
+            },
+            Action::ClickText(a) => InputAction::ClickText {
+                text: a.text,
+                region: a.region.map(rect),
+                button: button(a.button)?,
+                timeout_ms: a.timeout_ms,
+                anchor: a.anchor,
+            },
+            Action::WaitForImage(a) => InputAction::WaitForImage {
+                image: image(a.image)?,
+                region: a.region.map(rect),
+                threshold: a.threshold,
+                timeout_ms: a.timeout_ms,
+                anchor: a.anchor,
+            },
+            Action::ClickImage(a) => InputAction::ClickImage {
+                image: image(a.image)?,
+                region: a.region.map(rect),
+                threshold: a.threshold,
+                button: button(a.button)?,
+                timeout_ms: a.timeout_ms,
+                anchor: a.anchor,
+            },
+            Action::FocusWindow(a) => InputAction::FocusWindow {
+                window: window(a.window)?,
+            },
+            Action::MoveWindow(a) => InputAction::MoveWindow {
+                window: window(a.window)?,
+                x: a.x,
+                y: a.y,
+            },
+            Action::ResizeWindow(a) => InputAction::ResizeWindow {
+                window: window(a.window)?,
+                width: a.width,
+                height: a.height,
+            },
+            Action::MinimizeWindow(a) => InputAction::MinimizeWindow {
+                window: window(a.window)?,
+            },
+            Action::CloseWindow(a) => InputAction::CloseWindow {
+                window: window(a.window)?,
+            },
+            Action::WaitForWindow(a) => InputAction::WaitForWindow {
+                window: window(a.window)?,
+                timeout_ms: a.timeout_ms,
+            },
+            Action::WaitForElement(a) => InputAction::WaitForElement {
+                selector: selector(a.selector)?,
+                state: a.state,
+                timeout_ms: a.timeout_ms,
+                anchor: a.anchor,
+            },
+            Action::ClickElement(a) => InputAction::ClickElement {
+                selector: selector(a.selector)?,
+                timeout_ms: a.timeout_ms,
+            },
+            Action::SetElementText(a) => InputAction::SetElementText {
+                selector: selector(a.selector)?,
+                text: a.text,
+                timeout_ms: a.timeout_ms,
+            },
+            Action::Sequence(a) => InputAction::Sequence {
+                actions: actions(a.actions)?,
+            },
+            Action::Parallel(a) => InputAction::Parallel {
+                actions: actions(a.actions)?,
+            },
+        })
+    }
+}
+
+// ---------- Rust -> proto ----------
+
+fn proto_rect(rect: Rect) -> proto::Rect {
+    proto::Rect {
+        x: rect.x,
+        y: rect.y,
+        width: rect.width,
+        height: rect.height,
+    }
+}
+
+impl From<StepTiming> for proto::StepTiming {
+    fn from(timing: StepTiming) -> Self {
+        proto::StepTiming {
+            index: timing.index as u64,
+            requested_us: timing.requested_us,
+            planned_us: timing.planned_us,
+            actual_us: timing.actual_us,
+            deviation_us: timing.deviation_us,
+        }
+    }
+}
+
+impl From<ExecutionReport> for proto::ExecutionReport {
+    fn from(report: ExecutionReport) -> Self {
+        proto::ExecutionReport {
+            result: report.result,
+            duration_ms: u64::try_from(report.duration_ms).unwrap_or(u64::MAX),
+            timings: report.timings.into_iter().map(proto::StepTiming::from).collect(),
+        }
+    }
+}
+
+impl From<ExecutionEvent> for proto::ExecutionEvent {
+    fn from(event: ExecutionEvent) -> Self {
+        use proto::execution_event::Event;
+
+        let event = match event {
+            ExecutionEvent::ActionStarted { path, action } => Event::Started(proto::ActionStarted { path, action }),
+            ExecutionEvent::ActionFinished {
+                path,
+                action,
+                result,
+                duration_ms,
+            } => Event::Finished(proto::ActionFinished {
+                path,
+                action,
+                result,
+                duration_ms,
+            }),
+            ExecutionEvent::ActionFailed { path, action, error } => {
+                Event::Failed(proto::ActionFailed { path, action, error })
+            }
+            ExecutionEvent::Log { path, message } => Event::Log(proto::ActionLog { path, message }),
+        };
+        proto::ExecutionEvent { event: Some(event) }
+    }
+}
+
+impl From<JobInfo> for proto::Job {
+    fn from(job: JobInfo) -> Self {
+        let status = match job.status {
+            JobStatus::Queued => proto::JobStatus::Queued,
+            JobStatus::Running => proto::JobStatus::Running,
+            JobStatus::Succeeded => proto::JobStatus::Succeeded,
+            JobStatus::Failed => proto::JobStatus::Failed,
+            JobStatus::Cancelled => proto::JobStatus::Cancelled,
+        };
+        proto::Job {
+            id: job.id,
+            status: status.into(),
+            priority: job.priority,
+            owner: job.owner,
+            submitted_at_ms: job.submitted_at_ms,
+            started_at_ms: job.started_at_ms,
+            finished_at_ms: job.finished_at_ms,
+            progress: Some(proto::JobProgress {
+                completed: job.progress.completed as u64,
+                total: job.progress.total as u64,
+                current: job.progress.current,
+            }),
+            report: job.report.map(proto::ExecutionReport::from),
+            error: job.error,
+        }
+    }
+}
+
+impl From<DisplayInfo> for proto::Display {
+    fn from(display: DisplayInfo) -> Self {
+        proto::Display {
+            id: display.id,
+            name: display.name,
+            primary: display.primary,
+            bounds: Some(proto_rect(display.bounds)),
+        }
+    }
+}
+
+/// 与释放时返回的描述一致
+fn held_inputs(held: &HeldInputs) -> Vec<String> {
+    let buttons = held.buttons.iter().map(|button| format!("{:?} button", button));
+    let raw_keys = held.raw_keys.iter().map(|keycode| format!("keycode {}", keycode));
+    let keys = held.keys.iter().map(|key| format!("{:?} key", key));
+    buttons.chain(raw_keys).chain(keys).collect()
+}
+
+#[cfg(target_os = "linux")]
+fn capture_png(region: Option<Rect>) -> Result<proto::Image, AutomationError> {
+    let image = match region {
+        Some(region) => crate::screen::capture_region(region)?,
+        None => crate::screen::capture_screen()?,
+    };
+    Ok(proto::Image {
+        png: crate::screen::encode_png(&image)?,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture_png(_region: Option<Rect>) -> Result<proto::Image, AutomationError> {
+    Err(AutomationError::Platform(
+        "screen capture is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn list_displays() -> Result<Vec<DisplayInfo>, AutomationError> {
+    crate::screen::list_displays()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_displays() -> Result<Vec<DisplayInfo>, AutomationError> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_json::json;
+
+    use super::*;
+    use proto::input_action::Action;
+
+    fn code(code: proto::KeyCode) -> proto::Key {
+        proto::Key {
+            key: Some(proto::key::Key::Code(code.into())),
+        }
+    }
+
+    fn left() -> Option<proto::MouseButton> {
+        Some(proto::MouseButton {
+            button: Some(proto::mouse_button::Button::Code(proto::MouseButtonCode::Left.into())),
+        })
+    }
+
+    fn action(action: Action) -> proto::InputAction {
+        proto::InputAction { action: Some(action) }
+    }
+
+    fn xterm() -> Option<proto::WindowSelector> {
+        Some(proto::WindowSelector {
+            title: Some("xterm".into()),
+            ..Default::default()
+        })
+    }
+
+    fn region() -> Option<proto::Rect> {
+        Some(proto::Rect {
+            x: 1,
+            y: 2,
+            width: 3,
+            height: 4,
+        })
+    }
+
+    fn status<T: std::fmt::Debug>(result: Result<T, Status>) -> String {
+        let status = result.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        status.message().to_string()
+    }
+
+    /// 将 "ArrowLeft" 转换为 proto 枚举值名称 "ARROW_LEFT"
+    fn screaming_snake(name: &str) -> String {
+        let mut out = String::new();
+        for (index, c) in name.chars().enumerate() {
+            if index > 0 && c.is_ascii_uppercase() {
+                out.push('_');
+            }
+            out.push(c.to_ascii_uppercase());
+        }
+        out
+    }
+
+    /// 模式中列出的单元变体名称
+    fn unit_variants(schema: schemars::Schema) -> BTreeSet<String> {
+        schema.as_value()["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|variant| variant["enum"].as_array())
+            .flatten()
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_key_code_maps_to_the_key_of_the_same_name() {
+        let mut names = BTreeSet::new();
+        for value in 1.. {
+            let Ok(proto_code) = proto::KeyCode::try_from(value) else {
+                break;
+            };
+            let key = Key::try_from(code(proto_code)).unwrap();
+            let name = serde_json::to_value(key).unwrap().as_str().unwrap().to_string();
+            assert_eq!(proto_code.as_str_name(), format!("KEY_CODE_{}", screaming_snake(&name)));
+            assert!(names.insert(name));
+        }
+        assert_eq!(names, unit_variants(schemars::schema_for!(Key)));
+
+        let other = proto::Key {
+            key: Some(proto::key::Key::Other(0xff0d)),
+        };
+        assert_eq!(Key::try_from(other).unwrap(), Key::Other(0xff0d));
+        assert_eq!(status(Key::try_from(proto::Key { key: None })), "missing field `key`");
+        assert_eq!(status(Key::try_from(code(proto::KeyCode::Unspecified))), "invalid `key.code`: 0");
+        let unknown = proto::Key {
+            key: Some(proto::key::Key::Code(10_000)),
+        };
+        assert_eq!(status(Key::try_from(unknown)), "invalid `key.code`: 10000");
+    }
+
+    #[test]
+    fn every_button_code_maps_to_the_button_of_the_same_name() {
+        let mut names = BTreeSet::new();
+        for value in 1.. {
+            let Ok(proto_code) = proto::MouseButtonCode::try_from(value) else {
+                break;
+            };
+            let button = MouseButton::try_from(proto::MouseButton {
+                button: Some(proto::mouse_button::Button::Code(value)),
+            })
+            .unwrap();
+            let name = serde_json::to_value(button).unwrap().as_str().unwrap().to_string();
+            assert_eq!(proto_code.as_str_name(), format!("MOUSE_BUTTON_CODE_{}", screaming_snake(&name)));
+            assert!(names.insert(name));
+        }
+        assert_eq!(names, unit_variants(schemars::schema_for!(MouseButton)));
+
+        let other = |code| proto::MouseButton {
+            button: Some(proto::mouse_button::Button::Other(code)),
+        };
+        assert_eq!(MouseButton::try_from(other(8)).unwrap(), MouseButton::Other(8));
+        assert_eq!(status(MouseButton::try_from(other(256))), "invalid `button.other`: 256");
+        assert_eq!(status(MouseButton::try_from(proto::MouseButton { button: None })), "missing field `button`");
+    }
+
+    #[test]
+    fn every_location_maps_to_the_location_of_the_same_name() {
+        assert_eq!(location(0).unwrap(), None);
+        for value in 1.. {
+            let Ok(proto_location) = proto::KeyLocation::try_from(value) else {
+                assert_eq!(value, 5);
+                break;
+            };
+            let name = serde_json::to_value(location(value).unwrap()).unwrap();
+            assert_eq!(
+                proto_location.as_str_name(),
+                format!("KEY_LOCATION_{}", screaming_snake(name.as_str().unwrap()))
+            );
+        }
+        assert_eq!(status(location(5)), "invalid `location`: 5");
+    }
+
+    #[test]
+    fn converts_every_action() {
+        let target = Some(proto::InputTarget {
+            window: xterm(),
+            delivery: proto::InputDelivery::Focus.into(),
+        });
+        let window_origin = Some(proto::Origin {
+            origin: Some(proto::origin::Origin::Window(proto::WindowSelector {
+                id: Some(7),
+                ..Default::default()
+            })),
+        });
+        let anchor_origin = Some(proto::Origin {
+            origin: Some(proto::origin::Origin::Anchor("label".into())),
+        });
+        let screen_origin = Some(proto::Origin {
+            origin: Some(proto::origin::Origin::Screen(proto::ScreenOrigin {})),
+        });
+        let selector = Some(proto::ElementSelector {
+            application: Some("gedit".into()),
+            role: Some("push button".into()),
+            ..Default::default()
+        });
+        let png = |source| {
+            Some(proto::ImageSource {
+                source: Some(source),
+            })
+        };
+
+        let actions = vec![
+            action(Action::MouseMove(proto::MouseMove {
+                x: 1,
+                y: 2,
+                origin: window_origin.clone(),
+                target: target.clone(),
+            })),
+            action(Action::MouseClick(proto::MouseClick {
+                button: left(),
+                x: 3,
+                y: 4,
+                origin: anchor_origin,
+                target: None,
+            })),
+            action(Action::MouseDoubleClick(proto::MouseDoubleClick {
+                button: left(),
+                x: 5,
+                y: 6,
+                origin: screen_origin,
+                ..Default::default()
+            })),
+            action(Action::MousePress(proto::MousePress {
+                button: left(),
+                x: 7,
+                y: 8,
+                duration_ms: 100,
+                ..Default::default()
+            })),
+            action(Action::MouseDown(proto::MouseDown {
+                button: left(),
+                target: target.clone(),
+            })),
+            action(Action::MouseUp(proto::MouseUp {
+                button: left(),
+                target: None,
+            })),
+            action(Action::MouseDrag(proto::MouseDrag {
+                button: left(),
+                start_x: 1,
+                start_y: 2,
+                end_x: 3,
+                end_y: 4,
+                duration_ms: 200,
+                origin: window_origin,
+                target: None,
+            })),
+            action(Action::MouseScroll(proto::MouseScroll {
+                delta_x: -1,
+                delta_y: 3,
+                target: None,
+            })),
+            action(Action::KeyPress(proto::KeyPress {
+                key: Some(code(proto::KeyCode::Enter)),
+                location: proto::KeyLocation::Numpad.into(),
+                target: target.clone(),
+            })),
+            action(Action::KeyDown(proto::KeyDown {
+                key: Some(code(proto::KeyCode::Shift)),
+                location: proto::KeyLocation::Left.into(),
+                target: None,
+            })),
+            action(Action::KeyUp(proto::KeyUp {
+                key: Some(code(proto::KeyCode::Shift)),
+                location: proto::KeyLocation::Unspecified.into(),
+                target: None,
+            })),
+            action(Action::KeySequence(proto::KeySequence {
+                keys: vec![code(proto::KeyCode::A), proto::Key { key: Some(proto::key::Key::Other(65)) }],
+                key_delay_ms: Some(30),
+                target: None,
+            })),
+            action(Action::TypeText(proto::TypeText {
+                text: "hello".into(),
+                char_delay_ms: None,
+                target,
+            })),
+            action(Action::Hotkey(proto::Hotkey {
+                modifiers: vec![code(proto::KeyCode::Ctrl), code(proto::KeyCode::Alt)],
+                key: Some(code(proto::KeyCode::Delete)),
+                location: proto::KeyLocation::Right.into(),
+                target: None,
+            })),
+            action(Action::Delay(proto::Delay { milliseconds: 250 })),
+            action(Action::WaitForPixel(proto::WaitForPixel {
+                x: 1,
+                y: 2,
+                color: Some(proto::Color { r: 255, g: 128, b: 0 }),
+                tolerance: 10,
+                timeout_ms: 1000,
+            })),
+            action(Action::WaitForScreenStable(proto::WaitForScreenStable {
+                region: region(),
+                quiet_ms: 300,
+                timeout_ms: 2000,
+            })),
+            action(Action::WaitForChange(proto::WaitForChange {
+                region: None,
+                timeout_ms: 500,
+            })),
+            action(Action::WaitForText(proto::WaitForText {
+                text: "Save".into(),
+                region: region(),
+                timeout_ms: 1000,
+                anchor: Some("save".into()),
+            })),
+            action(Action::ClickText(proto::ClickText {
+                text: "OK".into(),
+                region: None,
+                button: left(),
+                timeout_ms: 1000,
+                anchor: None,
+            })),
+            action(Action::WaitForImage(proto::WaitForImage {
+                image: png(proto::image_source::Source::Path("button.png".into())),
+                region: region(),
+                threshold: 0.5,
+                timeout_ms: 1000,
+                anchor: Some("button".into()),
+            })),
+            action(Action::ClickImage(proto::ClickImage {
+                image: png(proto::image_source::Source::Png(vec![1, 2, 3])),
+                region: None,
+                threshold: 0.25,
+                button: left(),
+                timeout_ms: 1000,
+                anchor: None,
+            })),
+            action(Action::FocusWindow(proto::FocusWindow { window: xterm() })),
+            action(Action::MoveWindow(proto::MoveWindow {
+                window: xterm(),
+                x: 10,
+                y: -20,
+            })),
+            action(Action::ResizeWindow(proto::ResizeWindow {
+                window: xterm(),
+                width: 640,
+                height: 480,
+            })),
+            action(Action::MinimizeWindow(proto::MinimizeWindow { window: xterm() })),
+            action(Action::CloseWindow(proto::CloseWindow { window: xterm() })),
+            action(Action::WaitForWindow(proto::WaitForWindow {
+                window: xterm(),
+                timeout_ms: 3000,
+            })),
+            action(Action::WaitForElement(proto::WaitForElement {
+                selector: selector.clone(),
+                state: Some("enabled".into()),
+                timeout_ms: 1000,
+                anchor: None,
+            })),
+            action(Action::ClickElement(proto::ClickElement {
+                selector: selector.clone(),
+                timeout_ms: 1000,
+            })),
+            action(Action::SetElementText(proto::SetElementText {
+                selector,
+                text: "notes".into(),
+                timeout_ms: 1000,
+            })),
+            action(Action::Parallel(proto::Parallel {
+                actions: vec![action(Action::Delay(proto::Delay { milliseconds: 1 }))],
+            })),
+        ];
+        let converted = InputAction::try_from(action(Action::Sequence(proto::Sequence { actions }))).unwrap();
+
+        let target = json!({"window": {"title": "xterm"}, "delivery": "Focus"});
+        let xterm = json!({"title": "xterm"});
+        let region = json!({"x": 1, "y": 2, "width": 3, "height": 4});
+        let selector = json!({"application": "gedit", "role": "push button"});
+        let expected = json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseMove", "params": {"x": 1, "y": 2, "origin": {"Window": {"id": 7}}, "target": target}},
+            {"type": "MouseClick", "params": {"button": "Left", "x": 3, "y": 4, "origin": {"Anchor": "label"}}},
+            {"type": "MouseDoubleClick", "params": {"button": "Left", "x": 5, "y": 6, "origin": "Screen"}},
+            {"type": "MousePress", "params": {"button": "Left", "x": 7, "y": 8, "duration_ms": 100}},
+            {"type": "MouseDown", "params": {"button": "Left", "target": target}},
+            {"type": "MouseUp", "params": {"button": "Left"}},
+            {"type": "MouseDrag", "params": {
+                "button": "Left", "start_x": 1, "start_y": 2, "end_x": 3, "end_y": 4, "duration_ms": 200,
+                "origin": {"Window": {"id": 7}}
+            }},
+            {"type": "MouseScroll", "params": {"delta_x": -1, "delta_y": 3}},
+            {"type": "KeyPress", "params": {"key": "Enter", "location": "Numpad", "target": target}},
+            {"type": "KeyDown", "params": {"key": "Shift", "location": "Left"}},
+            {"type": "KeyUp", "params": {"key": "Shift"}},
+            {"type": "KeySequence", "params": {"keys": ["A", {"Other": 65}], "key_delay_ms": 30}},
+            {"type": "TypeText", "params": {"text": "hello", "target": target}},
+            {"type": "Hotkey", "params": {"modifiers": ["Ctrl", "Alt"], "key": "Delete", "location": "Right"}},
+            {"type": "Delay", "params": {"milliseconds": 250}},
+            {"type": "WaitForPixel", "params": {
+                "x": 1, "y": 2, "color": {"r": 255, "g": 128, "b": 0}, "tolerance": 10, "timeout_ms": 1000
+            }},
+            {"type": "WaitForScreenStable", "params": {"region": region, "quiet_ms": 300, "timeout_ms": 2000}},
+            {"type": "WaitForChange", "params": {"timeout_ms": 500}},
+            {"type": "WaitForText", "params": {"text": "Save", "region": region, "timeout_ms": 1000, "anchor": "save"}},
+            {"type": "ClickText", "params": {"text": "OK", "button": "Left", "timeout_ms": 1000}},
+            {"type": "WaitForImage", "params": {
+                "image": {"path": "button.png"}, "region": region, "threshold": 0.5, "timeout_ms": 1000, "anchor": "button"
+            }},
+            {"type": "ClickImage", "params": {
+                "image": {"base64": "AQID"}, "threshold": 0.25, "button": "Left", "timeout_ms": 1000
+            }},
+            {"type": "FocusWindow", "params": {"window": xterm}},
+            {"type": "MoveWindow", "params": {"window": xterm, "x": 10, "y": -20}},
+            {"type": "ResizeWindow", "params": {"window": xterm, "width": 640, "height": 480}},
+            {"type": "MinimizeWindow", "params": {"window": xterm}},
+            {"type": "CloseWindow", "params": {"window": xterm}},
+            {"type": "WaitForWindow", "params": {"window": xterm, "timeout_ms": 3000}},
+            {"type": "WaitForElement", "params": {"selector": selector, "state": "enabled", "timeout_ms": 1000}},
+            {"type": "ClickElement", "params": {"selector": selector, "timeout_ms": 1000}},
+            {"type": "SetElementText", "params": {"selector": selector, "text": "notes", "timeout_ms": 1000}},
+            {"type": "Parallel", "params": {"actions": [{"type": "Delay", "params": {"milliseconds": 1}}]}}
+        ]}});
+        let expected = serde_json::from_value::<InputAction>(expected).unwrap();
+        assert_eq!(serde_json::to_value(&converted).unwrap(), serde_json::to_value(&expected).unwrap());
+
+        // 每种动作都覆盖到
+        let InputAction::Sequence { actions } = &converted else {
+            panic!("{:?}", converted);
+        };
+        let names: BTreeSet<String> = actions
+            .iter()
+            .map(|action| action.name().to_string())
+            .chain(["Sequence".to_string()])
+            .collect();
+        let all: BTreeSet<String> = schemars::schema_for!(InputAction).as_value()["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["properties"]["type"]["const"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, all);
+    }
+
+    #[test]
+    fn rejects_missing_and_out_of_range_fields() {
+        let convert = |a| InputAction::try_from(action(a));
+        assert_eq!(status(InputAction::try_from(proto::InputAction { action: None })), "missing field `action`");
+        assert_eq!(
+            status(convert(Action::MouseClick(proto::MouseClick::default()))),
+            "missing field `button`"
+        );
+        assert_eq!(
+            status(convert(Action::KeyPress(proto::KeyPress {
+                key: Some(code(proto::KeyCode::A)),
+                location: 9,
+                target: None,
+            }))),
+            "invalid `location`: 9"
+        );
+        let pixel = |color, tolerance| {
+            convert(Action::WaitForPixel(proto::WaitForPixel {
+                color,
+                tolerance,
+                ..Default::default()
+            }))
+        };
+        assert_eq!(status(pixel(None, 0)), "missing field `color`");
+        assert_eq!(
+            status(pixel(Some(proto::Color { r: 0, g: 256, b: 0 }), 0)),
+            "invalid `color.g`: 256"
+        );
+        assert_eq!(status(pixel(Some(proto::Color::default()), 300)), "invalid `tolerance`: 300");
+        assert_eq!(
+            status(convert(Action::MouseDown(proto::MouseDown {
+                button: left(),
+                target: Some(proto::InputTarget {
+                    window: None,
+                    delivery: 0,
+                }),
+            }))),
+            "missing field `target.window`"
+        );
+        assert_eq!(
+            status(convert(Action::MouseMove(proto::MouseMove {
+                origin: Some(proto::Origin { origin: None }),
+                ..Default::default()
+            }))),
+            "missing field `origin`"
+        );
+        assert_eq!(
+            status(convert(Action::WaitForImage(proto::WaitForImage {
+                image: Some(proto::ImageSource { source: None }),
+                ..Default::default()
+            }))),
+            "missing field `image.source`"
+        );
+        assert_eq!(
+            status(convert(Action::Sequence(proto::Sequence {
+                actions: vec![action(Action::Delay(proto::Delay::default())), proto::InputAction { action: None }],
+            }))),
+            "missing field `action`"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use enigo::{Axis, Button, Coordinate, Direction, InputResult, Key as EnigoKey, Keyboard, Mouse};
+use image::RgbaImage;
+use tokio::time::Duration;
+
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::backend::InputBackend;
+use crate::error::AutomationError;
+use crate::screen::Rect;
+use crate::window::{WindowCommand, WindowEvent, WindowInfo, WindowSelector};
+
+/// 当前处于按下状态的键和鼠标按键（按按下顺序）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeldInputs {
+    pub keys: Vec<EnigoKey>,
+    pub raw_keys: Vec<u16>,
+    pub buttons: Vec<Button>,
+}
+
+impl HeldInputs {
+    /// 是否没有任何按下的输入
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.raw_keys.is_empty() && self.buttons.is_empty()
+    }
+
+    /// 按与按下相反的顺序释放全部输入，返回被释放输入的描述
+    pub fn release<E: Keyboard + Mouse + ?Sized>(self, enigo: &mut E) -> Result<Vec<String>, AutomationError> {
+        let mut released = Vec::new();
+        let mut first_error = None;
+        for button in self.buttons.into_iter().rev() {
+            match enigo.button(button, Direction::Release) {
+                Ok(()) => released.push(format!("{:?} button", button)),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        for keycode in self.raw_keys.into_iter().rev() {
+            match enigo.raw(keycode, Direction::Release) {
+                Ok(()) => released.push(format!("keycode {}", keycode)),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        for key in self.keys.into_iter().rev() {
+            match enigo.key(key, Direction::Release) {
+                Ok(()) => released.push(format!("{:?} key", key)),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        match first_error {
+            Some(err) => Err(AutomationError::Platform(format!("failed to release held input: {}", err))),
+            None => Ok(released),
+        }
+    }
+
+    pub(crate) fn update<T: PartialEq>(held: &mut Vec<T>, value: T, direction: Direction) {
+        match direction {
+            Direction::Press => {
+                if !held.contains(&value) {
+                    held.push(value);
+                }
+            }
+            Direction::Release => held.retain(|held| *held != value),
+            Direction::Click => {}
+        }
+    }
+}
+
+/// 记录按下状态的输入后端包装，便于中断或出错后释放仍被按住的键
+pub struct HeldInputsBackend<'a, B: InputBackend> {
+    inner: &'a mut B,
+    held: Arc<Mutex<HeldInputs>>,
+}
+
+impl<'a, B: InputBackend> HeldInputsBackend<'a, B> {
+    pub fn new(inner: &'a mut B, held: Arc<Mutex<HeldInputs>>) -> Self {
+        HeldInputsBackend { inner, held }
+    }
+
+    fn held(&self) -> std::sync::MutexGuard<'_, HeldInputs> {
+        self.held.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<B: InputBackend> Keyboard for HeldInputsBackend<'_, B> {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.inner.fast_text(text)
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.inner.key(key, direction)?;
+        HeldInputs::update(&mut self.held().keys, key, direction);
+        Ok(())
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.inner.raw(keycode, direction)?;
+        HeldInputs::update(&mut self.held().raw_keys, keycode, direction);
+        Ok(())
+    }
+}
+
+impl<B: InputBackend> Mouse for HeldInputsBackend<'_, B> {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.inner.button(button, direction)?;
+        HeldInputs::update(&mut self.held().buttons, button, direction);
+        Ok(())
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.inner.move_mouse(x, y, coordinate)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.inner.scroll(length, axis)
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        self.inner.main_display()
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.inner.location()
+    }
+}
+
+impl<B: InputBackend> InputBackend for HeldInputsBackend<'_, B> {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        self.inner.wait(duration)
+    }
+
+    fn begin_input(&mut self) -> impl Future<Output = ()> + Send {
+        self.inner.begin_input()
+    }
+
+    fn end_input(&mut self) {
+        self.inner.end_input()
+    }
+
+    fn reset_timeline(&mut self) {
+        self.inner.reset_timeline()
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        self.inner.capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        self.inner.windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        self.inner.window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        self.inner.send_window_event(window, event)
+    }
+
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        self.inner.find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        self.inner.element_command(selector, command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dry_run::{DryRunBackend, InputEvent};
+
+    fn inputs(backend: &DryRunBackend) -> Vec<InputEvent> {
+        backend.events().iter().map(|event| event.event.clone()).collect()
+    }
+
+    #[test]
+    fn backend_tracks_pressed_inputs() {
+        let held = Arc::new(Mutex::new(HeldInputs::default()));
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let mut backend = HeldInputsBackend::new(&mut dry_run, held.clone());
+        backend.key(EnigoKey::Shift, Direction::Press).unwrap();
+        backend.key(EnigoKey::Control, Direction::Press).unwrap();
+        backend.key(EnigoKey::Control, Direction::Press).unwrap();
+        backend.key(EnigoKey::Unicode('a'), Direction::Click).unwrap();
+        backend.raw(38, Direction::Press).unwrap();
+        backend.button(Button::Left, Direction::Press).unwrap();
+        backend.button(Button::Right, Direction::Click).unwrap();
+        backend.key(EnigoKey::Shift, Direction::Release).unwrap();
+
+        let held = held.lock().unwrap().clone();
+        assert_eq!(
+            held,
+            HeldInputs {
+                keys: vec![EnigoKey::Control],
+                raw_keys: vec![38],
+                buttons: vec![Button::Left],
+            }
+        );
+        assert!(!held.is_empty());
+        assert_eq!(inputs(&dry_run).len(), 8);
+    }
+
+    #[test]
+    fn release_goes_in_reverse_order() {
+        let held = HeldInputs {
+            keys: vec![EnigoKey::Shift, EnigoKey::Control],
+            raw_keys: vec![38],
+            buttons: vec![Button::Left, Button::Right],
+        };
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let released = held.release(&mut dry_run).unwrap();
+        assert_eq!(
+            released,
+            vec!["Right button", "Left button", "keycode 38", "Control key", "Shift key"]
+        );
+        let release = Direction::Release;
+        assert_eq!(
+            inputs(&dry_run),
+            vec![
+                InputEvent::MouseButton { button: Button::Right, direction: release },
+                InputEvent::MouseButton { button: Button::Left, direction: release },
+                InputEvent::RawKey { keycode: 38, direction: release },
+                InputEvent::Key { key: EnigoKey::Control, direction: release },
+                InputEvent::Key { key: EnigoKey::Shift, direction: release },
+            ]
+        );
+        assert!(HeldInputs::default().release(&mut dry_run).unwrap().is_empty());
+    }
+}
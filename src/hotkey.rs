@@ -0,0 +1,271 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::Key;
+
+/// 全局热键：修饰键加主键
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    /// 修饰键（Shift、Ctrl、Alt、Meta）
+    #[serde(default)]
+    pub modifiers: Vec<Key>,
+    /// 主键
+    pub key: Key,
+}
+
+impl Hotkey {
+    pub fn new(modifiers: Vec<Key>, key: Key) -> Self {
+        Hotkey { modifiers, key }
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{:?}+", modifier)?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use self::x11::KeyGrabs;
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::connection::Connection;
+    use x11rb::errors::ReplyError;
+    use x11rb::protocol::xproto::{ConnectionExt as _, GrabMode, KeyPressEvent, ModMask, Window};
+    use x11rb::protocol::ErrorKind;
+
+    use super::Hotkey;
+    use crate::enums::Key;
+    use crate::error::AutomationError;
+    use crate::recorder::Keymap;
+
+    /// 参与匹配的修饰键：Shift、Ctrl、Alt、Meta
+    const MODIFIERS: [ModMask; 4] = [ModMask::SHIFT, ModMask::CONTROL, ModMask::M1, ModMask::M4];
+
+    /// CapsLock 和 NumLock 不影响热键，抓取时需要覆盖它们的所有组合
+    fn ignored() -> [ModMask; 4] {
+        [ModMask::from(0u16), ModMask::LOCK, ModMask::M2, ModMask::LOCK | ModMask::M2]
+    }
+
+    /// 抓取 modifiers 需要使用的全部修饰键状态；extra 为 true 时还包括多按了其他修饰键的组合
+    pub(super) fn masks(modifiers: ModMask, extra: bool) -> Vec<ModMask> {
+        let extras: Vec<ModMask> = if extra {
+            let free: Vec<ModMask> = MODIFIERS.into_iter().filter(|mask| !modifiers.contains(*mask)).collect();
+            (0..1usize << free.len())
+                .map(|bits| {
+                    free.iter()
+                        .enumerate()
+                        .filter(|(i, _)| bits & (1 << i) != 0)
+                        .fold(ModMask::from(0u16), |mask, (_, free)| mask | *free)
+                })
+                .collect()
+        } else {
+            vec![ModMask::from(0u16)]
+        };
+        extras
+            .into_iter()
+            .flat_map(|extra| ignored().map(|ignored| modifiers | extra | ignored))
+            .collect()
+    }
+
+    struct Grab<T> {
+        keycodes: Vec<u8>,
+        modifiers: ModMask,
+        /// 实际抓取的修饰键状态
+        masks: Vec<ModMask>,
+        /// 多按了其他修饰键时同样匹配
+        extra: bool,
+        value: T,
+    }
+
+    /// 按下时的修饰键 pressed 是否匹配热键的 modifiers：extra 为 true 时只要求包含热键的修饰键
+    pub(super) fn modifiers_match(modifiers: ModMask, extra: bool, pressed: ModMask) -> bool {
+        if extra {
+            modifiers & pressed == modifiers
+        } else {
+            modifiers == pressed
+        }
+    }
+
+    /// 通过 XGrabKey 在根窗口上抓取的全局热键，value 标识热键对应的用途
+    pub(crate) struct KeyGrabs<T> {
+        root: Window,
+        keymap: Keymap,
+        grabs: Vec<Grab<T>>,
+    }
+
+    impl<T: PartialEq> KeyGrabs<T> {
+        pub(crate) fn new(conn: &impl Connection, screen: usize) -> Result<Self, AutomationError> {
+            Ok(KeyGrabs {
+                root: conn.setup().roots[screen].root,
+                keymap: Keymap::query(conn)?,
+                grabs: Vec::new(),
+            })
+        }
+
+        /// 根窗口，热键事件都发送到这里
+        pub(crate) fn root(&self) -> Window {
+            self.root
+        }
+
+        /// 抓取热键，其他客户端已抓取同一组合时返回错误
+        pub(crate) fn grab(&mut self, conn: &impl Connection, hotkey: &Hotkey, value: T) -> Result<(), AutomationError> {
+            self.grab_masks(conn, hotkey, value, false)
+        }
+
+        /// 抓取热键，同时按住其他修饰键时也触发（用于紧急停止，不应因为多按了修饰键而失效）
+        pub(crate) fn grab_any_modifiers(
+            &mut self,
+            conn: &impl Connection,
+            hotkey: &Hotkey,
+            value: T,
+        ) -> Result<(), AutomationError> {
+            self.grab_masks(conn, hotkey, value, true)
+        }
+
+        fn grab_masks(
+            &mut self,
+            conn: &impl Connection,
+            hotkey: &Hotkey,
+            value: T,
+            extra: bool,
+        ) -> Result<(), AutomationError> {
+            let modifiers = hotkey
+                .modifiers
+                .iter()
+                .map(|modifier| modifier_mask(*modifier))
+                .try_fold(ModMask::from(0u16), |mask, modifier| {
+                    modifier
+                        .map(|modifier| mask | modifier)
+                        .ok_or_else(|| AutomationError::Platform(format!("{} uses a key that is not a modifier", hotkey)))
+                })?;
+            let keycodes = self.keymap.keycodes(hotkey.key);
+            if keycodes.is_empty() {
+                return Err(AutomationError::Platform(format!(
+                    "{:?} in {} has no keycode in the current keyboard mapping",
+                    hotkey.key, hotkey
+                )));
+            }
+            let masks = masks(modifiers, extra);
+            let mut grabbed = Vec::new();
+            for &keycode in &keycodes {
+                for &mask in &masks {
+                    // 同一连接中的其他用途已经抓取了这个组合
+                    if self.grabs.iter().any(|grab| grab.keycodes.contains(&keycode) && grab.masks.contains(&mask)) {
+                        continue;
+                    }
+                    let result = conn
+                        .grab_key(true, self.root, mask, keycode, GrabMode::ASYNC, GrabMode::ASYNC)?
+                        .check();
+                    if let Err(err) = result {
+                        for (keycode, mask) in grabbed {
+                            let _ = conn.ungrab_key(keycode, self.root, mask);
+                        }
+                        let _ = conn.flush();
+                        return Err(match err {
+                            ReplyError::X11Error(error) if error.error_kind == ErrorKind::Access => {
+                                AutomationError::Platform(format!("{} is already grabbed by another client", hotkey))
+                            }
+                            err => err.into(),
+                        });
+                    }
+                    grabbed.push((keycode, mask));
+                }
+            }
+            conn.flush()?;
+            self.grabs.push(Grab {
+                keycodes,
+                modifiers,
+                masks,
+                extra,
+                value,
+            });
+            Ok(())
+        }
+
+        /// 释放 value 对应的全部热键
+        pub(crate) fn ungrab(&mut self, conn: &impl Connection, value: &T) -> Result<(), AutomationError> {
+            let (released, kept) = std::mem::take(&mut self.grabs)
+                .into_iter()
+                .partition(|grab| grab.value == *value);
+            self.grabs = kept;
+            for grab in released {
+                for &keycode in &grab.keycodes {
+                    for &mask in &grab.masks {
+                        // 同一组合可能还被其他用途使用，这时保留抓取
+                        if self.grabs.iter().any(|other| other.keycodes.contains(&keycode) && other.masks.contains(&mask)) {
+                            continue;
+                        }
+                        conn.ungrab_key(keycode, self.root, mask)?;
+                    }
+                }
+            }
+            conn.flush()?;
+            Ok(())
+        }
+
+        /// 按下事件对应的全部用途
+        pub(crate) fn matches<'a>(&'a self, event: &'a KeyPressEvent) -> impl Iterator<Item = &'a T> + 'a {
+            // 只比较参与匹配的修饰键，忽略 CapsLock、NumLock 和鼠标按键状态
+            let modifiers = ModMask::from(u16::from(event.state))
+                & (ModMask::SHIFT | ModMask::CONTROL | ModMask::M1 | ModMask::M4);
+            self.grabs
+                .iter()
+                .filter(move |grab| grab.keycodes.contains(&event.detail) && modifiers_match(grab.modifiers, grab.extra, modifiers))
+                .map(|grab| &grab.value)
+        }
+    }
+
+    fn modifier_mask(key: Key) -> Option<ModMask> {
+        match key {
+            Key::Shift => Some(ModMask::SHIFT),
+            Key::Ctrl => Some(ModMask::CONTROL),
+            Key::Alt => Some(ModMask::M1),
+            Key::Meta => Some(ModMask::M4),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use x11rb::protocol::xproto::ModMask;
+
+    use super::x11::{masks, modifiers_match};
+
+    #[test]
+    fn extra_modifier_grabs_cover_every_combination() {
+        let hotkey = ModMask::CONTROL | ModMask::M1;
+        let exact = masks(hotkey, false);
+        assert_eq!(exact.len(), 4);
+        assert!(exact.iter().all(|mask| *mask & (ModMask::SHIFT | ModMask::M4) == ModMask::from(0u16)));
+
+        let extra = masks(hotkey, true);
+        assert_eq!(extra.len(), 16);
+        for mask in [
+            hotkey,
+            hotkey | ModMask::SHIFT,
+            hotkey | ModMask::M4 | ModMask::LOCK,
+            hotkey | ModMask::SHIFT | ModMask::M4 | ModMask::M2,
+        ] {
+            assert!(extra.contains(&mask), "{:?}", mask);
+        }
+        assert!(extra.iter().all(|mask| mask.contains(hotkey)));
+    }
+
+    #[test]
+    fn extra_modifiers_match_as_a_superset() {
+        let hotkey = ModMask::CONTROL | ModMask::M1;
+        assert!(modifiers_match(hotkey, false, hotkey));
+        assert!(!modifiers_match(hotkey, false, hotkey | ModMask::SHIFT));
+        assert!(modifiers_match(hotkey, true, hotkey | ModMask::SHIFT));
+        assert!(modifiers_match(hotkey, true, hotkey | ModMask::SHIFT | ModMask::M4));
+        assert!(!modifiers_match(hotkey, true, ModMask::CONTROL | ModMask::SHIFT));
+    }
+}
@@ -0,0 +1,556 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+use crate::enums::InputAction;
+use crate::events::{EventHub, ExecutionEvent, LiveEvent};
+use crate::executor::{ActionExecutor, ExecutionReport};
+
+/// 任务编号，从 1 开始递增
+pub type JobId = u64;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// 是否已经结束（成功、失败或取消）
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// 任务进度，按叶子动作（Sequence 以外的动作）计数
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct JobProgress {
+    pub completed: usize,
+    pub total: usize,
+    /// 正在执行的动作路径，如 `$.actions[2]`
+    pub current: Option<String>,
+}
+
+/// 任务信息
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub status: JobStatus,
+    /// 优先级，数值大的先执行，相同优先级按提交顺序执行
+    pub priority: i32,
+    /// 提交任务的调用方名称（见 `Principal`），进程内提交（如触发器）的任务为空
+    pub owner: Option<String>,
+    /// 提交、开始和结束时间（Unix 毫秒）
+    pub submitted_at_ms: u64,
+    pub started_at_ms: Option<u64>,
+    pub finished_at_ms: Option<u64>,
+    pub progress: JobProgress,
+    /// 成功时的执行报告
+    pub report: Option<ExecutionReport>,
+    /// 失败或取消的原因
+    pub error: Option<String>,
+}
+
+/// 任务队列选项
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct JobQueueOptions {
+    /// 保留多少个已结束任务的记录，超出时丢弃最早结束的
+    pub history_limit: usize,
+    /// 最多允许多少个任务排队等待
+    pub max_queued: usize,
+}
+
+impl Default for JobQueueOptions {
+    fn default() -> Self {
+        JobQueueOptions {
+            history_limit: 100,
+            max_queued: 1000,
+        }
+    }
+}
+
+/// 任务队列错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobError {
+    /// 任务不存在（或记录已被丢弃）
+    NotFound(JobId),
+    /// 任务已经结束，无法取消
+    AlreadyFinished(JobId),
+    /// 排队任务数达到上限
+    QueueFull(usize),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::NotFound(id) => write!(f, "job {} not found", id),
+            JobError::AlreadyFinished(id) => write!(f, "job {} has already finished", id),
+            JobError::QueueFull(limit) => write!(f, "job queue is full ({} queued)", limit),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+/// 串行执行任务的优先级队列
+///
+/// 任务按优先级（相同时按提交顺序）逐个交给同一个 `ActionExecutor` 执行；
+/// 取消正在执行的任务时会中断执行并释放仍被按住的键和鼠标按键
+#[derive(Clone)]
+pub struct JobQueue {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    executor: Arc<ActionExecutor>,
+    options: JobQueueOptions,
+    events: EventHub,
+    state: Mutex<QueueState>,
+    notify: Notify,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_id: JobId,
+    jobs: HashMap<JobId, Job>,
+    queued: BinaryHeap<Queued>,
+    /// 已结束任务的编号（按结束顺序），用于丢弃过旧的记录
+    finished: VecDeque<JobId>,
+    /// 正在执行的任务及其取消信号
+    running: Option<(JobId, oneshot::Sender<()>)>,
+}
+
+struct Job {
+    info: JobInfo,
+    /// 开始执行时取出
+    action: Option<InputAction>,
+}
+
+#[derive(PartialEq, Eq)]
+struct Queued {
+    priority: i32,
+    id: JobId,
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl JobQueue {
+    /// 创建队列并在当前 tokio 运行时中启动执行任务的后台任务
+    pub fn start(executor: Arc<ActionExecutor>, options: JobQueueOptions) -> Self {
+        Self::with_events(executor, options, EventHub::default())
+    }
+
+    /// 同 `start`，任务状态变化和执行事件发布到 events
+    pub fn with_events(executor: Arc<ActionExecutor>, options: JobQueueOptions, events: EventHub) -> Self {
+        let shared = Arc::new(Shared {
+            executor,
+            options,
+            events,
+            state: Mutex::new(QueueState {
+                next_id: 1,
+                ..Default::default()
+            }),
+            notify: Notify::new(),
+        });
+        tokio::spawn(work(shared.clone()));
+        JobQueue { shared }
+    }
+
+    /// 提交任务，返回排队中的任务信息
+    pub fn submit(&self, action: InputAction, priority: i32) -> Result<JobInfo, JobError> {
+        self.submit_with_owner(action, priority, None)
+    }
+
+    /// 代表远程调用方提交任务，记录为任务的 owner
+    pub fn submit_as(&self, owner: &str, action: InputAction, priority: i32) -> Result<JobInfo, JobError> {
+        self.submit_with_owner(action, priority, Some(owner.to_string()))
+    }
+
+    fn submit_with_owner(&self, action: InputAction, priority: i32, owner: Option<String>) -> Result<JobInfo, JobError> {
+        let mut state = self.shared.lock();
+        let queued = state
+            .jobs
+            .values()
+            .filter(|job| job.info.status == JobStatus::Queued)
+            .count();
+        if queued >= self.shared.options.max_queued {
+            return Err(JobError::QueueFull(queued));
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        let info = JobInfo {
+            id,
+            status: JobStatus::Queued,
+            priority,
+            owner,
+            submitted_at_ms: now_ms(),
+            started_at_ms: None,
+            finished_at_ms: None,
+            progress: JobProgress {
+                total: count_steps(&action),
+                ..Default::default()
+            },
+            report: None,
+            error: None,
+        };
+        state.jobs.insert(
+            id,
+            Job {
+                info: info.clone(),
+                action: Some(action),
+            },
+        );
+        state.queued.push(Queued { priority, id });
+        drop(state);
+
+        self.shared.events.publish(LiveEvent::Job(info.clone()));
+        self.shared.notify.notify_one();
+        Ok(info)
+    }
+
+    /// 查询任务
+    pub fn get(&self, id: JobId) -> Result<JobInfo, JobError> {
+        self.shared
+            .lock()
+            .jobs
+            .get(&id)
+            .map(|job| job.info.clone())
+            .ok_or(JobError::NotFound(id))
+    }
+
+    /// 任务状态变化和执行事件的广播
+    pub fn events(&self) -> &EventHub {
+        &self.shared.events
+    }
+
+    /// 列出所有排队中、执行中和保留的已结束任务（按编号）
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<_> = self.shared.lock().jobs.values().map(|job| job.info.clone()).collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// 取消任务：排队中的任务直接标记为取消，执行中的任务会被中断
+    pub fn cancel(&self, id: JobId) -> Result<JobInfo, JobError> {
+        let mut state = self.shared.lock();
+        let info = state.jobs.get(&id).ok_or(JobError::NotFound(id))?.info.clone();
+        match info.status {
+            JobStatus::Queued => {
+                state.finish(id, JobStatus::Cancelled, None, Some("cancelled before start".to_string()));
+                let info = state.jobs[&id].info.clone();
+                state.trim(self.shared.options.history_limit);
+                drop(state);
+                self.shared.events.publish(LiveEvent::Job(info.clone()));
+                Ok(info)
+            }
+            JobStatus::Running => {
+                if state.running.as_ref().is_some_and(|(running, _)| *running == id)
+                    && let Some((_, cancel)) = state.running.take()
+                {
+                    let _ = cancel.send(());
+                }
+                Ok(info)
+            }
+            _ => Err(JobError::AlreadyFinished(id)),
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 取出下一个排队中的任务并标记为执行中
+    fn next_job(&self) -> Option<(JobId, InputAction, oneshot::Receiver<()>)> {
+        let mut state = self.lock();
+        while let Some(Queued { id, .. }) = state.queued.pop() {
+            let Some(job) = state.jobs.get_mut(&id) else {
+                continue;
+            };
+            // 已取消的任务仍留在堆中，跳过即可
+            if job.info.status != JobStatus::Queued {
+                continue;
+            }
+            let Some(action) = job.action.take() else {
+                continue;
+            };
+            job.info.status = JobStatus::Running;
+            job.info.started_at_ms = Some(now_ms());
+            let info = job.info.clone();
+            let (cancel, cancelled) = oneshot::channel();
+            state.running = Some((id, cancel));
+            drop(state);
+            self.events.publish(LiveEvent::Job(info));
+            return Some((id, action, cancelled));
+        }
+        None
+    }
+
+    fn progress(&self, id: JobId, event: ExecutionEvent) {
+        self.events.publish(LiveEvent::Action {
+            job: Some(id),
+            event: event.clone(),
+        });
+        let mut state = self.lock();
+        let Some(job) = state.jobs.get_mut(&id) else {
+            return;
+        };
+        let progress = &mut job.info.progress;
+        match event {
+            ExecutionEvent::ActionStarted { path, action } if action != "Sequence" => {
+                progress.current = Some(path);
+            }
+            ExecutionEvent::ActionFinished { action, .. } if action != "Sequence" => {
+                progress.completed += 1;
+                progress.current = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&self, id: JobId, status: JobStatus, report: Option<ExecutionReport>, error: Option<String>) {
+        let mut state = self.lock();
+        if state.running.as_ref().is_some_and(|(running, _)| *running == id) {
+            state.running = None;
+        }
+        state.finish(id, status, report, error);
+        let info = state.jobs.get(&id).map(|job| job.info.clone());
+        state.trim(self.options.history_limit);
+        drop(state);
+        if let Some(info) = info {
+            self.events.publish(LiveEvent::Job(info));
+        }
+    }
+}
+
+impl QueueState {
+    fn finish(&mut self, id: JobId, status: JobStatus, report: Option<ExecutionReport>, error: Option<String>) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.info.status = status;
+            job.info.finished_at_ms = Some(now_ms());
+            job.info.progress.current = None;
+            job.info.report = report;
+            job.info.error = error;
+            job.action = None;
+            self.finished.push_back(id);
+        }
+    }
+
+    /// 丢弃超出保留数量的已结束任务
+    fn trim(&mut self, history_limit: usize) {
+        while self.finished.len() > history_limit {
+            if let Some(id) = self.finished.pop_front() {
+                self.jobs.remove(&id);
+            }
+        }
+    }
+}
+
+/// 后台任务：逐个执行排队中的任务
+async fn work(shared: Arc<Shared>) {
+    loop {
+        let Some((id, action, mut cancelled)) = shared.next_job() else {
+            shared.notify.notified().await;
+            continue;
+        };
+
+        let (events, mut received) = mpsc::unbounded_channel();
+        let outcome = {
+            let run = shared.executor.execute_observed(&action, events);
+            tokio::pin!(run);
+            loop {
+                tokio::select! {
+                    result = &mut run => break Some(result),
+                    _ = &mut cancelled => break None,
+                    Some(event) = received.recv() => shared.progress(id, event),
+                }
+            }
+        };
+        while let Ok(event) = received.try_recv() {
+            shared.progress(id, event);
+        }
+
+        match outcome {
+            Some(Ok(report)) => shared.finish(id, JobStatus::Succeeded, Some(report), None),
+            Some(Err(err)) => shared.finish(id, JobStatus::Failed, None, Some(err.to_string())),
+            None => {
+                // 执行被中断时可能还有按住的键
+                let error = match shared.executor.release_all().await {
+                    Ok(_) => "cancelled".to_string(),
+                    Err(err) => format!("cancelled ({})", err),
+                };
+                shared.finish(id, JobStatus::Cancelled, None, Some(error));
+            }
+        }
+    }
+}
+
+/// 统计动作树中的叶子动作数量
+fn count_steps(action: &InputAction) -> usize {
+    match action {
+        InputAction::Sequence { actions } => actions.iter().map(count_steps).sum(),
+        _ => 1,
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::InputSimulator;
+    use serde_json::json;
+    use tokio::sync::broadcast;
+    use tokio::time::{timeout, Duration};
+
+    fn action(value: serde_json::Value) -> InputAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn delay(milliseconds: u64) -> InputAction {
+        action(json!({"type": "Delay", "params": {"milliseconds": milliseconds}}))
+    }
+
+    /// 没有输入设备的队列：任务会立即以失败结束，足以检查调度顺序和记录
+    fn detached_queue(options: JobQueueOptions) -> JobQueue {
+        let executor = ActionExecutor::with_simulator(InputSimulator::detached((800, 600)));
+        JobQueue::start(Arc::new(executor), options)
+    }
+
+    /// 等待 count 个任务结束，返回按结束顺序排列的任务
+    async fn finished(events: &mut broadcast::Receiver<LiveEvent>, count: usize) -> Vec<JobInfo> {
+        let mut finished = Vec::new();
+        while finished.len() < count {
+            let event = timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+            if let LiveEvent::Job(info) = event
+                && info.status.is_finished()
+            {
+                finished.push(info);
+            }
+        }
+        finished
+    }
+
+    #[tokio::test]
+    async fn runs_jobs_by_priority_then_submission_order() {
+        let queue = detached_queue(JobQueueOptions::default());
+        let mut events = queue.events().subscribe();
+        // 后台任务在测试第一次让出执行权之前不会开始，所以这些任务同时在排队
+        let low = queue.submit(delay(1), 0).unwrap();
+        let high = queue.submit(delay(1), 5).unwrap();
+        let high_later = queue.submit(delay(1), 5).unwrap();
+        let negative = queue.submit(delay(1), -1).unwrap();
+        assert_eq!(low.status, JobStatus::Queued);
+        assert_eq!((low.id, high.id), (1, 2));
+
+        let order: Vec<JobId> = finished(&mut events, 4).await.iter().map(|job| job.id).collect();
+        assert_eq!(order, vec![high.id, high_later.id, low.id, negative.id]);
+        let job = queue.get(high.id).unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.owner, None);
+        assert!(job.started_at_ms.is_some() && job.finished_at_ms.is_some());
+        assert!(job.error.unwrap().contains("no input device"));
+    }
+
+    #[tokio::test]
+    async fn cancels_queued_jobs() {
+        let queue = detached_queue(JobQueueOptions::default());
+        let mut events = queue.events().subscribe();
+        let first = queue.submit(delay(1), 0).unwrap();
+        let second = queue.submit_as("ci", delay(1), 0).unwrap();
+        assert_eq!(second.owner.as_deref(), Some("ci"));
+
+        let cancelled = queue.cancel(second.id).unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.error.as_deref(), Some("cancelled before start"));
+        assert_eq!(queue.cancel(second.id), Err(JobError::AlreadyFinished(second.id)));
+        assert_eq!(queue.cancel(99), Err(JobError::NotFound(99)));
+
+        let order: Vec<_> = finished(&mut events, 2).await.iter().map(|job| (job.id, job.status)).collect();
+        assert_eq!(order, vec![(second.id, JobStatus::Cancelled), (first.id, JobStatus::Failed)]);
+    }
+
+    #[tokio::test]
+    async fn limits_queue_length_and_trims_history() {
+        let queue = detached_queue(JobQueueOptions {
+            history_limit: 2,
+            max_queued: 3,
+        });
+        let mut events = queue.events().subscribe();
+        for _ in 0..3 {
+            queue.submit(delay(1), 0).unwrap();
+        }
+        assert_eq!(queue.submit(delay(1), 0), Err(JobError::QueueFull(3)));
+        finished(&mut events, 3).await;
+        queue.submit(delay(1), 0).unwrap();
+        finished(&mut events, 1).await;
+
+        let ids: Vec<JobId> = queue.list().iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec![3, 4]);
+        assert_eq!(queue.get(1), Err(JobError::NotFound(1)));
+    }
+
+    #[tokio::test]
+    async fn counts_progress_and_cancels_running_jobs() {
+        if !crate::testing::display_available() {
+            return;
+        }
+        let queue = JobQueue::start(Arc::new(ActionExecutor::new()), JobQueueOptions::default());
+        let mut events = queue.events().subscribe();
+        let job = queue
+            .submit(action(json!({"type": "Sequence", "params": {"actions": [
+                {"type": "Delay", "params": {"milliseconds": 10}},
+                {"type": "Sequence", "params": {"actions": [
+                    {"type": "Delay", "params": {"milliseconds": 10}},
+                    {"type": "Delay", "params": {"milliseconds": 5000}}
+                ]}}
+            ]}})), 0)
+            .unwrap();
+        assert_eq!(job.progress.total, 3);
+
+        // 等到第三个动作开始后取消
+        loop {
+            let event = timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+            if let LiveEvent::Action { event: ExecutionEvent::ActionStarted { path, .. }, .. } = event
+                && path == "$.actions[1].actions[1]"
+            {
+                break;
+            }
+        }
+        assert_eq!(queue.get(job.id).unwrap().progress.completed, 2);
+        assert_eq!(queue.cancel(job.id).unwrap().status, JobStatus::Running);
+        let cancelled = finished(&mut events, 1).await.remove(0);
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.error.as_deref(), Some("cancelled"));
+    }
+}
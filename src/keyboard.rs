@@ -15,34 +15,34 @@ impl KeyboardSimulator {
     }
 
     /// 按下并释放单个键
-    pub fn press_key(&self, enigo: &mut Enigo, key: Key, location: Option<KeyLocation>) {
-        self.key_down(enigo, key, location.clone());
+    pub fn press_key<E: Keyboard + ?Sized>(&self, enigo: &mut E, key: Key, location: Option<KeyLocation>) {
+        self.key_down(enigo, key, location);
         self.key_up(enigo, key, location);
     }
 
     /// 按下键（不释放）
-    pub fn key_down(&self, enigo: &mut Enigo, key: Key, location: Option<KeyLocation>) {
+    pub fn key_down<E: Keyboard + ?Sized>(&self, enigo: &mut E, key: Key, location: Option<KeyLocation>) {
         if let Some(key_code) = self.key_mapper.map_key(key, location) {
-            enigo.key(key_code, Direction::Press);
+            let _ = enigo.key(key_code, Direction::Press);
         }
     }
 
     /// 释放键
-    pub fn key_up(&self, enigo: &mut Enigo, key: Key, location: Option<KeyLocation>) {
+    pub fn key_up<E: Keyboard + ?Sized>(&self, enigo: &mut E, key: Key, location: Option<KeyLocation>) {
         if let Some(key_code) = self.key_mapper.map_key(key, location) {
-            enigo.key(key_code, Direction::Release);
+            let _ = enigo.key(key_code, Direction::Release);
         }
     }
 
     /// 输入文本
-    pub fn type_text(&self, enigo: &mut Enigo, text: &str) {
-        enigo.text(text);
+    pub fn type_text<E: Keyboard + ?Sized>(&self, enigo: &mut E, text: &str) {
+        let _ = enigo.text(text);
     }
 
     /// 执行热键组合
-    pub fn hotkey(
+    pub fn hotkey<E: Keyboard + ?Sized>(
         &self, 
-        enigo: &mut Enigo, 
+        enigo: &mut E, 
         modifiers: &[Key], 
         key: Key, 
         location: Option<KeyLocation>
@@ -60,4 +60,10 @@ impl KeyboardSimulator {
             self.key_up(enigo, *modifier, None);
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for KeyboardSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
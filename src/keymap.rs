@@ -41,7 +41,7 @@ impl KeyMapper {
             Key::ArrowUp => Some(EnigoKey::UpArrow),
             Key::ArrowRight => Some(EnigoKey::RightArrow),
             Key::ArrowDown => Some(EnigoKey::DownArrow),
-            Key::PrintScreen => Some(EnigoKey::Print),
+            Key::PrintScreen => Some(EnigoKey::PrintScr),
             Key::Insert => Some(EnigoKey::Insert),
             Key::Delete => Some(EnigoKey::Delete),
             Key::Num0 => Some(EnigoKey::Unicode('0')),
@@ -134,4 +134,66 @@ impl KeyMapper {
             _ => None,
         }
     }
-}
\ No newline at end of file
+
+    /// 将字符映射回逻辑按键（用于录制时把字符还原为热键主键）
+    pub fn key_for_char(&self, c: char) -> Option<Key> {
+        let key = match c.to_ascii_lowercase() {
+            '0' => Key::Num0,
+            '1' => Key::Num1,
+            '2' => Key::Num2,
+            '3' => Key::Num3,
+            '4' => Key::Num4,
+            '5' => Key::Num5,
+            '6' => Key::Num6,
+            '7' => Key::Num7,
+            '8' => Key::Num8,
+            '9' => Key::Num9,
+            'a' => Key::A,
+            'b' => Key::B,
+            'c' => Key::C,
+            'd' => Key::D,
+            'e' => Key::E,
+            'f' => Key::F,
+            'g' => Key::G,
+            'h' => Key::H,
+            'i' => Key::I,
+            'j' => Key::J,
+            'k' => Key::K,
+            'l' => Key::L,
+            'm' => Key::M,
+            'n' => Key::N,
+            'o' => Key::O,
+            'p' => Key::P,
+            'q' => Key::Q,
+            'r' => Key::R,
+            's' => Key::S,
+            't' => Key::T,
+            'u' => Key::U,
+            'v' => Key::V,
+            'w' => Key::W,
+            'x' => Key::X,
+            'y' => Key::Y,
+            'z' => Key::Z,
+            ' ' => Key::Space,
+            ';' => Key::Semicolon,
+            '=' => Key::Equal,
+            ',' => Key::Comma,
+            '-' => Key::Minus,
+            '.' => Key::Period,
+            '/' => Key::Slash,
+            '`' => Key::Backquote,
+            '[' => Key::LeftBracket,
+            '\\' => Key::Backslash,
+            ']' => Key::RightBracket,
+            '\'' => Key::Quote,
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+impl Default for KeyMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,39 @@
+pub mod accessibility;
+pub mod auth;
+pub mod backend;
+pub mod dry_run;
 pub mod enums;
+pub mod error;
+pub mod events;
 pub mod executor;
+pub mod fail_safe;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod held;
+pub mod hotkey;
+pub mod jobs;
+#[cfg(feature = "mcp")]
+pub mod mcp;
 pub mod simulator;
 pub mod mouse;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+pub mod policy;
 pub mod keyboard;
-pub mod keymap;
\ No newline at end of file
+pub mod keymap;
+pub mod recorder;
+pub mod schema;
+pub mod screen;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(test)]
+mod testing;
+pub mod timing;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod trigger;
+pub mod validator;
+pub mod vision;
+#[cfg(feature = "server")]
+pub mod webdriver;
+pub mod window;
@@ -0,0 +1,471 @@
+use std::sync::Arc;
+
+use base64::Engine;
+use rmcp::handler::server::tool::ToolRouter;
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{CallToolResult, Content, Implementation, ServerCapabilities, ServerInfo};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::{tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler, ServiceExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tracing::info;
+
+use crate::enums::{InputAction, Key, MouseButton};
+use crate::error::AutomationError;
+use crate::executor::ActionExecutor;
+use crate::screen::Rect;
+use crate::window::WindowInfo;
+
+/// 通过标准输入输出提供 MCP 服务，直到客户端断开
+pub async fn serve_stdio(executor: Arc<ActionExecutor>) -> std::io::Result<()> {
+    let service = AutomationTools::new(executor)
+        .serve(rmcp::transport::stdio())
+        .await
+        .map_err(std::io::Error::other)?;
+    service.waiting().await.map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+/// 在 listener 上通过 Streamable HTTP 提供 MCP 服务，端点为 `/mcp`
+pub async fn serve_http(listener: TcpListener, executor: Arc<ActionExecutor>) -> std::io::Result<()> {
+    let service = StreamableHttpService::new(
+        move || Ok(AutomationTools::new(executor.clone())),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    axum::serve(listener, axum::Router::new().nest_service("/mcp", service)).await
+}
+
+/// 屏幕坐标
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PointParams {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// mouse_click 的参数
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClickParams {
+    pub x: i32,
+    pub y: i32,
+    /// 默认为左键
+    #[serde(default = "left_button")]
+    pub button: MouseButton,
+    /// 是否双击
+    #[serde(default)]
+    pub double: bool,
+}
+
+/// mouse_drag 的参数
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DragParams {
+    pub start_x: i32,
+    pub start_y: i32,
+    pub end_x: i32,
+    pub end_y: i32,
+    #[serde(default = "left_button")]
+    pub button: MouseButton,
+    /// 拖动耗时（毫秒）
+    #[serde(default = "default_drag_ms")]
+    pub duration_ms: u64,
+}
+
+/// mouse_scroll 的参数，正值向下（向右）滚动
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScrollParams {
+    #[serde(default)]
+    pub delta_x: i32,
+    #[serde(default)]
+    pub delta_y: i32,
+}
+
+/// key_press 的参数，按顺序依次按下并释放
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeysParams {
+    pub keys: Vec<Key>,
+}
+
+/// type_text 的参数
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TypeTextParams {
+    pub text: String,
+    /// 每个字符之间的延时（毫秒）
+    #[serde(default)]
+    pub char_delay_ms: Option<u64>,
+}
+
+/// hotkey 的参数，如 modifiers=["Ctrl"]、key="C"
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HotkeyParams {
+    #[serde(default)]
+    pub modifiers: Vec<Key>,
+    pub key: Key,
+}
+
+/// screenshot 的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ScreenshotParams {
+    /// 截取区域，不指定时截取整个屏幕
+    #[serde(default)]
+    pub region: Option<Rect>,
+}
+
+/// execute_action 的参数
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExecuteParams {
+    pub action: InputAction,
+}
+
+fn left_button() -> MouseButton {
+    MouseButton::Left
+}
+
+fn default_drag_ms() -> u64 {
+    500
+}
+
+// ---------- 工具参数 -> InputAction ----------
+
+impl From<PointParams> for InputAction {
+    fn from(params: PointParams) -> Self {
+        InputAction::MouseMove {
+            x: params.x,
+            y: params.y,
+            origin: None,
+            target: None,
+        }
+    }
+}
+
+impl From<ClickParams> for InputAction {
+    fn from(params: ClickParams) -> Self {
+        if params.double {
+            InputAction::MouseDoubleClick {
+                button: params.button,
+                x: params.x,
+                y: params.y,
+                origin: None,
+                target: None,
+            }
+        } else {
+            InputAction::MouseClick {
+                button: params.button,
+                x: params.x,
+                y: params.y,
+                origin: None,
+                target: None,
+            }
+        }
+    }
+}
+
+impl From<DragParams> for InputAction {
+    fn from(params: DragParams) -> Self {
+        InputAction::MouseDrag {
+            button: params.button,
+            start_x: params.start_x,
+            start_y: params.start_y,
+            end_x: params.end_x,
+            end_y: params.end_y,
+            duration_ms: params.duration_ms,
+            origin: None,
+            target: None,
+        }
+    }
+}
+
+impl From<ScrollParams> for InputAction {
+    fn from(params: ScrollParams) -> Self {
+        InputAction::MouseScroll {
+            delta_x: params.delta_x,
+            delta_y: params.delta_y,
+            target: None,
+        }
+    }
+}
+
+impl From<KeysParams> for InputAction {
+    fn from(params: KeysParams) -> Self {
+        InputAction::KeySequence {
+            keys: params.keys,
+            key_delay_ms: None,
+            target: None,
+        }
+    }
+}
+
+impl From<TypeTextParams> for InputAction {
+    fn from(params: TypeTextParams) -> Self {
+        InputAction::TypeText {
+            text: params.text,
+            char_delay_ms: params.char_delay_ms,
+            target: None,
+        }
+    }
+}
+
+impl From<HotkeyParams> for InputAction {
+    fn from(params: HotkeyParams) -> Self {
+        InputAction::Hotkey {
+            modifiers: params.modifiers,
+            key: params.key,
+            location: None,
+            target: None,
+        }
+    }
+}
+
+/// 以 MCP 工具的形式提供鼠标、键盘、截图和窗口列表，工具调用转换为 `InputAction` 交给执行器
+#[derive(Clone)]
+pub struct AutomationTools {
+    executor: Arc<ActionExecutor>,
+    tool_router: ToolRouter<Self>,
+}
+
+#[tool_router]
+impl AutomationTools {
+    pub fn new(executor: Arc<ActionExecutor>) -> Self {
+        AutomationTools {
+            executor,
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Move the mouse cursor to screen coordinates (x, y).")]
+    async fn mouse_move(&self, Parameters(params): Parameters<PointParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Move to screen coordinates (x, y) and click (or double-click) a mouse button.")]
+    async fn mouse_click(&self, Parameters(params): Parameters<ClickParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Press a mouse button at the start point, move to the end point and release it.")]
+    async fn mouse_drag(&self, Parameters(params): Parameters<DragParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Scroll the mouse wheel; positive delta_y scrolls down, positive delta_x scrolls right.")]
+    async fn mouse_scroll(&self, Parameters(params): Parameters<ScrollParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Press and release keys one after another, e.g. [\"Enter\"] or [\"ArrowDown\", \"ArrowDown\", \"Enter\"].")]
+    async fn key_press(&self, Parameters(params): Parameters<KeysParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Type text into the focused window.")]
+    async fn type_text(&self, Parameters(params): Parameters<TypeTextParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Hold the modifier keys, press the key, then release everything, e.g. Ctrl+C.")]
+    async fn hotkey(&self, Parameters(params): Parameters<HotkeyParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.into()).await
+    }
+
+    #[tool(description = "Capture the screen (or a region of it) as a PNG image.")]
+    async fn screenshot(&self, Parameters(params): Parameters<ScreenshotParams>) -> Result<CallToolResult, McpError> {
+        let png = tokio::task::spawn_blocking(move || capture_png(params.region))
+            .await
+            .map_err(|err| McpError::internal_error(err.to_string(), None))?;
+        Ok(match png {
+            Ok(png) => CallToolResult::success(vec![Content::image(
+                base64::engine::general_purpose::STANDARD.encode(png),
+                "image/png",
+            )]),
+            Err(err) => CallToolResult::error(vec![Content::text(err.to_string())]),
+        })
+    }
+
+    #[tool(description = "List top-level windows (topmost first) with their id, title, class, pid and geometry.")]
+    async fn list_windows(&self) -> Result<CallToolResult, McpError> {
+        let windows = tokio::task::spawn_blocking(list_windows)
+            .await
+            .map_err(|err| McpError::internal_error(err.to_string(), None))?;
+        Ok(match windows {
+            Ok(windows) => CallToolResult::success(vec![Content::json(windows)?]),
+            Err(err) => CallToolResult::error(vec![Content::text(err.to_string())]),
+        })
+    }
+
+    #[tool(description = "Execute any automation action (see the InputAction schema), including sequences and waits.")]
+    async fn execute_action(&self, Parameters(params): Parameters<ExecuteParams>) -> Result<CallToolResult, McpError> {
+        self.execute(params.action).await
+    }
+}
+
+impl AutomationTools {
+    /// 执行失败（包括校验失败）作为工具错误返回给模型，而不是协议错误
+    async fn execute(&self, action: InputAction) -> Result<CallToolResult, McpError> {
+        info!("MCP tool call: {}", action.name());
+        Ok(match self.executor.execute_with_report(&action).await {
+            Ok(report) => CallToolResult::success(vec![Content::text(report.result)]),
+            Err(err) => CallToolResult::error(vec![Content::text(err.to_string())]),
+        })
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for AutomationTools {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+            instructions: Some(
+                "Desktop automation: control the mouse and keyboard, take screenshots and list windows. \
+                 Coordinates are screen pixels with the origin at the top-left corner."
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn capture_png(region: Option<Rect>) -> Result<Vec<u8>, AutomationError> {
+    let image = match region {
+        Some(region) => crate::screen::capture_region(region)?,
+        None => crate::screen::capture_screen()?,
+    };
+    crate::screen::encode_png(&image)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture_png(_region: Option<Rect>) -> Result<Vec<u8>, AutomationError> {
+    Err(AutomationError::Platform(
+        "screen capture is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn list_windows() -> Result<Vec<WindowInfo>, AutomationError> {
+    crate::window::list_windows()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_windows() -> Result<Vec<WindowInfo>, AutomationError> {
+    Err(AutomationError::Platform(
+        "window listing is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::simulator::InputSimulator;
+
+    /// 按客户端传来的 JSON 参数解析并转换为动作
+    fn mapped<P: DeserializeOwned + Into<InputAction>>(arguments: Value) -> Value {
+        let params: P = serde_json::from_value(arguments).unwrap();
+        serde_json::to_value(params.into()).unwrap()
+    }
+
+    fn action(action: Value) -> Value {
+        serde_json::to_value(serde_json::from_value::<InputAction>(action).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn maps_tool_arguments_to_actions() {
+        assert_eq!(
+            mapped::<PointParams>(json!({"x": 10, "y": -20})),
+            action(json!({"type": "MouseMove", "params": {"x": 10, "y": -20}}))
+        );
+        assert_eq!(
+            mapped::<ClickParams>(json!({"x": 1, "y": 2})),
+            action(json!({"type": "MouseClick", "params": {"button": "Left", "x": 1, "y": 2}}))
+        );
+        assert_eq!(
+            mapped::<ClickParams>(json!({"x": 1, "y": 2, "button": "Right", "double": true})),
+            action(json!({"type": "MouseDoubleClick", "params": {"button": "Right", "x": 1, "y": 2}}))
+        );
+        assert_eq!(
+            mapped::<DragParams>(json!({"start_x": 1, "start_y": 2, "end_x": 3, "end_y": 4})),
+            action(json!({"type": "MouseDrag", "params": {
+                "button": "Left", "start_x": 1, "start_y": 2, "end_x": 3, "end_y": 4, "duration_ms": 500
+            }}))
+        );
+        assert_eq!(
+            mapped::<DragParams>(json!({
+                "start_x": 1, "start_y": 2, "end_x": 3, "end_y": 4, "button": {"Other": 8}, "duration_ms": 50
+            })),
+            action(json!({"type": "MouseDrag", "params": {
+                "button": {"Other": 8}, "start_x": 1, "start_y": 2, "end_x": 3, "end_y": 4, "duration_ms": 50
+            }}))
+        );
+        assert_eq!(
+            mapped::<ScrollParams>(json!({"delta_y": 3})),
+            action(json!({"type": "MouseScroll", "params": {"delta_x": 0, "delta_y": 3}}))
+        );
+        assert_eq!(
+            mapped::<KeysParams>(json!({"keys": ["ArrowDown", "ArrowDown", "Enter"]})),
+            action(json!({"type": "KeySequence", "params": {"keys": ["ArrowDown", "ArrowDown", "Enter"]}}))
+        );
+        assert_eq!(
+            mapped::<TypeTextParams>(json!({"text": "hello", "char_delay_ms": 20})),
+            action(json!({"type": "TypeText", "params": {"text": "hello", "char_delay_ms": 20}}))
+        );
+        assert_eq!(
+            mapped::<HotkeyParams>(json!({"modifiers": ["Ctrl"], "key": "C"})),
+            action(json!({"type": "Hotkey", "params": {"modifiers": ["Ctrl"], "key": "C"}}))
+        );
+        assert_eq!(
+            mapped::<HotkeyParams>(json!({"key": "F5"})),
+            action(json!({"type": "Hotkey", "params": {"modifiers": [], "key": "F5"}}))
+        );
+
+        let execute: ExecuteParams =
+            serde_json::from_value(json!({"action": {"type": "Delay", "params": {"milliseconds": 100}}})).unwrap();
+        assert_eq!(
+            serde_json::to_value(execute.action).unwrap(),
+            action(json!({"type": "Delay", "params": {"milliseconds": 100}}))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_tool_arguments() {
+        assert!(serde_json::from_value::<PointParams>(json!({"x": 1})).is_err());
+        assert!(serde_json::from_value::<ClickParams>(json!({"x": 1, "y": 2, "button": "Thumb"})).is_err());
+        assert!(serde_json::from_value::<KeysParams>(json!({"keys": ["NoSuchKey"]})).is_err());
+        assert!(serde_json::from_value::<HotkeyParams>(json!({"modifiers": ["Ctrl"]})).is_err());
+        assert!(serde_json::from_value::<ExecuteParams>(json!({"action": {"type": "Teleport"}})).is_err());
+    }
+
+    #[tokio::test]
+    async fn execution_errors_are_tool_errors() {
+        let executor = ActionExecutor::with_simulator(InputSimulator::detached((800, 600)));
+        let tools = AutomationTools::new(Arc::new(executor));
+
+        let names: Vec<_> = tools.tool_router.list_all().into_iter().map(|tool| tool.name).collect();
+        for name in [
+            "mouse_move",
+            "mouse_click",
+            "mouse_drag",
+            "mouse_scroll",
+            "key_press",
+            "type_text",
+            "hotkey",
+            "screenshot",
+            "list_windows",
+            "execute_action",
+        ] {
+            assert!(names.iter().any(|tool| tool == name), "{}", name);
+        }
+
+        let result = tools.mouse_move(Parameters(PointParams { x: 1, y: 2 })).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let text = &result.content[0].as_text().unwrap().text;
+        assert!(text.contains("no input device"), "{}", text);
+    }
+}
@@ -1,6 +1,7 @@
 use enigo::*;
+use crate::backend::InputBackend;
 use crate::enums::MouseButton;
-use tokio::time::{sleep, Duration};
+use tokio::time::Duration;
 
 /// 鼠标模拟器实现
 pub struct MouseSimulator;
@@ -11,44 +12,44 @@ impl MouseSimulator {
     }
 
     /// 移动鼠标到指定位置
-    pub fn move_to(&self, enigo: &mut Enigo, x: i32, y: i32) {
-        enigo.move_mouse(x, y,Coordinate::Abs);
+    pub fn move_to<E: Mouse + ?Sized>(&self, enigo: &mut E, x: i32, y: i32) {
+        let _ = enigo.move_mouse(x, y,Coordinate::Abs);
     }
 
     /// 单击鼠标按钮
-    pub fn click(&self, enigo: &mut Enigo, button: MouseButton, x: i32, y: i32) {
+    pub fn click<E: Mouse + ?Sized>(&self, enigo: &mut E, button: MouseButton, x: i32, y: i32) {
         self.move_to(enigo, x, y);
-        enigo.button(button.into(),Direction::Click);
+        let _ = enigo.button(button.into(),Direction::Click);
     }
 
     /// 双击鼠标按钮
-    pub fn double_click(&self, enigo: &mut Enigo, button: MouseButton, x: i32, y: i32) {
+    pub fn double_click<E: Mouse + ?Sized>(&self, enigo: &mut E, button: MouseButton, x: i32, y: i32) {
         self.move_to(enigo, x, y);
-        enigo.button(button.into(),Direction::Click);
+        let _ = enigo.button(button.into(),Direction::Click);
         // delay
-        enigo.button(button.into(),Direction::Click);
+        let _ = enigo.button(button.into(),Direction::Click);
     }
 
     /// 按下鼠标按钮（保持）
-    pub fn button_down(&self, enigo: &mut Enigo, button: MouseButton) {
-        enigo.button(button.into(), Direction::Press);
+    pub fn button_down<E: Mouse + ?Sized>(&self, enigo: &mut E, button: MouseButton) {
+        let _ = enigo.button(button.into(), Direction::Press);
     }
 
     /// 释放鼠标按钮
-    pub fn button_up(&self, enigo: &mut Enigo, button: MouseButton) {
-        enigo.button(button.into(), Direction::Release);
+    pub fn button_up<E: Mouse + ?Sized>(&self, enigo: &mut E, button: MouseButton) {
+        let _ = enigo.button(button.into(), Direction::Release);
     }
 
     /// 滚动鼠标
-    pub fn scroll(&self, enigo: &mut Enigo, delta_x: i32, delta_y: i32) {
-        enigo.scroll(delta_x,Axis::Vertical);
-        enigo.scroll(delta_y,Axis::Horizontal);
+    pub fn scroll<E: Mouse + ?Sized>(&self, enigo: &mut E, delta_x: i32, delta_y: i32) {
+        let _ = enigo.scroll(delta_x,Axis::Horizontal);
+        let _ = enigo.scroll(delta_y,Axis::Vertical);
     }
 
     /// 长按鼠标按钮
-    pub async fn press(
+    pub async fn press<B: InputBackend>(
         &self, 
-        enigo: &mut Enigo, 
+        enigo: &mut B, 
         button: MouseButton, 
         x: i32, 
         y: i32, 
@@ -56,14 +57,15 @@ impl MouseSimulator {
     ) {
         self.move_to(enigo, x, y);
         self.button_down(enigo, button);
-        sleep(Duration::from_millis(duration_ms)).await;
+        enigo.wait(Duration::from_millis(duration_ms)).await;
         self.button_up(enigo, button);
     }
 
     /// 拖拽操作（带平滑移动）
-    pub async fn drag(
+    #[allow(clippy::too_many_arguments)]
+    pub async fn drag<B: InputBackend>(
         &self, 
-        enigo: &mut Enigo, 
+        enigo: &mut B, 
         button: MouseButton, 
         start_x: i32, 
         start_y: i32, 
@@ -76,7 +78,8 @@ impl MouseSimulator {
         
         // 平滑拖动效果
         let steps = 20;
-        let step_duration = duration_ms / steps as u64;
+        // 用 Duration 计算避免整数截断，配合截止时间调度保证总时长准确
+        let step_duration = Duration::from_millis(duration_ms) / steps as u32;
         let dx = (end_x - start_x) as f32 / steps as f32;
         let dy = (end_y - start_y) as f32 / steps as f32;
         
@@ -84,13 +87,19 @@ impl MouseSimulator {
             let x = start_x + (dx * i as f32) as i32;
             let y = start_y + (dy * i as f32) as i32;
             self.move_to(enigo, x, y);
-            sleep(Duration::from_millis(step_duration)).await;
+            enigo.wait(step_duration).await;
         }
         
         self.button_up(enigo, button);
     }
 }
 
+impl Default for MouseSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // 为MouseButton实现到Enigo按钮类型的转换
 impl From<MouseButton> for enigo::Button {
     fn from(button: MouseButton) -> Self {
@@ -0,0 +1,295 @@
+use std::sync::Mutex;
+
+use image::RgbaImage;
+use leptess::LepTess;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AutomationError;
+use crate::screen::{encode_png, Rect};
+
+/// 默认识别语言，可通过 AUTOMATION_OCR_LANG 环境变量覆盖（如 "eng+chi_sim"）
+pub const DEFAULT_OCR_LANG: &str = "eng";
+
+/// 屏幕截图的分辨率，避免 Tesseract 因缺少 DPI 信息而误判字号
+const SCREEN_DPI: i32 = 96;
+
+/// 识别出的文字及其位置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TextMatch {
+    pub text: String,
+    /// 文字所在区域（屏幕坐标）
+    pub rect: Rect,
+    /// 识别置信度（0~1）
+    pub confidence: f32,
+}
+
+/// 识别结果中的一行文字
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLine {
+    pub words: Vec<TextMatch>,
+}
+
+impl TextLine {
+    /// 整行文字，单词之间以空格分隔
+    pub fn text(&self) -> String {
+        self.words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// 基于 Tesseract 的离线文字识别引擎
+pub struct OcrEngine {
+    tess: LepTess,
+}
+
+impl OcrEngine {
+    /// data_path 为 tessdata 目录（None 时使用 TESSDATA_PREFIX），lang 为语言代码
+    pub fn new(data_path: Option<&str>, lang: &str) -> Result<Self, AutomationError> {
+        let tess = LepTess::new(data_path, lang).map_err(|err| {
+            AutomationError::Platform(format!("failed to initialize OCR engine for {:?}: {}", lang, err))
+        })?;
+        Ok(OcrEngine { tess })
+    }
+
+    /// 识别图片中的文字，按行返回（坐标相对于图片）
+    pub fn recognize(&mut self, image: &RgbaImage) -> Result<Vec<TextLine>, AutomationError> {
+        let png = encode_png(image)?;
+        self.tess
+            .set_image_from_mem(&png)
+            .map_err(|err| AutomationError::Image(err.to_string()))?;
+        self.tess.set_source_resolution(SCREEN_DPI);
+        let tsv = self
+            .tess
+            .get_tsv_text(0)
+            .map_err(|err| AutomationError::Platform(err.to_string()))?;
+        Ok(parse_tsv(&tsv))
+    }
+}
+
+/// 进程内共享的识别引擎，首次使用时初始化
+static ENGINE: Mutex<Option<OcrEngine>> = Mutex::new(None);
+
+/// 使用共享引擎识别图片中的文字
+pub fn recognize(image: &RgbaImage) -> Result<Vec<TextLine>, AutomationError> {
+    let mut guard = ENGINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let engine = match guard.take() {
+        Some(engine) => engine,
+        None => {
+            let lang = std::env::var("AUTOMATION_OCR_LANG").unwrap_or_else(|_| DEFAULT_OCR_LANG.to_string());
+            OcrEngine::new(None, &lang)?
+        }
+    };
+    guard.insert(engine).recognize(image)
+}
+
+/// 在识别结果中查找文字（忽略大小写，多个单词需位于同一行且相邻），按置信度降序返回
+pub fn find_in_lines(lines: &[TextLine], text: &str) -> Vec<TextMatch> {
+    let needle: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found = Vec::new();
+    for line in lines {
+        if line.words.len() < needle.len() {
+            continue;
+        }
+        let words: Vec<String> = line.words.iter().map(|word| word.text.to_lowercase()).collect();
+        for start in 0..=(words.len() - needle.len()) {
+            let end = start + needle.len();
+            if phrase_matches(&words[start..end], &needle) {
+                found.push(merge_words(&line.words[start..end]));
+            }
+        }
+    }
+    found.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    found
+}
+
+/// 将相对于截图的识别结果换算为屏幕坐标
+pub fn offset_lines(lines: Vec<TextLine>, region: Rect) -> Vec<TextLine> {
+    lines
+        .into_iter()
+        .map(|mut line| {
+            for word in &mut line.words {
+                word.rect.x += region.x;
+                word.rect.y += region.y;
+            }
+            line
+        })
+        .collect()
+}
+
+/// 在屏幕（或指定区域）中查找文字
+#[cfg(target_os = "linux")]
+pub fn find_text(text: &str, region: Option<Rect>) -> Result<Vec<TextMatch>, AutomationError> {
+    let (lines, region) = recognize_screen(region)?;
+    Ok(find_in_lines(&offset_lines(lines, region), text))
+}
+
+/// 读取屏幕（或指定区域）中的全部文字，每行一段
+#[cfg(target_os = "linux")]
+pub fn read_text(region: Option<Rect>) -> Result<String, AutomationError> {
+    let (lines, _) = recognize_screen(region)?;
+    Ok(lines.iter().map(TextLine::text).collect::<Vec<_>>().join("\n"))
+}
+
+#[cfg(target_os = "linux")]
+fn recognize_screen(region: Option<Rect>) -> Result<(Vec<TextLine>, Rect), AutomationError> {
+    let capturer = crate::screen::ScreenCapturer::connect(None)?;
+    let (width, height) = capturer.screen_size();
+    let region = capturer.clip(region.unwrap_or(Rect::new(0, 0, width, height)))?;
+    let screenshot = capturer.capture_region(region)?;
+    Ok((recognize(&screenshot)?, region))
+}
+
+/// 单个单词时允许部分匹配；多个单词时首尾允许部分匹配，中间必须完全一致
+fn phrase_matches(window: &[String], needle: &[String]) -> bool {
+    let last = needle.len() - 1;
+    if last == 0 {
+        return window[0].contains(&needle[0]);
+    }
+    window[0].ends_with(&needle[0]) && window[last].starts_with(&needle[last]) && window[1..last] == needle[1..last]
+}
+
+fn merge_words(words: &[TextMatch]) -> TextMatch {
+    let mut rect = words[0].rect;
+    for word in &words[1..] {
+        rect = rect.union(&word.rect);
+    }
+    TextMatch {
+        text: words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" "),
+        rect,
+        confidence: words.iter().map(|word| word.confidence).fold(1.0, f32::min),
+    }
+}
+
+/// 解析 Tesseract 的 TSV 输出，只保留单词级（level 5）的非空结果
+///
+/// 列依次为 level、page、block、par、line、word、left、top、width、height、conf、text
+fn parse_tsv(tsv: &str) -> Vec<TextLine> {
+    let mut lines: Vec<((i64, i64, i64), TextLine)> = Vec::new();
+    for row in tsv.lines() {
+        let fields: Vec<&str> = row.split('\t').collect();
+        if fields.len() < 12 || fields[0] != "5" {
+            continue;
+        }
+        let text = fields[11].trim();
+        if text.is_empty() {
+            continue;
+        }
+        let number = |i: usize| fields[i].trim().parse::<i64>().unwrap_or(0);
+        let word = TextMatch {
+            text: text.to_string(),
+            rect: Rect::new(
+                number(6) as i32,
+                number(7) as i32,
+                number(8).max(0) as u32,
+                number(9).max(0) as u32,
+            ),
+            confidence: (fields[10].trim().parse::<f32>().unwrap_or(0.0) / 100.0).clamp(0.0, 1.0),
+        };
+        let key = (number(2), number(3), number(4));
+        match lines.last_mut() {
+            Some((last, line)) if *last == key => line.words.push(word),
+            _ => lines.push((key, TextLine { words: vec![word] })),
+        }
+    }
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tesseract 输出的 TSV：表头、页/块/段/行级记录、空单词和两行文字
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t400\t200\t-1\t
+2\t1\t1\t0\t0\t0\t10\t20\t300\t60\t-1\t
+3\t1\t1\t1\t0\t0\t10\t20\t300\t60\t-1\t
+4\t1\t1\t1\t1\t0\t10\t20\t300\t20\t-1\t
+5\t1\t1\t1\t1\t1\t10\t20\t60\t20\t96\tSave
+5\t1\t1\t1\t1\t2\t80\t20\t40\t20\t90\tFile
+5\t1\t1\t1\t1\t3\t130\t20\t60\t20\t-1\t 
+5\t1\t1\t1\t1\t4\t130\t20\t80\t20\t75\tAs...
+4\t1\t1\t1\t2\t0\t10\t60\t300\t20\t-1\t
+5\t1\t1\t1\t2\t1\t10\t60\t70\t20\t50\tCancel
+5\t1\t1\t1\t2\t2\t90\t60\t40\t20\t101\tsave
+";
+
+    fn word(text: &str, rect: Rect, confidence: f32) -> TextMatch {
+        TextMatch {
+            text: text.to_string(),
+            rect,
+            confidence,
+        }
+    }
+
+    fn words(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_words_into_lines() {
+        let lines = parse_tsv(TSV);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text(), "Save File As...");
+        assert_eq!(lines[1].text(), "Cancel save");
+        assert_eq!(lines[0].words[0], word("Save", Rect::new(10, 20, 60, 20), 0.96));
+        assert_eq!(lines[1].words[1].confidence, 1.0);
+        assert!(parse_tsv("").is_empty());
+        assert!(parse_tsv("5\t1\t1\t1\t1\t1\t10\t20").is_empty());
+    }
+
+    #[test]
+    fn finds_phrases_within_a_line() {
+        let lines = parse_tsv(TSV);
+
+        // 单个单词部分匹配，按置信度降序
+        let found = find_in_lines(&lines, "SAVE");
+        assert_eq!(found.iter().map(|found| found.text.as_str()).collect::<Vec<_>>(), ["save", "Save"]);
+
+        let found = find_in_lines(&lines, "file as");
+        assert_eq!(found, vec![word("File As...", Rect::new(80, 20, 130, 20), 0.75)]);
+        assert_eq!(find_in_lines(&lines, "ave File A").len(), 1);
+
+        // 不跨行、不跳过中间的单词
+        assert!(find_in_lines(&lines, "As... Cancel").is_empty());
+        assert!(find_in_lines(&lines, "Save As").is_empty());
+        assert!(find_in_lines(&lines, "   ").is_empty());
+        assert!(find_in_lines(&lines, "Save File As... now").is_empty());
+    }
+
+    #[test]
+    fn phrase_ends_may_be_partial() {
+        assert!(phrase_matches(&words(&["settings"]), &words(&["ting"])));
+        assert!(phrase_matches(&words(&["open", "recent"]), &words(&["pen", "rec"])));
+        assert!(!phrase_matches(&words(&["open", "recent"]), &words(&["ope", "recent"])));
+        assert!(!phrase_matches(&words(&["open", "recent"]), &words(&["open", "cent"])));
+        assert!(phrase_matches(&words(&["xopen", "the", "filex"]), &words(&["open", "the", "file"])));
+        assert!(!phrase_matches(&words(&["open", "their", "file"]), &words(&["open", "the", "file"])));
+    }
+
+    #[test]
+    fn merges_words_into_one_match() {
+        let merged = merge_words(&[
+            word("Save", Rect::new(10, 20, 60, 20), 0.9),
+            word("As", Rect::new(80, 18, 30, 26), 0.6),
+        ]);
+        assert_eq!(merged, word("Save As", Rect::new(10, 18, 100, 26), 0.6));
+        let single = word("Save", Rect::new(10, 20, 60, 20), 0.9);
+        assert_eq!(merge_words(std::slice::from_ref(&single)), single);
+    }
+
+    #[test]
+    fn offsets_words_into_screen_coordinates() {
+        let lines = offset_lines(parse_tsv(TSV), Rect::new(100, 200, 400, 200));
+        assert_eq!(lines[0].words[0].rect, Rect::new(110, 220, 60, 20));
+        assert_eq!(lines[1].words[1].rect, Rect::new(190, 260, 40, 20));
+        assert_eq!(find_in_lines(&lines, "cancel")[0].rect, Rect::new(110, 260, 70, 20));
+    }
+}
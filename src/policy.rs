@@ -0,0 +1,419 @@
+use std::sync::Arc;
+
+use enigo::{Coordinate, Direction, Key as EnigoKey};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{check_rules, rule_matches};
+use crate::dry_run::InputEvent;
+use crate::enums::{InputAction, Key, KeyLocation};
+use crate::keymap::KeyMapper;
+use crate::screen::Rect;
+
+/// 执行策略：在每个动作执行前检查，禁止危险的组合键、限制鼠标区域、等待时长和文本长度，
+/// 并可以要求指定动作在执行前得到确认
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// 禁止的组合键，如 [["Ctrl", "Alt", "Delete"], ["Meta", "L"]]；
+    /// 与 KeyDown 等仍按住的键凑成组合时同样拒绝
+    pub forbidden_hotkeys: Vec<Vec<Key>>,
+    /// 允许鼠标操作的屏幕区域，为空时不限制
+    pub allowed_regions: Vec<Rect>,
+    /// 单次等待、按住、拖动和超时的最长时间（毫秒）
+    pub max_delay_ms: Option<u64>,
+    /// TypeText 和 SetElementText 文本的最大字符数
+    pub max_text_length: Option<usize>,
+    /// 禁止执行的动作或分组（"*"、动作名称或 mouse、keyboard 等分组名称）
+    pub deny: Vec<String>,
+    /// 执行前需要确认的动作或分组，没有设置确认回调时拒绝执行
+    pub confirm: Vec<String>,
+}
+
+/// 策略对单个动作的判定结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// 允许执行
+    Allow,
+    /// 需要确认后才能执行，附带原因
+    Confirm(String),
+    /// 拒绝执行，附带原因
+    Deny(String),
+}
+
+/// 需要确认的动作
+#[derive(Debug)]
+pub struct ConfirmRequest<'a> {
+    /// 动作在树中的路径
+    pub path: &'a str,
+    pub action: &'a InputAction,
+    pub reason: &'a str,
+}
+
+/// 确认回调，返回 true 时执行动作；在执行线程中同步调用
+pub type Confirmation = Arc<dyn Fn(&ConfirmRequest) -> bool + Send + Sync>;
+
+impl Policy {
+    /// 是否没有任何限制
+    pub fn is_empty(&self) -> bool {
+        *self == Policy::default()
+    }
+
+    /// 检查策略本身是否有效
+    pub fn check_rules(&self) -> Result<(), String> {
+        check_rules(self.deny.iter().chain(&self.confirm))?;
+        if self.forbidden_hotkeys.iter().any(Vec::is_empty) {
+            return Err("forbidden_hotkeys contains an empty combination".to_string());
+        }
+        Ok(())
+    }
+
+    /// 判定单个动作（不检查子动作，Sequence 和 Parallel 的子动作在执行前单独判定）
+    ///
+    /// offset 为已解析的坐标原点，cursor 为当前光标位置，held 为仍按住的键
+    pub fn evaluate(
+        &self,
+        action: &InputAction,
+        offset: (i32, i32),
+        cursor: Option<(i32, i32)>,
+        held: &[EnigoKey],
+    ) -> Verdict {
+        let name = action.name();
+        if self.deny.iter().any(|rule| rule_matches(rule, name)) {
+            return Verdict::Deny(format!("{} is denied", name));
+        }
+        let checks = [
+            self.check_hotkeys(action, held),
+            self.check_regions(action, offset, cursor),
+            self.check_delays(action),
+            self.check_text(action),
+        ];
+        if let Some(Err(reason)) = checks.into_iter().find(Result::is_err) {
+            return Verdict::Deny(reason);
+        }
+        if self.confirm.iter().any(|rule| rule_matches(rule, name)) {
+            return Verdict::Confirm(format!("{} requires confirmation", name));
+        }
+        Verdict::Allow
+    }
+
+    /// 检查运行时才确定的点击位置（ClickText、ClickImage、ClickElement）
+    pub fn check_point(&self, name: &str, x: i32, y: i32) -> Result<(), String> {
+        if self.allowed_regions.is_empty() || self.allowed_regions.iter().any(|region| region.contains(x, y)) {
+            Ok(())
+        } else {
+            Err(format!("{} at ({}, {}) is outside the allowed regions", name, x, y))
+        }
+    }
+
+    /// 检查 Parallel 分支合并后的事件时间线：各分支单独允许的操作合并后仍可能组成禁止的组合键，
+    /// 或在其他分支移动光标后落到允许的区域外
+    ///
+    /// cursor 为开始时的光标位置，held 为开始时按住的键
+    pub fn check_timeline<'a>(
+        &self,
+        events: impl IntoIterator<Item = &'a InputEvent>,
+        mut cursor: Option<(i32, i32)>,
+        held: &[EnigoKey],
+    ) -> Result<(), String> {
+        if self.forbidden_hotkeys.is_empty() && self.allowed_regions.is_empty() {
+            return Ok(());
+        }
+        let mapper = KeyMapper::new();
+        let mut held = held.to_vec();
+        for event in events {
+            match event {
+                InputEvent::Key { key, direction: Direction::Release } => held.retain(|held| held != key),
+                InputEvent::Key { key, .. } => {
+                    if !held.contains(key) {
+                        held.push(*key);
+                    }
+                    self.check_chord(&mapper, &[], &held)?;
+                }
+                InputEvent::Text { text } if !held.is_empty() => {
+                    for key in text.chars().filter_map(|c| mapper.key_for_char(c)) {
+                        self.check_chord(&mapper, &[key], &held)?;
+                    }
+                }
+                InputEvent::MouseMove { x, y, coordinate: Coordinate::Abs } => cursor = Some((*x, *y)),
+                InputEvent::MouseMove { x, y, coordinate: Coordinate::Rel } => {
+                    cursor = cursor.map(|(cx, cy)| (cx + x, cy + y));
+                }
+                // 移动路径由各分支的动作检查，这里只检查按键和滚动时光标所在的位置
+                InputEvent::MouseButton { direction: Direction::Release, .. } => {}
+                InputEvent::MouseButton { .. } | InputEvent::MouseScroll { .. } if !self.allowed_regions.is_empty() => {
+                    let name = if matches!(event, InputEvent::MouseButton { .. }) { "MouseButton" } else { "MouseScroll" };
+                    match cursor {
+                        Some((x, y)) => self.check_point(name, x, y)?,
+                        None => return Err(format!("{} at an unknown cursor position", name)),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn check_hotkeys(&self, action: &InputAction, held: &[EnigoKey]) -> Result<(), String> {
+        if self.forbidden_hotkeys.is_empty() {
+            return Ok(());
+        }
+        let mapper = KeyMapper::new();
+        let chords: Vec<Vec<Key>> = match action {
+            InputAction::Hotkey { modifiers, key, .. } => {
+                vec![modifiers.iter().copied().chain([*key]).collect()]
+            }
+            InputAction::KeyPress { key, .. } | InputAction::KeyDown { key, .. } => vec![vec![*key]],
+            InputAction::KeySequence { keys, .. } => keys.iter().map(|key| vec![*key]).collect(),
+            // 只有按住了其他键时文本才可能组成组合键
+            InputAction::TypeText { text, .. } if !held.is_empty() => {
+                text.chars().filter_map(|c| mapper.key_for_char(c)).map(|key| vec![key]).collect()
+            }
+            _ => return Ok(()),
+        };
+        for chord in &chords {
+            self.check_chord(&mapper, chord, held)?;
+        }
+        Ok(())
+    }
+
+    /// 检查同时按下 chord 和 held 中的键是否组成禁止的组合键
+    fn check_chord(&self, mapper: &KeyMapper, chord: &[Key], held: &[EnigoKey]) -> Result<(), String> {
+        let is_held = |key: Key| {
+            [None, Some(KeyLocation::Left), Some(KeyLocation::Right)]
+                .into_iter()
+                .filter_map(|location| mapper.map_key(key, location))
+                .any(|mapped| held.contains(&mapped))
+        };
+        match self
+            .forbidden_hotkeys
+            .iter()
+            .find(|forbidden| forbidden.iter().all(|key| chord.contains(key) || is_held(*key)))
+        {
+            Some(forbidden) => Err(format!("hotkey {:?} is forbidden", forbidden)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_regions(&self, action: &InputAction, offset: (i32, i32), cursor: Option<(i32, i32)>) -> Result<(), String> {
+        if self.allowed_regions.is_empty() {
+            return Ok(());
+        }
+        let name = action.name();
+        let (dx, dy) = offset;
+        match action {
+            InputAction::MouseMove { target: Some(_), .. }
+            | InputAction::MouseClick { target: Some(_), .. }
+            | InputAction::MouseDoubleClick { target: Some(_), .. }
+            | InputAction::MousePress { target: Some(_), .. }
+            | InputAction::MouseDown { target: Some(_), .. }
+            | InputAction::MouseUp { target: Some(_), .. }
+            | InputAction::MouseDrag { target: Some(_), .. }
+            | InputAction::MouseScroll { target: Some(_), .. } => Err(format!(
+                "{} is sent to a window and cannot be checked against the allowed regions",
+                name
+            )),
+            InputAction::MouseMove { x, y, .. }
+            | InputAction::MouseClick { x, y, .. }
+            | InputAction::MouseDoubleClick { x, y, .. }
+            | InputAction::MousePress { x, y, .. } => {
+                self.check_point(name, x.saturating_add(dx), y.saturating_add(dy))
+            }
+            InputAction::MouseDrag { start_x, start_y, end_x, end_y, .. } => {
+                let (start_x, start_y) = (start_x.saturating_add(dx), start_y.saturating_add(dy));
+                let (end_x, end_y) = (end_x.saturating_add(dx), end_y.saturating_add(dy));
+                // 区域是矩形，起点和终点在同一个区域内时整条拖动路径都在区域内
+                let inside = self
+                    .allowed_regions
+                    .iter()
+                    .any(|region| region.contains(start_x, start_y) && region.contains(end_x, end_y));
+                if inside {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} from ({}, {}) to ({}, {}) leaves the allowed regions",
+                        name, start_x, start_y, end_x, end_y
+                    ))
+                }
+            }
+            InputAction::MouseDown { .. } | InputAction::MouseUp { .. } | InputAction::MouseScroll { .. } => {
+                match cursor {
+                    Some((x, y)) => self.check_point(name, x, y),
+                    None => Err(format!("{} at an unknown cursor position", name)),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_delays(&self, action: &InputAction) -> Result<(), String> {
+        let Some(max) = self.max_delay_ms else {
+            return Ok(());
+        };
+        let delays = match action {
+            InputAction::Delay { milliseconds } => vec![("milliseconds", *milliseconds)],
+            InputAction::MousePress { duration_ms, .. } | InputAction::MouseDrag { duration_ms, .. } => {
+                vec![("duration_ms", *duration_ms)]
+            }
+            InputAction::KeySequence { key_delay_ms, .. } => {
+                key_delay_ms.map(|delay| ("key_delay_ms", delay)).into_iter().collect()
+            }
+            InputAction::TypeText { char_delay_ms, .. } => {
+                char_delay_ms.map(|delay| ("char_delay_ms", delay)).into_iter().collect()
+            }
+            _ => action.timeout_ms().map(|timeout| ("timeout_ms", timeout)).into_iter().collect(),
+        };
+        match delays.into_iter().find(|(_, delay)| *delay > max) {
+            Some((field, delay)) => Err(format!(
+                "{} {} {}ms exceeds the limit of {}ms",
+                action.name(),
+                field,
+                delay,
+                max
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn check_text(&self, action: &InputAction) -> Result<(), String> {
+        let (Some(max), InputAction::TypeText { text, .. } | InputAction::SetElementText { text, .. }) =
+            (self.max_text_length, action)
+        else {
+            return Ok(());
+        };
+        let length = text.chars().count();
+        if length > max {
+            Err(format!(
+                "{} text of {} characters exceeds the limit of {}",
+                action.name(),
+                length,
+                max
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn action(value: serde_json::Value) -> InputAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn regions_apply_the_origin_offset_to_every_pointer_action() {
+        let policy = Policy {
+            allowed_regions: vec![Rect::new(100, 100, 50, 50)],
+            ..Policy::default()
+        };
+        for value in [
+            json!({"type": "MouseMove", "params": {"x": 10, "y": 10}}),
+            json!({"type": "MouseClick", "params": {"button": "Left", "x": 10, "y": 10}}),
+            json!({"type": "MouseDoubleClick", "params": {"button": "Left", "x": 10, "y": 10}}),
+            json!({"type": "MousePress", "params": {"button": "Left", "x": 10, "y": 10, "duration_ms": 100}}),
+            json!({"type": "MouseDrag", "params": {
+                "button": "Left", "start_x": 10, "start_y": 10, "end_x": 40, "end_y": 40, "duration_ms": 100
+            }}),
+        ] {
+            let action = action(value);
+            assert!(matches!(policy.evaluate(&action, (0, 0), None, &[]), Verdict::Deny(_)), "{}", action.name());
+            assert_eq!(policy.evaluate(&action, (100, 100), None, &[]), Verdict::Allow, "{}", action.name());
+            // 超出 i32 范围的坐标视为在区域外
+            let verdict = policy.evaluate(&action, (i32::MAX, i32::MIN), None, &[]);
+            assert!(matches!(verdict, Verdict::Deny(_)), "{}", action.name());
+        }
+    }
+
+    #[test]
+    fn evaluate_denies_rules_and_limits_before_confirming() {
+        let policy = Policy {
+            max_delay_ms: Some(1000),
+            max_text_length: Some(5),
+            deny: vec!["window".to_string()],
+            confirm: vec!["keyboard".to_string()],
+            ..Policy::default()
+        };
+        let verdict = |value| policy.evaluate(&action(value), (0, 0), None, &[]);
+        let denied = |value, reason: &str| match verdict(value) {
+            Verdict::Deny(denied) => assert!(denied.contains(reason), "{}", denied),
+            other => panic!("{:?}", other),
+        };
+
+        denied(json!({"type": "CloseWindow", "params": {"window": {"title": "x"}}}), "CloseWindow is denied");
+        denied(json!({"type": "Delay", "params": {"milliseconds": 2000}}), "milliseconds 2000ms");
+        denied(json!({"type": "TypeText", "params": {"text": "abcdef"}}), "6 characters");
+        denied(json!({"type": "TypeText", "params": {"text": "abc", "char_delay_ms": 1500}}), "char_delay_ms");
+        assert_eq!(
+            verdict(json!({"type": "TypeText", "params": {"text": "abc"}})),
+            Verdict::Confirm("TypeText requires confirmation".to_string())
+        );
+        assert_eq!(verdict(json!({"type": "Delay", "params": {"milliseconds": 1000}})), Verdict::Allow);
+    }
+
+    #[test]
+    fn evaluate_combines_hotkeys_with_held_keys() {
+        let policy = Policy {
+            forbidden_hotkeys: vec![vec![Key::Ctrl, Key::Alt, Key::Delete], vec![Key::Meta, Key::L]],
+            ..Policy::default()
+        };
+        let mapper = KeyMapper::new();
+        let held: Vec<EnigoKey> = [Key::Ctrl, Key::Alt].map(|key| mapper.map_key(key, None).unwrap()).into();
+        let delete = action(json!({"type": "KeyPress", "params": {"key": "Delete"}}));
+        let hotkey = action(json!({"type": "Hotkey", "params": {"modifiers": ["Ctrl", "Alt"], "key": "Delete"}}));
+        let typed = action(json!({"type": "TypeText", "params": {"text": "l"}}));
+        let meta = vec![mapper.map_key(Key::Meta, Some(KeyLocation::Left)).unwrap()];
+
+        assert_eq!(policy.evaluate(&delete, (0, 0), None, &[]), Verdict::Allow);
+        assert!(matches!(policy.evaluate(&delete, (0, 0), None, &held), Verdict::Deny(_)));
+        assert!(matches!(policy.evaluate(&hotkey, (0, 0), None, &[]), Verdict::Deny(_)));
+        assert_eq!(policy.evaluate(&typed, (0, 0), None, &[]), Verdict::Allow);
+        assert!(matches!(policy.evaluate(&typed, (0, 0), None, &meta), Verdict::Deny(_)));
+    }
+
+    #[test]
+    fn evaluate_checks_pointer_actions_at_the_cursor() {
+        let policy = Policy {
+            allowed_regions: vec![Rect::new(100, 100, 50, 50)],
+            ..Policy::default()
+        };
+        let down = action(json!({"type": "MouseDown", "params": {"button": "Left"}}));
+        assert_eq!(policy.evaluate(&down, (0, 0), Some((120, 120)), &[]), Verdict::Allow);
+        assert!(matches!(policy.evaluate(&down, (0, 0), Some((10, 10)), &[]), Verdict::Deny(_)));
+        assert!(matches!(policy.evaluate(&down, (0, 0), None, &[]), Verdict::Deny(_)));
+        let targeted = action(json!({"type": "MouseMove", "params": {"x": 120, "y": 120, "target": {"window": {"title": "x"}}}}));
+        assert!(matches!(policy.evaluate(&targeted, (0, 0), None, &[]), Verdict::Deny(_)));
+    }
+
+    #[test]
+    fn timeline_tracks_keys_and_cursor_across_events() {
+        let policy = Policy {
+            forbidden_hotkeys: vec![vec![Key::Ctrl, Key::Alt, Key::Delete]],
+            allowed_regions: vec![Rect::new(100, 100, 50, 50)],
+            ..Policy::default()
+        };
+        let mapper = KeyMapper::new();
+        let key = |key, direction| InputEvent::Key { key: mapper.map_key(key, None).unwrap(), direction };
+        let move_to = |x, y| InputEvent::MouseMove { x, y, coordinate: Coordinate::Abs };
+        let click = InputEvent::MouseButton { button: enigo::Button::Left, direction: Direction::Click };
+
+        let chord = [key(Key::Ctrl, Direction::Press), key(Key::Alt, Direction::Press), key(Key::Delete, Direction::Click)];
+        assert!(policy.check_timeline(&chord, None, &[]).is_err());
+        let released = [
+            key(Key::Ctrl, Direction::Press),
+            key(Key::Ctrl, Direction::Release),
+            key(Key::Alt, Direction::Press),
+            key(Key::Delete, Direction::Click),
+        ];
+        assert!(policy.check_timeline(&released, None, &[]).is_ok());
+
+        assert!(policy.check_timeline([&click], Some((120, 120)), &[]).is_ok());
+        assert!(policy.check_timeline([&click], None, &[]).is_err());
+        // 光标经过区域外不影响，按下时所在的位置才需要在区域内
+        assert!(policy.check_timeline(&[move_to(0, 0), move_to(120, 120), click.clone()], None, &[]).is_ok());
+        assert!(policy.check_timeline(&[move_to(120, 120), move_to(10, 10), click], None, &[]).is_err());
+    }
+}
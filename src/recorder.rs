@@ -0,0 +1,900 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::{InputAction, Key, MouseButton};
+
+/// 录制到的原始输入
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", content = "params")]
+pub enum RecordedInput {
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    ButtonDown {
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    },
+    ButtonUp {
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    },
+    Scroll {
+        delta_x: i32,
+        delta_y: i32,
+    },
+    KeyDown {
+        key: Key,
+        /// 按键产生的字符（已考虑 Shift/CapsLock），不可打印键为 None
+        text: Option<char>,
+    },
+    KeyUp {
+        key: Key,
+    },
+}
+
+/// 带时间戳的录制事件
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct RecordedEvent {
+    /// 相对于录制开始的时间（毫秒）
+    pub time_ms: u64,
+    pub input: RecordedInput,
+}
+
+/// 合并录制事件时使用的选项
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct CoalesceOptions {
+    /// 是否保留未伴随点击的鼠标移动
+    pub record_moves: bool,
+    /// 小于该值的动作间隔不生成 Delay（毫秒）
+    pub min_delay_ms: u64,
+    /// 两次单击间隔小于该值时合并为双击（毫秒）
+    pub double_click_ms: u64,
+    /// 按下与释放位置距离超过该值时视为拖拽（像素）
+    pub drag_threshold_px: i32,
+    /// 按住不动超过该值时视为长按（毫秒）
+    pub long_press_ms: u64,
+    /// 连续输入的字符间隔超过该值时拆分为多个 TypeText（毫秒）
+    pub typing_gap_ms: u64,
+}
+
+impl Default for CoalesceOptions {
+    fn default() -> Self {
+        CoalesceOptions {
+            record_moves: true,
+            min_delay_ms: 50,
+            double_click_ms: 400,
+            drag_threshold_px: 5,
+            long_press_ms: 500,
+            typing_gap_ms: 1000,
+        }
+    }
+}
+
+/// 将原始录制事件合并为高层动作序列（MouseDrag、TypeText、Hotkey 等），动作间的停顿生成 Delay
+pub fn coalesce(events: &[RecordedEvent], options: &CoalesceOptions) -> InputAction {
+    let mut coalescer = Coalescer::new(options);
+    for event in events {
+        coalescer.feed(event);
+    }
+    coalescer.finish()
+}
+
+/// 已生成的动作及其起止时间
+struct TimedAction {
+    start_ms: u64,
+    end_ms: u64,
+    action: InputAction,
+}
+
+/// 正在按住的鼠标按钮
+struct HeldButton {
+    button: MouseButton,
+    start_ms: u64,
+    start: (i32, i32),
+}
+
+/// 正在累积的文本输入
+struct PendingText {
+    start_ms: u64,
+    end_ms: u64,
+    text: String,
+}
+
+/// 正在按住的修饰键
+struct HeldModifier {
+    key: Key,
+    start_ms: u64,
+    /// 按住期间是否有其他键被按下
+    used: bool,
+}
+
+struct Coalescer<'a> {
+    options: &'a CoalesceOptions,
+    actions: Vec<TimedAction>,
+    pending_move: Option<(u64, i32, i32)>,
+    pending_text: Option<PendingText>,
+    held_button: Option<HeldButton>,
+    modifiers: Vec<HeldModifier>,
+}
+
+impl<'a> Coalescer<'a> {
+    fn new(options: &'a CoalesceOptions) -> Self {
+        Coalescer {
+            options,
+            actions: Vec::new(),
+            pending_move: None,
+            pending_text: None,
+            held_button: None,
+            modifiers: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, start_ms: u64, end_ms: u64, action: InputAction) {
+        self.actions.push(TimedAction {
+            start_ms,
+            end_ms,
+            action,
+        });
+    }
+
+    fn flush_move(&mut self) {
+        if let Some((time_ms, x, y)) = self.pending_move.take()
+            && self.options.record_moves
+        {
+            self.push(time_ms, time_ms, InputAction::MouseMove { x, y, origin: None, target: None });
+        }
+    }
+
+    fn flush_text(&mut self) {
+        if let Some(pending) = self.pending_text.take() {
+            let count = pending.text.chars().count() as u64;
+            let char_delay_ms = if count > 1 {
+                Some((pending.end_ms - pending.start_ms) / (count - 1))
+            } else {
+                None
+            };
+            self.push(
+                pending.start_ms,
+                pending.end_ms,
+                InputAction::TypeText {
+                    text: pending.text,
+                    char_delay_ms,
+                    target: None,
+                },
+            );
+        }
+    }
+
+    fn feed(&mut self, event: &RecordedEvent) {
+        let time_ms = event.time_ms;
+        match &event.input {
+            RecordedInput::MouseMove { x, y } => {
+                if self.held_button.is_none() {
+                    self.pending_move = Some((time_ms, *x, *y));
+                }
+            }
+            RecordedInput::ButtonDown { button, x, y } => {
+                self.flush_text();
+                // 点击本身带坐标，之前的移动不需要单独保留
+                self.pending_move = None;
+                self.held_button = Some(HeldButton {
+                    button: *button,
+                    start_ms: time_ms,
+                    start: (*x, *y),
+                });
+            }
+            RecordedInput::ButtonUp { button, x, y } => {
+                let Some(held) = self.held_button.take_if(|held| held.button == *button) else {
+                    return;
+                };
+                self.finish_button(held, time_ms, (*x, *y));
+            }
+            RecordedInput::Scroll { delta_x, delta_y } => {
+                self.flush_text();
+                self.flush_move();
+                if let Some(last) = self.actions.last_mut()
+                    && let InputAction::MouseScroll {
+                        delta_x: last_x,
+                        delta_y: last_y,
+                        target: None,
+                    } = &mut last.action
+                    && time_ms.saturating_sub(last.end_ms) <= self.options.double_click_ms
+                {
+                    *last_x += delta_x;
+                    *last_y += delta_y;
+                    last.end_ms = time_ms;
+                    return;
+                }
+                self.push(
+                    time_ms,
+                    time_ms,
+                    InputAction::MouseScroll {
+                        delta_x: *delta_x,
+                        delta_y: *delta_y,
+                        target: None,
+                    },
+                );
+            }
+            RecordedInput::KeyDown { key, text } => self.key_down(time_ms, *key, *text),
+            RecordedInput::KeyUp { key } => self.key_up(time_ms, *key),
+        }
+    }
+
+    fn finish_button(&mut self, held: HeldButton, time_ms: u64, end: (i32, i32)) {
+        let (start_x, start_y) = held.start;
+        let (end_x, end_y) = end;
+        let distance = (end_x - start_x).abs().max((end_y - start_y).abs());
+        let duration_ms = time_ms.saturating_sub(held.start_ms);
+
+        if distance > self.options.drag_threshold_px {
+            self.push(
+                held.start_ms,
+                time_ms,
+                InputAction::MouseDrag {
+                    button: held.button,
+                    start_x,
+                    start_y,
+                    end_x,
+                    end_y,
+                    duration_ms,
+                    origin: None,
+                    target: None,
+                },
+            );
+            return;
+        }
+
+        if duration_ms >= self.options.long_press_ms {
+            self.push(
+                held.start_ms,
+                time_ms,
+                InputAction::MousePress {
+                    button: held.button,
+                    x: start_x,
+                    y: start_y,
+                    duration_ms,
+                    origin: None,
+                    target: None,
+                },
+            );
+            return;
+        }
+
+        // 与上一次同位置、同按钮的快速单击合并为双击
+        if let Some(last) = self.actions.last_mut()
+            && let InputAction::MouseClick { button, x, y, origin: None, target: None } = last.action
+            && button == held.button
+            && (x - start_x).abs().max((y - start_y).abs()) <= self.options.drag_threshold_px
+            && held.start_ms.saturating_sub(last.end_ms) <= self.options.double_click_ms
+        {
+            last.action = InputAction::MouseDoubleClick { button, x, y, origin: None, target: None };
+            last.end_ms = time_ms;
+            return;
+        }
+
+        self.push(
+            held.start_ms,
+            time_ms,
+            InputAction::MouseClick {
+                button: held.button,
+                x: start_x,
+                y: start_y,
+                origin: None,
+                target: None,
+            },
+        );
+    }
+
+    fn key_down(&mut self, time_ms: u64, key: Key, text: Option<char>) {
+        if is_modifier(key) {
+            if !self.modifiers.iter().any(|held| held.key == key) {
+                self.modifiers.push(HeldModifier {
+                    key,
+                    start_ms: time_ms,
+                    used: false,
+                });
+            }
+            return;
+        }
+
+        for held in &mut self.modifiers {
+            held.used = true;
+        }
+        let chord = self.modifiers.iter().any(|held| held.key != Key::Shift);
+
+        if !chord
+            && let Some(c) = text.filter(|c| !c.is_control())
+        {
+            self.flush_move();
+            match &mut self.pending_text {
+                Some(pending) if time_ms.saturating_sub(pending.end_ms) <= self.options.typing_gap_ms => {
+                    pending.text.push(c);
+                    pending.end_ms = time_ms;
+                }
+                _ => {
+                    self.flush_text();
+                    self.pending_text = Some(PendingText {
+                        start_ms: time_ms,
+                        end_ms: time_ms,
+                        text: c.to_string(),
+                    });
+                }
+            }
+            return;
+        }
+
+        self.flush_text();
+        self.flush_move();
+        if chord {
+            let modifiers = self.modifiers.iter().map(|held| held.key).collect();
+            self.push(
+                time_ms,
+                time_ms,
+                InputAction::Hotkey {
+                    modifiers,
+                    key,
+                    location: None,
+                    target: None,
+                },
+            );
+        } else {
+            self.push(time_ms, time_ms, InputAction::KeyPress { key, location: None, target: None });
+        }
+    }
+
+    fn key_up(&mut self, time_ms: u64, key: Key) {
+        let Some(index) = self.modifiers.iter().position(|held| held.key == key) else {
+            return;
+        };
+        let held = self.modifiers.remove(index);
+        if !held.used {
+            // 单独按下并释放的修饰键
+            self.flush_text();
+            self.flush_move();
+            self.push(held.start_ms, time_ms, InputAction::KeyPress { key, location: None, target: None });
+        }
+    }
+
+    fn finish(mut self) -> InputAction {
+        self.flush_text();
+        self.flush_move();
+
+        let mut actions = Vec::new();
+        let mut last_end: Option<u64> = None;
+        for timed in self.actions {
+            if let Some(end_ms) = last_end {
+                let gap = timed.start_ms.saturating_sub(end_ms);
+                if gap >= self.options.min_delay_ms && gap > 0 {
+                    actions.push(InputAction::Delay { milliseconds: gap });
+                }
+            }
+            last_end = Some(timed.end_ms);
+            actions.push(timed.action);
+        }
+        InputAction::Sequence { actions }
+    }
+}
+
+fn is_modifier(key: Key) -> bool {
+    matches!(key, Key::Shift | Key::Ctrl | Key::Alt | Key::Meta)
+}
+
+#[cfg(target_os = "linux")]
+pub use self::x11::Recorder;
+#[cfg(target_os = "linux")]
+pub(crate) use self::x11::Keymap;
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::thread::JoinHandle;
+
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::record::{self, ConnectionExt as _};
+    use x11rb::protocol::xproto::{self, ConnectionExt as _, KeyButMask};
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::x11_utils::TryParse;
+
+    use super::{RecordedEvent, RecordedInput};
+    use crate::enums::{Key, MouseButton};
+    use crate::error::AutomationError;
+    use crate::keymap::KeyMapper;
+
+    const RECORD_FROM_SERVER: u8 = 0;
+    const RECORD_END_OF_DATA: u8 = 5;
+
+    /// 基于 X11 RECORD 扩展的输入录制器
+    ///
+    /// 录制所有客户端收到的真实设备事件，调用 `stop` 后返回事件列表
+    pub struct Recorder {
+        ctrl: RustConnection,
+        context: record::Context,
+        worker: JoinHandle<Result<Vec<RecordedEvent>, AutomationError>>,
+    }
+
+    impl Recorder {
+        /// 连接到指定显示（None 表示使用 DISPLAY 环境变量）并开始录制
+        pub fn start(display: Option<&str>) -> Result<Self, AutomationError> {
+            let (ctrl, _) = x11rb::connect(display)?;
+            let (data, _) = x11rb::connect(display)?;
+
+            if ctrl.extension_information(record::X11_EXTENSION_NAME)?.is_none() {
+                return Err(AutomationError::Platform(
+                    "X server does not support the RECORD extension".to_string(),
+                ));
+            }
+            ctrl.record_query_version(1, 13)?.reply()?;
+            let keymap = Keymap::query(&ctrl)?;
+
+            let context = ctrl.generate_id()?;
+            let empty = record::Range8 { first: 0, last: 0 };
+            let empty_ext = record::ExtRange {
+                major: empty,
+                minor: record::Range16 { first: 0, last: 0 },
+            };
+            let range = record::Range {
+                core_requests: empty,
+                core_replies: empty,
+                ext_requests: empty_ext,
+                ext_replies: empty_ext,
+                delivered_events: empty,
+                device_events: record::Range8 {
+                    first: xproto::KEY_PRESS_EVENT,
+                    last: xproto::MOTION_NOTIFY_EVENT,
+                },
+                errors: empty,
+                client_started: false,
+                client_died: false,
+            };
+            ctrl.record_create_context(context, 0, &[record::CS::ALL_CLIENTS.into()], &[range])?
+                .check()?;
+
+            let worker = std::thread::spawn(move || record_loop(data, context, keymap));
+            Ok(Recorder {
+                ctrl,
+                context,
+                worker,
+            })
+        }
+
+        /// 停止录制并返回录制到的事件
+        pub fn stop(self) -> Result<Vec<RecordedEvent>, AutomationError> {
+            self.ctrl.record_disable_context(self.context)?;
+            self.ctrl.sync()?;
+            let events = self
+                .worker
+                .join()
+                .map_err(|_| AutomationError::Platform("recorder thread panicked".to_string()))??;
+            self.ctrl.record_free_context(self.context)?;
+            self.ctrl.flush()?;
+            Ok(events)
+        }
+    }
+
+    /// 键码到 keysym 的映射表
+    pub(crate) struct Keymap {
+        min_keycode: u8,
+        per_keycode: usize,
+        keysyms: Vec<u32>,
+    }
+
+    impl Keymap {
+        /// 读取 X 服务器当前的键盘映射
+        pub(crate) fn query(conn: &impl Connection) -> Result<Self, AutomationError> {
+            let setup = conn.setup();
+            let min_keycode = setup.min_keycode;
+            let count = setup.max_keycode - min_keycode + 1;
+            let mapping = conn.get_keyboard_mapping(min_keycode, count)?.reply()?;
+            Ok(Keymap {
+                min_keycode,
+                per_keycode: mapping.keysyms_per_keycode as usize,
+                keysyms: mapping.keysyms,
+            })
+        }
+
+        /// 不按 Shift 时产生该逻辑按键的全部键码
+        pub(crate) fn keycodes(&self, key: Key) -> Vec<u8> {
+            let count = self.keysyms.len() / self.per_keycode.max(1);
+            (0..count)
+                .filter_map(|index| u8::try_from(index + self.min_keycode as usize).ok())
+                .filter(|&keycode| keysym_to_key(self.keysym(keycode, false)) == Some(key))
+                .collect()
+        }
+
+        pub(crate) fn keysym(&self, keycode: u8, shifted: bool) -> u32 {
+            let base = (keycode.saturating_sub(self.min_keycode)) as usize * self.per_keycode;
+            let normal = self.keysyms.get(base).copied().unwrap_or(0);
+            let shifted_sym = if self.per_keycode > 1 {
+                self.keysyms.get(base + 1).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            if shifted && shifted_sym != 0 {
+                shifted_sym
+            } else {
+                normal
+            }
+        }
+    }
+
+    fn record_loop(
+        data: RustConnection,
+        context: record::Context,
+        keymap: Keymap,
+    ) -> Result<Vec<RecordedEvent>, AutomationError> {
+        let mut events = Vec::new();
+        let mut origin: Option<u32> = None;
+
+        for reply in data.record_enable_context(context)? {
+            let reply = reply?;
+            if reply.category == RECORD_END_OF_DATA {
+                break;
+            }
+            if reply.category != RECORD_FROM_SERVER || reply.client_swapped {
+                continue;
+            }
+            let mut remaining = &reply.data[..];
+            while remaining.len() >= 32 {
+                let (time, input) = parse_event(remaining, &keymap)?;
+                remaining = &remaining[32..];
+                if let Some(input) = input {
+                    let origin = *origin.get_or_insert(time);
+                    events.push(RecordedEvent {
+                        time_ms: time.wrapping_sub(origin) as u64,
+                        input,
+                    });
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// 解析一个 32 字节的核心设备事件，返回服务器时间戳和转换后的输入
+    fn parse_event(data: &[u8], keymap: &Keymap) -> Result<(u32, Option<RecordedInput>), AutomationError> {
+        let parsed = match data[0] & 0x7f {
+            xproto::KEY_PRESS_EVENT => {
+                let (event, _) = xproto::KeyPressEvent::try_parse(data)?;
+                let (key, text) = translate_key(keymap, event.detail, event.state);
+                (event.time, Some(RecordedInput::KeyDown { key, text }))
+            }
+            xproto::KEY_RELEASE_EVENT => {
+                let (event, _) = xproto::KeyReleaseEvent::try_parse(data)?;
+                let (key, _) = translate_key(keymap, event.detail, event.state);
+                (event.time, Some(RecordedInput::KeyUp { key }))
+            }
+            xproto::BUTTON_PRESS_EVENT => {
+                let (event, _) = xproto::ButtonPressEvent::try_parse(data)?;
+                let (x, y) = (event.root_x as i32, event.root_y as i32);
+                let input = match event.detail {
+                    4 => RecordedInput::Scroll { delta_x: 0, delta_y: -1 },
+                    5 => RecordedInput::Scroll { delta_x: 0, delta_y: 1 },
+                    6 => RecordedInput::Scroll { delta_x: -1, delta_y: 0 },
+                    7 => RecordedInput::Scroll { delta_x: 1, delta_y: 0 },
+                    detail => RecordedInput::ButtonDown {
+                        button: mouse_button(detail),
+                        x,
+                        y,
+                    },
+                };
+                (event.time, Some(input))
+            }
+            xproto::BUTTON_RELEASE_EVENT => {
+                let (event, _) = xproto::ButtonReleaseEvent::try_parse(data)?;
+                let input = match event.detail {
+                    4..=7 => None,
+                    detail => Some(RecordedInput::ButtonUp {
+                        button: mouse_button(detail),
+                        x: event.root_x as i32,
+                        y: event.root_y as i32,
+                    }),
+                };
+                (event.time, input)
+            }
+            xproto::MOTION_NOTIFY_EVENT => {
+                let (event, _) = xproto::MotionNotifyEvent::try_parse(data)?;
+                let input = RecordedInput::MouseMove {
+                    x: event.root_x as i32,
+                    y: event.root_y as i32,
+                };
+                (event.time, Some(input))
+            }
+            _ => (0, None),
+        };
+        Ok(parsed)
+    }
+
+    fn mouse_button(detail: u8) -> MouseButton {
+        match detail {
+            1 => MouseButton::Left,
+            2 => MouseButton::Middle,
+            3 => MouseButton::Right,
+            8 => MouseButton::Back,
+            9 => MouseButton::Forward,
+            other => MouseButton::Other(other),
+        }
+    }
+
+    fn translate_key(keymap: &Keymap, keycode: u8, state: KeyButMask) -> (Key, Option<char>) {
+        let shift = state.contains(KeyButMask::SHIFT);
+        let caps = state.contains(KeyButMask::LOCK);
+        let keysym = keymap.keysym(keycode, shift);
+        let text = keysym_to_char(keysym).map(|c| {
+            if caps && c.is_alphabetic() {
+                if shift {
+                    c.to_lowercase().next().unwrap_or(c)
+                } else {
+                    c.to_uppercase().next().unwrap_or(c)
+                }
+            } else {
+                c
+            }
+        });
+        let key = keysym_to_key(keymap.keysym(keycode, false)).unwrap_or(Key::Other(keysym));
+        (key, text)
+    }
+
+    /// 将可打印 keysym 转为字符（Latin-1 与 Unicode keysym）
+    fn keysym_to_char(keysym: u32) -> Option<char> {
+        match keysym {
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+            0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+            _ => None,
+        }
+    }
+
+    /// 将 keysym 映射为逻辑按键
+    pub(crate) fn keysym_to_key(keysym: u32) -> Option<Key> {
+        let key = match keysym {
+            0xff08 => Key::Backspace,
+            0xff09 => Key::Tab,
+            0xff0d => Key::Enter,
+            0xff1b => Key::Escape,
+            0xff50 => Key::Home,
+            0xff51 => Key::ArrowLeft,
+            0xff52 => Key::ArrowUp,
+            0xff53 => Key::ArrowRight,
+            0xff54 => Key::ArrowDown,
+            0xff55 => Key::PageUp,
+            0xff56 => Key::PageDown,
+            0xff57 => Key::End,
+            0xff61 => Key::PrintScreen,
+            0xff63 => Key::Insert,
+            0xff67 => Key::ContextMenu,
+            0xffff => Key::Delete,
+            0xffe1 | 0xffe2 => Key::Shift,
+            0xffe3 | 0xffe4 => Key::Ctrl,
+            0xffe9 | 0xffea => Key::Alt,
+            0xffe7 | 0xffe8 | 0xffeb | 0xffec => Key::Meta,
+            0xffe5 => Key::CapsLock,
+            0xffbe => Key::F1,
+            0xffbf => Key::F2,
+            0xffc0 => Key::F3,
+            0xffc1 => Key::F4,
+            0xffc2 => Key::F5,
+            0xffc3 => Key::F6,
+            0xffc4 => Key::F7,
+            0xffc5 => Key::F8,
+            0xffc6 => Key::F9,
+            0xffc7 => Key::F10,
+            0xffc8 => Key::F11,
+            0xffc9 => Key::F12,
+            0xffb0 => Key::Numpad0,
+            0xffb1 => Key::Numpad1,
+            0xffb2 => Key::Numpad2,
+            0xffb3 => Key::Numpad3,
+            0xffb4 => Key::Numpad4,
+            0xffb5 => Key::Numpad5,
+            0xffb6 => Key::Numpad6,
+            0xffb7 => Key::Numpad7,
+            0xffb8 => Key::Numpad8,
+            0xffb9 => Key::Numpad9,
+            0xffaa => Key::NumpadMultiply,
+            0xffab => Key::NumpadAdd,
+            0xffad => Key::NumpadSubtract,
+            0xffae => Key::NumpadDecimal,
+            0xffaf => Key::NumpadDivide,
+            0xff8d => Key::NumpadEnter,
+            0x1008ff12 => Key::VolumeMute,
+            0x1008ff11 => Key::VolumeDown,
+            0x1008ff13 => Key::VolumeUp,
+            0x1008ff14 => Key::MediaPlayPause,
+            0x1008ff15 => Key::MediaStop,
+            0x1008ff17 => Key::MediaNextTrack,
+            0x1008ff16 => Key::MediaPreviousTrack,
+            other => return keysym_to_char(other).and_then(|c| KeyMapper::new().key_for_char(c)),
+        };
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(time_ms: u64, input: RecordedInput) -> RecordedEvent {
+        RecordedEvent { time_ms, input }
+    }
+
+    fn down(time_ms: u64, x: i32, y: i32) -> RecordedEvent {
+        event(time_ms, RecordedInput::ButtonDown { button: MouseButton::Left, x, y })
+    }
+
+    fn up(time_ms: u64, x: i32, y: i32) -> RecordedEvent {
+        event(time_ms, RecordedInput::ButtonUp { button: MouseButton::Left, x, y })
+    }
+
+    fn key_down(time_ms: u64, key: Key, text: Option<char>) -> RecordedEvent {
+        event(time_ms, RecordedInput::KeyDown { key, text })
+    }
+
+    fn key_up(time_ms: u64, key: Key) -> RecordedEvent {
+        event(time_ms, RecordedInput::KeyUp { key })
+    }
+
+    /// 比较合并结果与预期的动作列表（预期中省略的可选字段为 None）
+    fn assert_actions(events: &[RecordedEvent], options: &CoalesceOptions, expected: serde_json::Value) {
+        let expected: InputAction = serde_json::from_value(json!({"type": "Sequence", "params": {"actions": expected}}))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(coalesce(events, options)).unwrap(),
+            serde_json::to_value(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn clicks_become_double_clicks_drags_and_long_presses() {
+        let events = [
+            down(0, 10, 10),
+            up(50, 10, 10),
+            down(200, 11, 10),
+            up(250, 11, 10),
+            down(1000, 10, 10),
+            up(1100, 200, 150),
+            down(2000, 30, 30),
+            up(2600, 30, 30),
+        ];
+        assert_actions(
+            &events,
+            &CoalesceOptions::default(),
+            json!([
+                {"type": "MouseDoubleClick", "params": {"button": "Left", "x": 10, "y": 10}},
+                {"type": "Delay", "params": {"milliseconds": 750}},
+                {"type": "MouseDrag", "params": {
+                    "button": "Left", "start_x": 10, "start_y": 10, "end_x": 200, "end_y": 150, "duration_ms": 100
+                }},
+                {"type": "Delay", "params": {"milliseconds": 900}},
+                {"type": "MousePress", "params": {"button": "Left", "x": 30, "y": 30, "duration_ms": 600}}
+            ]),
+        );
+    }
+
+    #[test]
+    fn typing_becomes_text_and_chords_become_hotkeys() {
+        let events = [
+            key_down(0, Key::Shift, None),
+            key_down(10, Key::H, Some('H')),
+            key_up(20, Key::H),
+            key_up(30, Key::Shift),
+            key_down(110, Key::I, Some('i')),
+            key_up(120, Key::I),
+            key_down(500, Key::Ctrl, None),
+            key_down(510, Key::S, Some('s')),
+            key_up(520, Key::S),
+            key_up(530, Key::Ctrl),
+            key_down(600, Key::Meta, None),
+            key_up(610, Key::Meta),
+        ];
+        assert_actions(
+            &events,
+            &CoalesceOptions::default(),
+            json!([
+                {"type": "TypeText", "params": {"text": "Hi", "char_delay_ms": 100}},
+                {"type": "Delay", "params": {"milliseconds": 400}},
+                {"type": "Hotkey", "params": {"modifiers": ["Ctrl"], "key": "S"}},
+                {"type": "Delay", "params": {"milliseconds": 90}},
+                {"type": "KeyPress", "params": {"key": "Meta"}}
+            ]),
+        );
+    }
+
+    #[test]
+    fn long_pauses_split_text() {
+        let events = [
+            key_down(0, Key::A, Some('a')),
+            key_down(2000, Key::B, Some('b')),
+            key_down(2005, Key::Enter, None),
+        ];
+        let options = CoalesceOptions {
+            min_delay_ms: 10,
+            ..CoalesceOptions::default()
+        };
+        assert_actions(
+            &events,
+            &options,
+            json!([
+                {"type": "TypeText", "params": {"text": "a"}},
+                {"type": "Delay", "params": {"milliseconds": 2000}},
+                {"type": "TypeText", "params": {"text": "b"}},
+                {"type": "KeyPress", "params": {"key": "Enter"}}
+            ]),
+        );
+    }
+
+    #[test]
+    fn scrolls_merge_and_moves_are_optional() {
+        let events = [
+            event(0, RecordedInput::MouseMove { x: 5, y: 5 }),
+            event(10, RecordedInput::MouseMove { x: 50, y: 60 }),
+            event(20, RecordedInput::Scroll { delta_x: 0, delta_y: 1 }),
+            event(40, RecordedInput::Scroll { delta_x: 0, delta_y: 1 }),
+            event(60, RecordedInput::Scroll { delta_x: 1, delta_y: 0 }),
+        ];
+        assert_actions(
+            &events,
+            &CoalesceOptions::default(),
+            json!([
+                {"type": "MouseMove", "params": {"x": 50, "y": 60}},
+                {"type": "MouseScroll", "params": {"delta_x": 1, "delta_y": 2}}
+            ]),
+        );
+        let options = CoalesceOptions {
+            record_moves: false,
+            ..CoalesceOptions::default()
+        };
+        assert_actions(
+            &events,
+            &options,
+            json!([{"type": "MouseScroll", "params": {"delta_x": 1, "delta_y": 2}}]),
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn records_injected_input() {
+        use enigo::{Button, Coordinate, Direction, Enigo, Key as EnigoKey, Keyboard, Mouse, Settings};
+        use std::time::Duration;
+
+        if !crate::testing::display_available() {
+            return;
+        }
+        let recorder = Recorder::start(None).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let mut enigo = Enigo::new(&Settings::default()).unwrap();
+        enigo.move_mouse(40, 30, Coordinate::Abs).unwrap();
+        enigo.button(Button::Left, Direction::Click).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        for c in ['h', 'i'] {
+            enigo.key(EnigoKey::Unicode(c), Direction::Click).unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        let events = recorder.stop().unwrap();
+
+        let options = CoalesceOptions {
+            record_moves: false,
+            ..CoalesceOptions::default()
+        };
+        let InputAction::Sequence { actions } = coalesce(&events, &options) else {
+            panic!("coalesce returns a Sequence");
+        };
+        assert!(
+            actions
+                .iter()
+                .any(|action| matches!(action, InputAction::MouseClick { button: MouseButton::Left, x: 40, y: 30, .. })),
+            "{:?}",
+            actions
+        );
+        assert!(
+            actions
+                .iter()
+                .any(|action| matches!(action, InputAction::TypeText { text, .. } if text == "hi")),
+            "{:?}",
+            actions
+        );
+    }
+}
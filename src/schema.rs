@@ -0,0 +1,49 @@
+use schemars::{schema_for, Schema};
+
+use crate::enums::{InputAction, Key, KeyLocation, MouseButton};
+
+/// 仓库中导出的 InputAction Schema 文件路径（相对于 crate 根目录）
+pub const INPUT_ACTION_SCHEMA_PATH: &str = "schema/input_action.schema.json";
+
+/// 生成 InputAction 的 JSON Schema（包含 Key、MouseButton、KeyLocation 定义）
+pub fn input_action_schema() -> Schema {
+    schema_for!(InputAction)
+}
+
+/// 生成 Key 的 JSON Schema
+pub fn key_schema() -> Schema {
+    schema_for!(Key)
+}
+
+/// 生成 MouseButton 的 JSON Schema
+pub fn mouse_button_schema() -> Schema {
+    schema_for!(MouseButton)
+}
+
+/// 生成 KeyLocation 的 JSON Schema
+pub fn key_location_schema() -> Schema {
+    schema_for!(KeyLocation)
+}
+
+/// 以格式化 JSON 字符串形式输出 InputAction 的 Schema
+pub fn input_action_schema_json() -> String {
+    let mut json = serde_json::to_string_pretty(&input_action_schema())
+        .expect("schema serialization cannot fail");
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn committed_schema_is_up_to_date() {
+        let committed = include_str!("../schema/input_action.schema.json");
+        assert!(
+            committed == input_action_schema_json(),
+            "{} is out of date, run `just schema` to regenerate it",
+            INPUT_ACTION_SCHEMA_PATH
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use image::{ImageFormat, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AutomationError;
+
+/// 屏幕上的矩形区域（屏幕绝对坐标）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    /// 判断点是否位于区域内
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && (x as i64) < self.x as i64 + self.width as i64
+            && (y as i64) < self.y as i64 + self.height as i64
+    }
+
+    /// 与另一个区域的交集，没有交集时返回 None
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x as i64 + self.width as i64).min(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).min(other.y as i64 + other.height as i64);
+        if right <= left as i64 || bottom <= top as i64 {
+            return None;
+        }
+        Some(Rect {
+            x: left,
+            y: top,
+            width: (right - left as i64) as u32,
+            height: (bottom - top as i64) as u32,
+        })
+    }
+
+    /// 同时包含两个区域的最小区域
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x as i64 + self.width as i64).max(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).max(other.y as i64 + other.height as i64);
+        Rect {
+            x: left,
+            y: top,
+            width: (right - left as i64) as u32,
+            height: (bottom - top as i64) as u32,
+        }
+    }
+
+    /// 区域中心点
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.x + (self.width / 2) as i32,
+            self.y + (self.height / 2) as i32,
+        )
+    }
+}
+
+/// RGB 颜色
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// 每个通道的差值都不超过 tolerance 时视为匹配
+    pub fn matches(&self, other: &Color, tolerance: u8) -> bool {
+        self.r.abs_diff(other.r) <= tolerance
+            && self.g.abs_diff(other.g) <= tolerance
+            && self.b.abs_diff(other.b) <= tolerance
+    }
+}
+
+impl From<image::Rgba<u8>> for Color {
+    fn from(pixel: image::Rgba<u8>) -> Self {
+        let [r, g, b, _] = pixel.0;
+        Color { r, g, b }
+    }
+}
+
+/// 显示器信息
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct DisplayInfo {
+    /// 显示器编号（从 0 开始，用于 capture_display）
+    pub id: u32,
+    pub name: String,
+    pub primary: bool,
+    /// 显示器在整个虚拟屏幕中的位置和尺寸
+    pub bounds: Rect,
+}
+
+/// 将截图编码为 PNG 字节
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, AutomationError> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|err| AutomationError::Image(err.to_string()))?;
+    Ok(bytes)
+}
+
+/// 将截图保存为 PNG 文件
+pub fn save_png(image: &RgbaImage, path: impl AsRef<Path>) -> Result<(), AutomationError> {
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|err| AutomationError::Image(err.to_string()))
+}
+
+/// 截取整个虚拟屏幕（所有显示器）
+#[cfg(target_os = "linux")]
+pub fn capture_screen() -> Result<RgbaImage, AutomationError> {
+    ScreenCapturer::connect(None)?.capture_screen()
+}
+
+/// 截取屏幕上的指定区域
+#[cfg(target_os = "linux")]
+pub fn capture_region(rect: Rect) -> Result<RgbaImage, AutomationError> {
+    ScreenCapturer::connect(None)?.capture_region(rect)
+}
+
+/// 截取指定编号的显示器
+#[cfg(target_os = "linux")]
+pub fn capture_display(id: u32) -> Result<RgbaImage, AutomationError> {
+    ScreenCapturer::connect(None)?.capture_display(id)
+}
+
+/// 读取屏幕上单个像素的颜色
+#[cfg(target_os = "linux")]
+pub fn pixel_at(x: i32, y: i32) -> Result<Color, AutomationError> {
+    ScreenCapturer::connect(None)?.pixel_at(x, y)
+}
+
+/// 列出所有显示器
+#[cfg(target_os = "linux")]
+pub fn list_displays() -> Result<Vec<DisplayInfo>, AutomationError> {
+    ScreenCapturer::connect(None)?.displays()
+}
+
+#[cfg(target_os = "linux")]
+pub use self::x11::ScreenCapturer;
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use image::RgbaImage;
+    use x11rb::connection::Connection;
+    use x11rb::image::{BitsPerPixel, Image, ImageOrder, PixelLayout};
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::xproto::{ConnectionExt as _, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    use super::{Color, DisplayInfo, Rect};
+    use crate::error::AutomationError;
+
+    /// 基于 X11 GetImage 的截屏器，复用同一个连接以便频繁截图
+    pub struct ScreenCapturer {
+        conn: RustConnection,
+        root: Window,
+        width: u32,
+        height: u32,
+        layout: PixelLayout,
+    }
+
+    impl ScreenCapturer {
+        /// 连接到指定显示（None 表示使用 DISPLAY 环境变量）
+        pub fn connect(display: Option<&str>) -> Result<Self, AutomationError> {
+            let (conn, screen_num) = x11rb::connect(display)?;
+            let screen = &conn.setup().roots[screen_num];
+            let visual = screen
+                .allowed_depths
+                .iter()
+                .flat_map(|depth| depth.visuals.iter())
+                .find(|visual| visual.visual_id == screen.root_visual)
+                .copied()
+                .ok_or_else(|| AutomationError::Platform("root visual not found".to_string()))?;
+            let layout = PixelLayout::from_visual_type(visual)?;
+            let (root, width, height) = (
+                screen.root,
+                screen.width_in_pixels as u32,
+                screen.height_in_pixels as u32,
+            );
+            Ok(ScreenCapturer {
+                conn,
+                root,
+                width,
+                height,
+                layout,
+            })
+        }
+
+        /// 整个虚拟屏幕的尺寸
+        pub fn screen_size(&self) -> (u32, u32) {
+            (self.width, self.height)
+        }
+
+        /// 列出所有显示器（RandR 不可用时把整个屏幕作为一个显示器）
+        pub fn displays(&self) -> Result<Vec<DisplayInfo>, AutomationError> {
+            let monitors = match self.conn.randr_get_monitors(self.root, true) {
+                Ok(cookie) => cookie.reply().map(|reply| reply.monitors).unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            if monitors.is_empty() {
+                return Ok(vec![DisplayInfo {
+                    id: 0,
+                    name: "default".to_string(),
+                    primary: true,
+                    bounds: Rect::new(0, 0, self.width, self.height),
+                }]);
+            }
+
+            let mut displays = Vec::with_capacity(monitors.len());
+            for (id, monitor) in monitors.iter().enumerate() {
+                let name = self
+                    .conn
+                    .get_atom_name(monitor.name)?
+                    .reply()
+                    .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+                    .unwrap_or_default();
+                displays.push(DisplayInfo {
+                    id: id as u32,
+                    name,
+                    primary: monitor.primary,
+                    bounds: Rect::new(
+                        monitor.x as i32,
+                        monitor.y as i32,
+                        monitor.width as u32,
+                        monitor.height as u32,
+                    ),
+                });
+            }
+            Ok(displays)
+        }
+
+        /// 截取整个虚拟屏幕
+        pub fn capture_screen(&self) -> Result<RgbaImage, AutomationError> {
+            self.capture_region(Rect::new(0, 0, self.width, self.height))
+        }
+
+        /// 截取指定编号的显示器
+        pub fn capture_display(&self, id: u32) -> Result<RgbaImage, AutomationError> {
+            let display = self
+                .displays()?
+                .into_iter()
+                .find(|display| display.id == id)
+                .ok_or_else(|| AutomationError::Platform(format!("display {} not found", id)))?;
+            self.capture_region(display.bounds)
+        }
+
+        /// 裁掉区域中超出屏幕的部分，即 `capture_region` 实际截取的区域
+        pub fn clip(&self, rect: Rect) -> Result<Rect, AutomationError> {
+            rect.intersect(&Rect::new(0, 0, self.width, self.height))
+                .ok_or_else(|| AutomationError::Platform(format!("region {:?} is outside the screen", rect)))
+        }
+
+        /// 截取指定区域（超出屏幕的部分会被裁掉，见 `clip`）
+        pub fn capture_region(&self, rect: Rect) -> Result<RgbaImage, AutomationError> {
+            let rect = self.clip(rect)?;
+            let (image, _) = Image::get(
+                &self.conn,
+                self.root,
+                rect.x as i16,
+                rect.y as i16,
+                rect.width as u16,
+                rect.height as u16,
+            )?;
+            Ok(self.to_rgba(&image))
+        }
+
+        /// 读取单个像素的颜色
+        pub fn pixel_at(&self, x: i32, y: i32) -> Result<Color, AutomationError> {
+            let image = self.capture_region(Rect::new(x, y, 1, 1))?;
+            Ok(Color::from(*image.get_pixel(0, 0)))
+        }
+
+        fn to_rgba(&self, image: &Image<'_>) -> RgbaImage {
+            let (width, height) = (image.width() as u32, image.height() as u32);
+            let is_bgrx = image.bits_per_pixel() == BitsPerPixel::B32
+                && image.byte_order() == ImageOrder::LsbFirst
+                && self.layout.decode(0x00ff_0000) == (0xffff, 0, 0)
+                && self.layout.decode(0x0000_ff00) == (0, 0xffff, 0)
+                && self.layout.decode(0x0000_00ff) == (0, 0, 0xffff);
+
+            if is_bgrx {
+                // 常见的 24/32 位深度：每像素 4 字节 B、G、R、X，直接转换
+                let stride = image.data().len() / height.max(1) as usize;
+                let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+                for row in image.data().chunks(stride).take(height as usize) {
+                    for pixel in row[..width as usize * 4].chunks_exact(4) {
+                        rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+                    }
+                }
+                return RgbaImage::from_raw(width, height, rgba).unwrap_or_default();
+            }
+
+            RgbaImage::from_fn(width, height, |x, y| {
+                let (r, g, b) = self.layout.decode(image.get_pixel(x as u16, y as u16));
+                image::Rgba([(r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8, 255])
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_contains_uses_half_open_bounds() {
+        let rect = Rect::new(10, 20, 30, 40);
+        assert!(rect.contains(10, 20));
+        assert!(rect.contains(39, 59));
+        assert!(!rect.contains(40, 20));
+        assert!(!rect.contains(10, 60));
+        assert!(!rect.contains(9, 20));
+        assert!(!Rect::new(0, 0, 0, 10).contains(0, 0));
+    }
+
+    #[test]
+    fn rect_intersect_and_union() {
+        let a = Rect::new(0, 0, 100, 50);
+        let b = Rect::new(80, 40, 50, 50);
+        assert_eq!(a.intersect(&b), Some(Rect::new(80, 40, 20, 10)));
+        assert_eq!(b.intersect(&a), a.intersect(&b));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 130, 90));
+        // 只有边相接时没有交集
+        assert_eq!(a.intersect(&Rect::new(100, 0, 10, 10)), None);
+        // 负坐标区域裁剪到屏幕内
+        let screen = Rect::new(0, 0, 1920, 1080);
+        assert_eq!(Rect::new(-10, -20, 50, 50).intersect(&screen), Some(Rect::new(0, 0, 40, 30)));
+        assert_eq!(Rect::new(1900, 1000, 50, 200).intersect(&screen), Some(Rect::new(1900, 1000, 20, 80)));
+    }
+
+    #[test]
+    fn rect_math_does_not_overflow() {
+        let huge = Rect::new(i32::MAX - 1, 0, u32::MAX, 10);
+        assert!(huge.contains(i32::MAX, 5));
+        assert_eq!(huge.intersect(&Rect::new(0, 0, 10, 10)), None);
+    }
+
+    #[test]
+    fn rect_center_and_color_tolerance() {
+        assert_eq!(Rect::new(10, 10, 5, 4).center(), (12, 12));
+        let color = Color::new(100, 150, 200);
+        assert!(color.matches(&Color::new(105, 145, 200), 5));
+        assert!(!color.matches(&Color::new(106, 150, 200), 5));
+        assert_eq!(Color::from(image::Rgba([1, 2, 3, 0])), Color::new(1, 2, 3));
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let image = RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8 * 50, y as u8 * 100, 7, 255]));
+        let bytes = encode_png(&image).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn captures_screen_regions_and_displays() {
+        if !crate::testing::display_available() {
+            return;
+        }
+        let capturer = ScreenCapturer::connect(None).unwrap();
+        let (width, height) = capturer.screen_size();
+        let screen = capturer.capture_screen().unwrap();
+        assert_eq!(screen.dimensions(), (width, height));
+
+        // 超出屏幕的部分被裁掉
+        let region = capturer.capture_region(Rect::new(-5, -5, 20, 10)).unwrap();
+        assert_eq!(region.dimensions(), (15, 5));
+        assert_eq!(*region.get_pixel(0, 0), *screen.get_pixel(0, 0));
+        let edge = capturer
+            .capture_region(Rect::new(width as i32 - 4, height as i32 - 4, 10, 10))
+            .unwrap();
+        assert_eq!(edge.dimensions(), (4, 4));
+        assert!(capturer.capture_region(Rect::new(width as i32, 0, 10, 10)).is_err());
+
+        let displays = capturer.displays().unwrap();
+        assert!(!displays.is_empty());
+        let display = capturer.capture_display(displays[0].id).unwrap();
+        assert_eq!(display.dimensions(), (displays[0].bounds.width, displays[0].bounds.height));
+        assert!(capturer.capture_display(displays.len() as u32).is_err());
+
+        let pixel = capturer.pixel_at(1, 1).unwrap();
+        assert_eq!(pixel, Color::from(*screen.get_pixel(1, 1)));
+    }
+}
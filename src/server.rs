@@ -0,0 +1,1164 @@
+use std::future::{ready, Ready};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{
+    delete, get, post, web, FromRequest, HttpMessage, HttpRequest, HttpResponse, HttpServer, Responder, ResponseError,
+};
+use actix_ws::{Message, MessageStream, Session};
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::auth::{AuthConfig, AuthError, Authenticator, Principal, TlsConfig, TokenConfig};
+use crate::enums::InputAction;
+use crate::error::AutomationError;
+use crate::events::{EventHub, LiveEvent};
+use crate::executor::{ActionExecutor, ExecutionReport};
+use crate::fail_safe::{FailSafe, FailSafeConfig};
+use crate::jobs::{JobError, JobId, JobInfo, JobQueue, JobQueueOptions};
+use crate::policy::Policy;
+use crate::schema::input_action_schema_json;
+use crate::screen::DisplayInfo;
+use crate::timing::TimingPolicy;
+use crate::trigger::{TriggerConfig, TriggerError, TriggerManager};
+use crate::validator::{Diagnostic, ValidationOptions};
+use crate::webdriver::WebDriverSessions;
+
+/// 指定配置文件路径的环境变量
+pub const CONFIG_ENV: &str = "AUTOMATION_SERVER_CONFIG";
+
+/// automation-server 的配置
+///
+/// 从 TOML 文件读取，`AUTOMATION_SERVER_*` 环境变量优先于文件中的值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 监听地址，如 "127.0.0.1:8080"（AUTOMATION_SERVER_BIND）
+    pub bind: String,
+    /// HTTP 工作线程数，不指定时使用 CPU 核数（AUTOMATION_SERVER_WORKERS）
+    pub workers: Option<usize>,
+    /// 执行前校验，设为 false 时直接执行（AUTOMATION_SERVER_VALIDATE）
+    pub validate: bool,
+    /// 校验选项，未指定屏幕尺寸时使用当前主屏幕尺寸
+    pub validation: ValidationOptions,
+    /// 执行器的时间策略
+    pub timing: TimingPolicy,
+    /// 执行策略；服务端没有确认回调，需要确认的动作一律拒绝
+    pub policy: Policy,
+    /// 光标进入屏幕角落或按下紧急停止热键时中止执行，不配置时只能通过 POST /fail-safe/trip 触发
+    pub fail_safe: Option<FailSafeConfig>,
+    /// 全局热键触发器，按下热键时把动作提交到任务队列
+    pub triggers: Vec<TriggerConfig>,
+    /// 任务队列选项
+    pub jobs: JobQueueOptions,
+    /// gRPC 监听地址，如 "127.0.0.1:50051"，不指定时不启动 gRPC 服务（需要 grpc feature，AUTOMATION_SERVER_GRPC_BIND）
+    pub grpc_bind: Option<String>,
+    /// 认证、权限和限流，未配置 token 和客户端证书时不做认证
+    pub auth: AuthConfig,
+    /// TLS 终止，HTTP 和 gRPC 接口共用（需要 tls feature）
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "127.0.0.1:8080".to_string(),
+            workers: None,
+            validate: true,
+            validation: ValidationOptions::default(),
+            timing: TimingPolicy::default(),
+            policy: Policy::default(),
+            fail_safe: None,
+            triggers: Vec::new(),
+            jobs: JobQueueOptions::default(),
+            grpc_bind: None,
+            auth: AuthConfig::default(),
+            tls: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 读取配置：path 为空时使用 AUTOMATION_SERVER_CONFIG 指定的文件，都没有时使用默认值
+    pub fn load(path: Option<PathBuf>) -> std::io::Result<Self> {
+        let path = path.or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None => ServerConfig::default(),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// 从 TOML 文件读取配置
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid config {}: {}", path.display(), err),
+            )
+        })
+    }
+
+    /// 用 AUTOMATION_SERVER_* 环境变量覆盖配置
+    pub fn apply_env(&mut self) -> std::io::Result<()> {
+        if let Some(bind) = env("BIND") {
+            self.bind = bind;
+        }
+        if let Some(workers) = env("WORKERS") {
+            self.workers = Some(parse_env("WORKERS", &workers)?);
+        }
+        if let Some(validate) = env("VALIDATE") {
+            self.validate = parse_env("VALIDATE", &validate)?;
+        }
+        if let Some(grpc_bind) = env("GRPC_BIND") {
+            self.grpc_bind = Some(grpc_bind);
+        }
+        // 额外添加一个拥有全部权限的 token，便于在部署时通过环境变量注入
+        if let Some(token) = env("TOKEN") {
+            self.auth.tokens.push(TokenConfig {
+                name: "AUTOMATION_SERVER_TOKEN".to_string(),
+                token,
+                permissions: Default::default(),
+                rate_limit: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// 按配置创建执行器
+    pub fn executor(&self) -> ActionExecutor {
+        let executor = ActionExecutor::new()
+            .with_timing(self.timing.clone())
+            .with_policy(self.policy.clone());
+        if self.validate {
+            executor.with_validation(self.validation.clone())
+        } else {
+            executor
+        }
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(format!("AUTOMATION_SERVER_{}", name))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_env<T: std::str::FromStr>(name: &str, value: &str) -> std::io::Result<T> {
+    value.parse().map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid AUTOMATION_SERVER_{}: {:?}", name, value),
+        )
+    })
+}
+
+/// 启动 HTTP 服务并一直运行到进程收到退出信号
+pub async fn run(config: ServerConfig) -> std::io::Result<()> {
+    let auth = Arc::new(Authenticator::new(&config.auth)?);
+    config.policy.check_rules().map_err(|err| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid policy: {}", err))
+    })?;
+    if !auth.is_enabled()
+        && config
+            .bind
+            .parse::<SocketAddr>()
+            .map_or(true, |addr| !addr.ip().is_loopback())
+    {
+        warn!("authentication is disabled and {} is not a loopback address; anyone who can connect controls this machine", config.bind);
+    }
+    #[cfg(not(feature = "tls"))]
+    if config.tls.is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "tls requires the `tls` feature",
+        ));
+    }
+    #[cfg(feature = "tls")]
+    let tls = config.tls.as_ref().map(crate::tls::server_config).transpose()?;
+
+    let fail_safe = match &config.fail_safe {
+        Some(fail_safe) => FailSafe::start(fail_safe, None)
+            .map_err(|err| std::io::Error::other(format!("failed to start fail-safe: {}", err)))?,
+        None => FailSafe::new(),
+    };
+    let executor = Arc::new(config.executor().with_fail_safe(fail_safe));
+    let jobs = web::Data::new(JobQueue::start(executor.clone(), config.jobs.clone()));
+    if let Some(grpc_bind) = &config.grpc_bind {
+        spawn_grpc(grpc_bind, executor.clone(), jobs.get_ref().clone(), auth.clone(), config.tls.as_ref()).await?;
+    }
+    let triggers = if config.triggers.is_empty() {
+        None
+    } else {
+        let triggers = TriggerManager::start(config.triggers.clone(), jobs.get_ref().clone(), None)
+            .map_err(|err| std::io::Error::other(format!("failed to start triggers: {}", err)))?;
+        Some(triggers)
+    };
+    let triggers = web::Data::new(triggers);
+    let executor = web::Data::from(executor);
+    let sessions = web::Data::new(WebDriverSessions::new());
+    let auth = web::Data::from(auth);
+    info!(
+        "automation-server listening on {}://{}",
+        if config.tls.is_some() { "https" } else { "http" },
+        config.bind
+    );
+    let mut server = HttpServer::new(move || {
+        actix_web::App::new()
+            .app_data(executor.clone())
+            .app_data(jobs.clone())
+            .app_data(sessions.clone())
+            .app_data(triggers.clone())
+            .app_data(auth.clone())
+            .wrap(from_fn(authenticate))
+            .configure(routes)
+    });
+    if let Some(workers) = config.workers {
+        server = server.workers(workers);
+    }
+    #[cfg(feature = "tls")]
+    let server = match tls {
+        Some(tls) => server
+            .on_connect(crate::tls::on_connect)
+            .bind_rustls_0_23(config.bind.as_str(), tls)?,
+        None => server.bind(config.bind.as_str())?,
+    };
+    #[cfg(not(feature = "tls"))]
+    let server = server.bind(config.bind.as_str())?;
+    server.run().await
+}
+
+/// 认证并限流，通过后把 `Principal` 放入请求扩展中；
+/// token 通过 `Authorization: Bearer` 头传递，浏览器中的 EventSource 和 WebSocket 无法设置请求头，
+/// /events 和 /ws 也可以使用 `?access_token=`
+async fn authenticate(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if let Some(auth) = request.app_data::<web::Data<Authenticator>>() {
+        let principal = auth
+            .authenticate(
+                bearer_token(request.request()).as_deref(),
+                client_common_name(request.request()).as_deref(),
+                request.peer_addr().map(|addr| addr.ip()),
+            )
+            .and_then(|principal| auth.throttle(&principal).map(|_| principal))
+            .map_err(ApiError::from)?;
+        request.extensions_mut().insert(principal);
+    }
+    next.call(request).await
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    access_token: String,
+}
+
+fn bearer_token(request: &HttpRequest) -> Option<String> {
+    let header = request
+        .headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    // 其他路由不接受查询参数中的 token，以免 token 出现在访问日志和浏览器历史中
+    if header.is_some() || !matches!(request.path(), "/events" | "/ws") {
+        return header;
+    }
+    web::Query::<TokenQuery>::from_query(request.query_string())
+        .ok()
+        .map(|query| query.into_inner().access_token)
+}
+
+#[cfg(feature = "tls")]
+fn client_common_name(request: &HttpRequest) -> Option<String> {
+    request
+        .conn_data::<crate::tls::ClientCertificate>()
+        .and_then(|cert| cert.common_name.clone())
+}
+
+#[cfg(not(feature = "tls"))]
+fn client_common_name(_request: &HttpRequest) -> Option<String> {
+    None
+}
+
+/// 认证中间件放入的调用方；没有经过中间件（例如嵌入到其他应用中）时拥有全部权限
+impl FromRequest for Principal {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let principal = request.extensions().get::<Principal>().cloned();
+        ready(Ok(principal.unwrap_or_else(|| Principal::anonymous("anonymous"))))
+    }
+}
+
+/// 在后台启动 gRPC 服务，监听失败或 TLS 配置有误时直接返回错误
+#[cfg(feature = "grpc")]
+async fn spawn_grpc(
+    bind: &str,
+    executor: Arc<ActionExecutor>,
+    jobs: JobQueue,
+    auth: Arc<Authenticator>,
+    tls: Option<&TlsConfig>,
+) -> std::io::Result<()> {
+    let router = crate::grpc::router(executor, jobs, auth, tls)?;
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    info!("gRPC listening on {}", bind);
+    tokio::spawn(async move {
+        if let Err(err) = crate::grpc::serve(listener, router).await {
+            tracing::error!("gRPC server stopped: {}", err);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(feature = "grpc"))]
+async fn spawn_grpc(
+    _bind: &str,
+    _executor: Arc<ActionExecutor>,
+    _jobs: JobQueue,
+    _auth: Arc<Authenticator>,
+    _tls: Option<&TlsConfig>,
+) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "grpc_bind requires the `grpc` feature",
+    ))
+}
+
+/// 注册全部路由（包括 WebDriver 路由），供嵌入到其他 actix-web 应用中使用
+/// （需要 `web::Data<ActionExecutor>`、`web::Data<JobQueue>` 和 `web::Data<WebDriverSessions>`）；
+/// 认证中间件不包含在内，未认证的请求拥有全部权限
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(execute_action)
+        .service(validate_action)
+        .service(dry_run_action)
+        .service(action_schema)
+        .service(screenshot)
+        .service(screen_info)
+        .service(cursor_position)
+        .service(release_all)
+        .service(fail_safe_status)
+        .service(trip_fail_safe)
+        .service(reset_fail_safe)
+        .service(submit_job)
+        .service(list_jobs)
+        .service(get_job)
+        .service(cancel_job)
+        .service(list_triggers)
+        .service(get_trigger)
+        .service(enable_trigger)
+        .service(disable_trigger)
+        .service(event_stream)
+        .service(websocket)
+        .configure(crate::webdriver::routes);
+}
+
+/// 出错时的响应体
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErrorResponse {
+    /// 错误类别：validation、platform、image、timeout、policy、aborted、not_found、conflict、queue_full、
+    /// unauthorized、forbidden、rate_limited
+    pub kind: String,
+    pub error: String,
+    /// 校验失败时的诊断信息
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// 屏幕信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScreenInfo {
+    /// 主屏幕尺寸
+    pub width: u32,
+    pub height: u32,
+    pub displays: Vec<DisplayInfo>,
+}
+
+/// 光标位置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CursorPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// 释放结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReleaseResponse {
+    pub released: Vec<String>,
+}
+
+/// 紧急停止状态
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FailSafeStatus {
+    /// 已触发时的原因，未触发时为空
+    pub tripped: Option<String>,
+}
+
+/// 提交任务的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitJob {
+    pub action: InputAction,
+    /// 优先级，数值大的先执行
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// 实时事件的过滤条件
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct EventFilter {
+    /// 只接收指定任务的事件
+    pub job: Option<JobId>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &LiveEvent) -> bool {
+        self.job.is_none_or(|job| event.job() == Some(job))
+    }
+}
+
+/// WebSocket 客户端发送的请求，id 会原样出现在对应的回复中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketRequest {
+    #[serde(default)]
+    pub id: Option<u64>,
+    pub command: SocketCommand,
+}
+
+/// WebSocket 命令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "params")]
+pub enum SocketCommand {
+    /// 立即执行（不经过任务队列），用于交互式远程控制
+    Execute(InputAction),
+    /// 提交到任务队列
+    Submit {
+        action: InputAction,
+        #[serde(default)]
+        priority: i32,
+    },
+    /// 取消任务
+    Cancel { job: JobId },
+    /// 释放仍被按住的键和鼠标按键
+    ReleaseAll,
+}
+
+/// WebSocket 服务端发送的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "params")]
+pub enum SocketMessage {
+    /// 实时事件
+    Event(LiveEvent),
+    Executed { id: Option<u64>, report: ExecutionReport },
+    Submitted { id: Option<u64>, job: JobInfo },
+    Cancelled { id: Option<u64>, job: JobInfo },
+    Released { id: Option<u64>, released: Vec<String> },
+    Error { id: Option<u64>, error: ErrorResponse },
+}
+
+/// 带状态码的 JSON 错误响应
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    body: ErrorResponse,
+    /// 限流时通过 Retry-After 头告知客户端
+    retry_after: Option<Duration>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, kind: &str, error: String, diagnostics: Vec<Diagnostic>) -> Self {
+        ApiError {
+            status,
+            body: ErrorResponse {
+                kind: kind.to_string(),
+                error,
+                diagnostics,
+            },
+            retry_after: None,
+        }
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(err: AuthError) -> Self {
+        let error = err.to_string();
+        match err {
+            AuthError::Unauthorized(_) => ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", error, Vec::new()),
+            AuthError::Forbidden(_) => ApiError::new(StatusCode::FORBIDDEN, "forbidden", error, Vec::new()),
+            AuthError::RateLimited { retry_after } => ApiError {
+                retry_after: Some(retry_after),
+                ..ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", error, Vec::new())
+            },
+        }
+    }
+}
+
+impl From<AutomationError> for ApiError {
+    fn from(err: AutomationError) -> Self {
+        let error = err.to_string();
+        match err {
+            AutomationError::Validation(diagnostics) => {
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation", error, diagnostics)
+            }
+            AutomationError::Platform(_) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "platform", error, Vec::new()),
+            AutomationError::Image(_) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "image", error, Vec::new()),
+            AutomationError::Timeout(_) => ApiError::new(StatusCode::GATEWAY_TIMEOUT, "timeout", error, Vec::new()),
+            AutomationError::Policy(_) => ApiError::new(StatusCode::FORBIDDEN, "policy", error, Vec::new()),
+            AutomationError::Aborted(_) => ApiError::new(StatusCode::CONFLICT, "aborted", error, Vec::new()),
+        }
+    }
+}
+
+impl From<JobError> for ApiError {
+    fn from(err: JobError) -> Self {
+        let (status, kind) = match err {
+            JobError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            JobError::AlreadyFinished(_) => (StatusCode::CONFLICT, "conflict"),
+            JobError::QueueFull(_) => (StatusCode::SERVICE_UNAVAILABLE, "queue_full"),
+        };
+        ApiError::new(status, kind, err.to_string(), Vec::new())
+    }
+}
+
+impl From<TriggerError> for ApiError {
+    fn from(err: TriggerError) -> Self {
+        match err {
+            TriggerError::NotFound(_) => ApiError::new(StatusCode::NOT_FOUND, "not_found", err.to_string(), Vec::new()),
+            TriggerError::Invalid(_) => {
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation", err.to_string(), Vec::new())
+            }
+            TriggerError::Platform(err) => err.into(),
+            TriggerError::Job(err) => err.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.body.error)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status);
+        if self.status == StatusCode::UNAUTHORIZED {
+            response.insert_header(("WWW-Authenticate", "Bearer"));
+        }
+        if let Some(retry_after) = self.retry_after {
+            // Retry-After 以秒为单位，向上取整
+            response.insert_header(("Retry-After", retry_after.as_millis().div_ceil(1000).to_string()));
+        }
+        response.json(&self.body)
+    }
+}
+
+// 执行动作，返回执行报告
+#[post("/execute")]
+async fn execute_action(
+    action: web::Json<InputAction>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Received {} from {}", action.name(), principal.name);
+    principal.permissions.check(&action)?;
+    match executor.execute_observed(&action, jobs.events().forward(None)).await {
+        Ok(report) => {
+            info!("Executed {} in {}ms", action.name(), report.duration_ms);
+            Ok(HttpResponse::Ok().json(report))
+        }
+        Err(err) => {
+            info!("Execution failed: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+// 校验动作但不执行；与执行一样检查权限，受限的 token 不能借此探测被禁止的脚本
+#[post("/validate")]
+async fn validate_action(
+    action: web::Json<InputAction>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check(&action)?;
+    Ok(HttpResponse::Ok().json(executor.validate(&action)))
+}
+
+// 演练动作：返回事件时间线和预计耗时
+#[post("/dry-run")]
+async fn dry_run_action(
+    action: web::Json<InputAction>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check(&action)?;
+    Ok(HttpResponse::Ok().json(executor.dry_run(&action).await?))
+}
+
+// 返回 InputAction 的 JSON Schema
+#[get("/schema")]
+async fn action_schema() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(input_action_schema_json())
+}
+
+// 截取整个屏幕并返回 PNG
+#[get("/screenshot")]
+async fn screenshot(principal: Principal) -> actix_web::Result<HttpResponse> {
+    principal.permissions.check_capture().map_err(ApiError::from)?;
+    let png = web::block(capture_png).await?.map_err(ApiError::from)?;
+    Ok(HttpResponse::Ok().content_type("image/png").body(png))
+}
+
+// 主屏幕尺寸和显示器布局
+#[get("/screen")]
+async fn screen_info(principal: Principal, executor: web::Data<ActionExecutor>) -> actix_web::Result<HttpResponse> {
+    principal.permissions.check_capture().map_err(ApiError::from)?;
+    let (width, height) = executor.get_screen_size();
+    let displays = web::block(list_displays).await?.map_err(ApiError::from)?;
+    Ok(HttpResponse::Ok().json(ScreenInfo {
+        width,
+        height,
+        displays,
+    }))
+}
+
+// 光标的当前位置
+#[get("/cursor")]
+async fn cursor_position(principal: Principal, executor: web::Data<ActionExecutor>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_capture()?;
+    let (x, y) = executor.cursor_position().await?;
+    Ok(HttpResponse::Ok().json(CursorPosition { x, y }))
+}
+
+// 释放仍被按住的键和鼠标按键
+#[post("/release-all")]
+async fn release_all(principal: Principal, executor: web::Data<ActionExecutor>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    let released = executor.release_all().await?;
+    info!("Released held inputs for {}: {:?}", principal.name, released);
+    Ok(HttpResponse::Ok().json(ReleaseResponse { released }))
+}
+
+// 紧急停止状态
+#[get("/fail-safe")]
+async fn fail_safe_status(principal: Principal, executor: web::Data<ActionExecutor>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    let tripped = executor.fail_safe().and_then(FailSafe::tripped);
+    Ok(HttpResponse::Ok().json(FailSafeStatus { tripped }))
+}
+
+// 触发紧急停止：中止正在执行的动作并释放按住的输入，之后的执行一律拒绝
+#[post("/fail-safe/trip")]
+async fn trip_fail_safe(principal: Principal, executor: web::Data<ActionExecutor>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    if let Some(fail_safe) = executor.fail_safe() {
+        fail_safe.trip(format!("tripped by {}", principal.name));
+    }
+    warn!("Fail-safe tripped by {}", principal.name);
+    let tripped = executor.fail_safe().and_then(FailSafe::tripped);
+    Ok(HttpResponse::Ok().json(FailSafeStatus { tripped }))
+}
+
+// 解除紧急停止
+#[post("/fail-safe/reset")]
+async fn reset_fail_safe(principal: Principal, executor: web::Data<ActionExecutor>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    if let Some(fail_safe) = executor.fail_safe() {
+        fail_safe.reset();
+    }
+    info!("Fail-safe reset by {}", principal.name);
+    let tripped = executor.fail_safe().and_then(FailSafe::tripped);
+    Ok(HttpResponse::Ok().json(FailSafeStatus { tripped }))
+}
+
+// 提交任务，立即返回任务编号，执行结果通过 GET /jobs/{id} 查询
+#[post("/jobs")]
+async fn submit_job(
+    request: web::Json<SubmitJob>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let SubmitJob { action, priority } = request.into_inner();
+    principal.permissions.check_jobs()?;
+    principal.permissions.check(&action)?;
+    executor.check(&action)?;
+    let job = jobs.submit_as(&principal.name, action, priority)?;
+    info!("Queued job {} from {} (priority {})", job.id, principal.name, job.priority);
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+// 列出调用方可以查看的排队中、执行中和保留的已结束任务
+#[get("/jobs")]
+async fn list_jobs(principal: Principal, jobs: web::Data<JobQueue>) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_jobs()?;
+    let owned: Vec<JobInfo> = jobs
+        .list()
+        .into_iter()
+        .filter(|job| principal.owns(job.owner.as_deref()))
+        .collect();
+    Ok(HttpResponse::Ok().json(owned))
+}
+
+// 列出全局热键触发器
+#[get("/triggers")]
+async fn list_triggers(
+    principal: Principal,
+    triggers: web::Data<Option<TriggerManager>>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    let triggers = triggers.get_ref().as_ref().map(TriggerManager::list).unwrap_or_default();
+    Ok(HttpResponse::Ok().json(triggers))
+}
+
+// 查询触发器
+#[get("/triggers/{name}")]
+async fn get_trigger(
+    name: web::Path<String>,
+    principal: Principal,
+    triggers: web::Data<Option<TriggerManager>>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    Ok(HttpResponse::Ok().json(trigger_manager(&triggers, &name)?.get(&name)?))
+}
+
+// 启用触发器并抓取热键
+#[post("/triggers/{name}/enable")]
+async fn enable_trigger(
+    name: web::Path<String>,
+    principal: Principal,
+    triggers: web::Data<Option<TriggerManager>>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    let trigger = trigger_manager(&triggers, &name)?.enable(&name)?;
+    info!("Enabled trigger {:?} ({})", trigger.name, trigger.hotkey);
+    Ok(HttpResponse::Ok().json(trigger))
+}
+
+// 停用触发器并释放热键
+#[post("/triggers/{name}/disable")]
+async fn disable_trigger(
+    name: web::Path<String>,
+    principal: Principal,
+    triggers: web::Data<Option<TriggerManager>>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_admin()?;
+    let trigger = trigger_manager(&triggers, &name)?.disable(&name)?;
+    info!("Disabled trigger {:?} ({})", trigger.name, trigger.hotkey);
+    Ok(HttpResponse::Ok().json(trigger))
+}
+
+/// 未配置任何触发器时所有名称都不存在
+fn trigger_manager<'a>(triggers: &'a Option<TriggerManager>, name: &str) -> Result<&'a TriggerManager, TriggerError> {
+    triggers.as_ref().ok_or_else(|| TriggerError::NotFound(name.to_string()))
+}
+
+/// 查询调用方可以查看的任务，其他调用方的任务视为不存在
+fn owned_job(principal: &Principal, jobs: &JobQueue, id: JobId) -> Result<JobInfo, ApiError> {
+    principal.permissions.check_jobs()?;
+    match jobs.get(id)? {
+        job if principal.owns(job.owner.as_deref()) => Ok(job),
+        _ => Err(JobError::NotFound(id).into()),
+    }
+}
+
+/// 调用方是否可以收到该事件：任务事件只推送给任务的提交者，直接执行的动作的事件只推送给 admin
+fn visible(principal: &Principal, jobs: &JobQueue, event: &LiveEvent) -> bool {
+    match event {
+        LiveEvent::Job(job) => principal.owns(job.owner.as_deref()),
+        LiveEvent::Action { job: Some(id), .. } => jobs
+            .get(*id)
+            .is_ok_and(|job| principal.owns(job.owner.as_deref())),
+        LiveEvent::Action { job: None, .. } => principal.owns(None),
+    }
+}
+
+// 查询任务状态和进度
+#[get("/jobs/{id}")]
+async fn get_job(id: web::Path<JobId>, principal: Principal, jobs: web::Data<JobQueue>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(owned_job(&principal, &jobs, *id)?))
+}
+
+// 取消排队中或执行中的任务
+#[delete("/jobs/{id}")]
+async fn cancel_job(id: web::Path<JobId>, principal: Principal, jobs: web::Data<JobQueue>) -> Result<HttpResponse, ApiError> {
+    let job = jobs.cancel(owned_job(&principal, &jobs, *id)?.id)?;
+    info!("Cancelling job {} for {}", job.id, principal.name);
+    Ok(HttpResponse::Ok().json(job))
+}
+
+// 以 Server-Sent Events 推送调用方可以查看的实时事件，可用 ?job=ID 只接收指定任务的事件
+#[get("/events")]
+async fn event_stream(
+    filter: web::Query<EventFilter>,
+    principal: Principal,
+    jobs: web::Data<JobQueue>,
+) -> Result<HttpResponse, ApiError> {
+    principal.permissions.check_events()?;
+    let filter = filter.into_inner();
+    let state = (jobs.events().subscribe(), principal, jobs.get_ref().clone());
+    let events = stream::unfold(state, move |(mut receiver, principal, jobs)| async move {
+        loop {
+            let chunk = match receiver.recv().await {
+                Ok(event) if filter.matches(&event) && visible(&principal, &jobs, &event) => {
+                    format!("data: {}\n\n", serde_json::to_string(&event).unwrap_or_default())
+                }
+                Ok(_) => continue,
+                // 订阅者跟不上时丢弃了部分事件，以注释行告知客户端
+                Err(RecvError::Lagged(skipped)) => format!(": lagged {}\n\n", skipped),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), (receiver, principal, jobs)));
+        }
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events))
+}
+
+// WebSocket：推送实时事件，同时接收 SocketRequest 命令
+#[get("/ws")]
+async fn websocket(
+    request: HttpRequest,
+    body: web::Payload,
+    filter: web::Query<EventFilter>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+) -> actix_web::Result<HttpResponse> {
+    let (response, session, messages) = actix_ws::handle(&request, body)?;
+    let client = SocketClient {
+        principal,
+        auth: request.app_data::<web::Data<Authenticator>>().cloned(),
+    };
+    actix_web::rt::spawn(serve_socket(
+        session,
+        messages,
+        filter.into_inner(),
+        client,
+        executor.into_inner(),
+        jobs.get_ref().clone(),
+    ));
+    Ok(response)
+}
+
+/// WebSocket 连接的调用方，每条命令单独限流
+struct SocketClient {
+    principal: Principal,
+    auth: Option<web::Data<Authenticator>>,
+}
+
+impl SocketClient {
+    /// 限流并按对应 HTTP 接口的规则检查权限
+    fn authorize(&self, command: &SocketCommand, jobs: &JobQueue) -> Result<(), ApiError> {
+        if let Some(auth) = &self.auth {
+            auth.throttle(&self.principal)?;
+        }
+        let permissions = &self.principal.permissions;
+        match command {
+            SocketCommand::Execute(action) => permissions.check(action)?,
+            SocketCommand::Submit { action, .. } => {
+                permissions.check_jobs()?;
+                permissions.check(action)?;
+            }
+            SocketCommand::Cancel { job } => {
+                owned_job(&self.principal, jobs, *job)?;
+            }
+            SocketCommand::ReleaseAll => permissions.check_admin()?,
+        }
+        Ok(())
+    }
+
+    /// 是否推送该事件（与 /events 相同）
+    fn receives(&self, filter: &EventFilter, jobs: &JobQueue, event: &LiveEvent) -> bool {
+        self.principal.permissions.events && filter.matches(event) && visible(&self.principal, jobs, event)
+    }
+}
+
+async fn serve_socket(
+    mut session: Session,
+    mut messages: MessageStream,
+    filter: EventFilter,
+    client: SocketClient,
+    executor: Arc<ActionExecutor>,
+    jobs: JobQueue,
+) {
+    let mut events = jobs.events().subscribe();
+    loop {
+        tokio::select! {
+            message = messages.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_command(&session, &text, &client, &executor, &jobs).await;
+                }
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) if client.receives(&filter, &jobs, &event) => {
+                    if send(&session, &SocketMessage::Event(event)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+    let _ = session.close(None).await;
+}
+
+/// 处理一条命令；执行和释放需要等待输入设备，放到单独的任务中以免阻塞事件推送
+async fn handle_command(
+    session: &Session,
+    text: &str,
+    client: &SocketClient,
+    executor: &Arc<ActionExecutor>,
+    jobs: &JobQueue,
+) {
+    let request: SocketRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = ApiError::new(StatusCode::BAD_REQUEST, "bad_request", err.to_string(), Vec::new());
+            let _ = send(session, &SocketMessage::Error { id: None, error: error.body }).await;
+            return;
+        }
+    };
+    let id = request.id;
+    if let Err(err) = client.authorize(&request.command, jobs) {
+        let _ = send(session, &SocketMessage::Error { id, error: err.body }).await;
+        return;
+    }
+    let reply = match request.command {
+        SocketCommand::Execute(action) => {
+            let (session, executor, events) = (session.clone(), executor.clone(), jobs.events().clone());
+            actix_web::rt::spawn(async move {
+                let reply = match execute_forwarded(&executor, &action, &events).await {
+                    Ok(report) => SocketMessage::Executed { id, report },
+                    Err(err) => SocketMessage::Error { id, error: err.body },
+                };
+                let _ = send(&session, &reply).await;
+            });
+            return;
+        }
+        SocketCommand::ReleaseAll => {
+            let (session, executor) = (session.clone(), executor.clone());
+            actix_web::rt::spawn(async move {
+                let reply = match executor.release_all().await {
+                    Ok(released) => SocketMessage::Released { id, released },
+                    Err(err) => SocketMessage::Error { id, error: ApiError::from(err).body },
+                };
+                let _ = send(&session, &reply).await;
+            });
+            return;
+        }
+        SocketCommand::Submit { action, priority } => match executor
+            .check(&action)
+            .map_err(ApiError::from)
+            .and_then(|_| jobs.submit_as(&client.principal.name, action, priority).map_err(ApiError::from))
+        {
+            Ok(job) => SocketMessage::Submitted { id, job },
+            Err(err) => SocketMessage::Error { id, error: err.body },
+        },
+        SocketCommand::Cancel { job } => match jobs.cancel(job) {
+            Ok(job) => SocketMessage::Cancelled { id, job },
+            Err(err) => SocketMessage::Error { id, error: ApiError::from(err).body },
+        },
+    };
+    let _ = send(session, &reply).await;
+}
+
+async fn execute_forwarded(
+    executor: &ActionExecutor,
+    action: &InputAction,
+    events: &EventHub,
+) -> Result<ExecutionReport, ApiError> {
+    Ok(executor.execute_observed(action, events.forward(None)).await?)
+}
+
+async fn send(session: &Session, message: &SocketMessage) -> Result<(), actix_ws::Closed> {
+    let text = serde_json::to_string(message).unwrap_or_default();
+    session.clone().text(text).await
+}
+
+#[cfg(target_os = "linux")]
+fn capture_png() -> Result<Vec<u8>, AutomationError> {
+    crate::screen::capture_screen().and_then(|image| crate::screen::encode_png(&image))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture_png() -> Result<Vec<u8>, AutomationError> {
+    Err(AutomationError::Platform(
+        "screen capture is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn list_displays() -> Result<Vec<DisplayInfo>, AutomationError> {
+    crate::screen::list_displays()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_displays() -> Result<Vec<DisplayInfo>, AutomationError> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Permissions;
+    use crate::events::ExecutionEvent;
+    use crate::simulator::InputSimulator;
+    use actix_web::http::Method;
+    use actix_web::test;
+    use serde_json::json;
+
+    const ALICE: &str = "alice-token-0123456789";
+    const BOB: &str = "bob-token-0123456789";
+    const ROOT: &str = "root-token-0123456789";
+
+    fn token(name: &str, token: &str, permissions: Permissions) -> TokenConfig {
+        TokenConfig {
+            name: name.to_string(),
+            token: token.to_string(),
+            permissions,
+            rate_limit: None,
+        }
+    }
+
+    fn request(method: Method, uri: &str, token: &str) -> test::TestRequest {
+        test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+    }
+
+    #[actix_web::test]
+    async fn routes_check_scopes_and_job_owners() {
+        let user = Permissions { admin: false, ..Permissions::default() };
+        let auth = Authenticator::new(&AuthConfig {
+            tokens: vec![
+                token("alice", ALICE, user.clone()),
+                token("bob", BOB, Permissions { capture: false, events: false, deny: vec!["keyboard".to_string()], ..user }),
+                token("root", ROOT, Permissions::default()),
+            ],
+            ..AuthConfig::default()
+        })
+        .unwrap();
+        // 没有输入设备，任务会立即失败，但仍保留记录
+        let executor = Arc::new(ActionExecutor::with_simulator(InputSimulator::detached((800, 600))));
+        let jobs = JobQueue::start(executor.clone(), JobQueueOptions::default());
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(web::Data::from(executor))
+                .app_data(web::Data::new(jobs))
+                .app_data(web::Data::new(None::<TriggerManager>))
+                .app_data(web::Data::new(auth))
+                .wrap(from_fn(authenticate))
+                .configure(routes),
+        )
+        .await;
+        let status = |request: test::TestRequest| {
+            let app = &app;
+            async move {
+                match test::try_call_service(app, request.to_request()).await {
+                    Ok(response) => response.status(),
+                    Err(error) => error.as_response_error().status_code(),
+                }
+            }
+        };
+
+        let submit = request(Method::POST, "/jobs", ALICE)
+            .set_json(json!({"action": {"type": "Delay", "params": {"milliseconds": 1}}}));
+        let job: JobInfo = test::call_and_read_body_json(&app, submit.to_request()).await;
+        assert_eq!(job.owner.as_deref(), Some("alice"));
+        let uri = format!("/jobs/{}", job.id);
+
+        assert_eq!(status(request(Method::GET, &uri, ALICE)).await, StatusCode::OK);
+        assert_eq!(status(request(Method::GET, &uri, ROOT)).await, StatusCode::OK);
+        assert_eq!(status(request(Method::GET, &uri, BOB)).await, StatusCode::NOT_FOUND);
+        assert_eq!(status(request(Method::DELETE, &uri, BOB)).await, StatusCode::NOT_FOUND);
+        let listed: Vec<JobInfo> = test::call_and_read_body_json(&app, request(Method::GET, "/jobs", BOB).to_request()).await;
+        assert!(listed.is_empty());
+        let listed: Vec<JobInfo> = test::call_and_read_body_json(&app, request(Method::GET, "/jobs", ROOT).to_request()).await;
+        assert_eq!(listed.len(), 1);
+
+        for (method, uri) in [
+            (Method::GET, "/fail-safe"),
+            (Method::GET, "/triggers"),
+            (Method::GET, "/triggers/greeting"),
+            (Method::POST, "/release-all"),
+            (Method::POST, "/fail-safe/trip"),
+            (Method::POST, "/fail-safe/reset"),
+            (Method::POST, "/triggers/greeting/enable"),
+            (Method::POST, "/triggers/greeting/disable"),
+        ] {
+            assert_eq!(status(request(method, uri, ALICE)).await, StatusCode::FORBIDDEN, "{}", uri);
+        }
+        assert_eq!(status(request(Method::GET, "/cursor", BOB)).await, StatusCode::FORBIDDEN);
+        let typing = json!({"type": "TypeText", "params": {"text": "secret"}});
+        for uri in ["/validate", "/dry-run"] {
+            let probe = |token| request(Method::POST, uri, token).set_json(&typing);
+            assert_eq!(status(probe(ALICE)).await, StatusCode::OK, "{}", uri);
+            assert_eq!(status(probe(BOB)).await, StatusCode::FORBIDDEN, "{}", uri);
+        }
+        assert_eq!(status(request(Method::GET, "/screen", BOB)).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(request(Method::GET, "/events", BOB)).await, StatusCode::FORBIDDEN);
+
+        // 查询参数中的 token 只用于 /events 和 /ws
+        let query = |uri: &str| test::TestRequest::get().uri(&format!("{}?access_token={}", uri, ALICE));
+        assert_eq!(status(query("/jobs")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(query("/events")).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn events_are_visible_to_job_owners_and_admins() {
+        let executor = Arc::new(ActionExecutor::with_simulator(InputSimulator::detached((800, 600))));
+        let jobs = JobQueue::start(executor, JobQueueOptions::default());
+        let job = jobs
+            .submit_as("alice", serde_json::from_value(json!({"type": "Delay", "params": {"milliseconds": 1}})).unwrap(), 0)
+            .unwrap();
+        let user = |name: &str| {
+            let mut principal = Principal::anonymous(name);
+            principal.permissions = Arc::new(Permissions { admin: false, ..Permissions::default() });
+            principal
+        };
+        let started = ExecutionEvent::ActionStarted { path: "$".into(), action: "Delay".into() };
+        let action = |job| LiveEvent::Action { job, event: started.clone() };
+
+        assert!(visible(&user("alice"), &jobs, &LiveEvent::Job(job.clone())));
+        assert!(visible(&user("alice"), &jobs, &action(Some(job.id))));
+        assert!(!visible(&user("bob"), &jobs, &LiveEvent::Job(job.clone())));
+        assert!(!visible(&user("bob"), &jobs, &action(Some(job.id))));
+        assert!(!visible(&user("alice"), &jobs, &action(None)));
+        assert!(visible(&Principal::anonymous("root"), &jobs, &action(None)));
+    }
+}
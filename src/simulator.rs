@@ -1,13 +1,117 @@
 use enigo::*;
 use enigo::Key as EnigoKey;
-use crate::enums::InputAction;
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::backend::{InputBackend, SharedDevice};
+use crate::dry_run::{DryRunBackend, DryRunReport};
+use crate::enums::{InputAction, MouseButton, Origin};
+use crate::error::AutomationError;
+use crate::events::{EventSender, ExecutionEvent};
+use crate::fail_safe::FailSafe;
+use crate::held::{HeldInputs, HeldInputsBackend};
+use crate::policy::{ConfirmRequest, Confirmation, Policy, Verdict};
+use crate::screen::{Color, Rect};
+use crate::window::{InputDelivery, InputTarget, WindowCommand, WindowEventBackend, WindowSelector};
+use crate::vision::{frame_difference, match_template, offset_matches, ImageMatch, ImageSource};
+use image::RgbaImage;
+use crate::timing::{DeadlineBackend, PacedBackend, StepTiming, TimingPolicy};
 use super::{keyboard::KeyboardSimulator, mouse::MouseSimulator};
-use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+/// 屏幕条件的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 激活目标窗口后等待窗口管理器切换焦点的时间
+const FOCUS_SETTLE: Duration = Duration::from_millis(50);
+
+/// 判断屏幕变化时每个颜色通道允许的差值，用于忽略抗锯齿、渐变等细微抖动
+const CHANGE_TOLERANCE: u8 = 16;
+
+/// 单次执行过程中的状态
+struct RunState {
+    /// 已定位的锚点（名称 -> 屏幕区域）
+    anchors: HashMap<String, Rect>,
+    /// 当前动作在树中的路径
+    path: String,
+    events: Option<EventSender>,
+    /// 演练模式下需要确认的动作视为已确认
+    dry_run: bool,
+    /// 不发送到真实设备时（演练、Parallel 分支）按住的输入，为空时使用模拟器记录的输入
+    held: Option<Arc<std::sync::Mutex<HeldInputs>>>,
+}
+
+impl RunState {
+    fn new(events: Option<EventSender>) -> Self {
+        RunState {
+            anchors: HashMap::new(),
+            path: "$".to_string(),
+            events,
+            dry_run: false,
+            held: None,
+        }
+    }
+
+    fn emit(&self, event: ExecutionEvent) {
+        if let Some(events) = &self.events {
+            // 接收方已关闭时不再关心事件
+            let _ = events.send(event);
+        }
+    }
+
+    /// 当前按住的输入
+    fn held(&self, simulator: &InputSimulator) -> HeldInputs {
+        match &self.held {
+            Some(held) => held.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone(),
+            None => simulator.held_inputs(),
+        }
+    }
+
+    fn log(&self, message: String) {
+        if self.events.is_some() {
+            self.emit(ExecutionEvent::Log {
+                path: self.path.clone(),
+                message,
+            });
+        }
+    }
+
+    /// 保存查找结果；后端无法观察屏幕时以查找区域作为假定位置
+    fn locate(&mut self, anchor: &Option<String>, located: Option<Rect>, searched: Rect) {
+        if let Some(name) = anchor {
+            let rect = located.unwrap_or(searched);
+            self.log(format!("anchor {:?} set to {:?}", name, rect));
+            self.anchors.insert(name.clone(), rect);
+        }
+    }
+}
+
+/// 屏幕轮询结果
+enum Polled<T> {
+    /// 条件已满足
+    Found(T),
+    /// 后端无法观察屏幕（演练模式），条件视为满足
+    Unobserved,
+    /// 超时，附带最后一次截图
+    TimedOut(RgbaImage),
+}
 
 /// 输入模拟器核心实现
 pub struct InputSimulator {
-    enigo: Arc<Mutex<Enigo>>,
+    /// 输入设备，detached 创建的模拟器没有设备；执行过程中按事件加锁
+    device: SharedDevice,
+    /// 保证同一时刻只有一个执行在使用设备
+    running: Arc<Mutex<()>>,
+    held: Arc<std::sync::Mutex<HeldInputs>>,
+    /// 上一个键盘鼠标动作结束的时刻，用于在先后的执行之间保持最小动作间隔
+    last_input: Arc<std::sync::Mutex<Option<Instant>>>,
+    screen_size: (u32, u32),
+    timing: TimingPolicy,
+    policy: Policy,
+    confirmation: Option<Confirmation>,
+    fail_safe: Option<FailSafe>,
     keyboard: KeyboardSimulator,
     mouse: MouseSimulator,
 }
@@ -15,8 +119,29 @@ pub struct InputSimulator {
 impl InputSimulator {
     /// 创建新的输入模拟器实例
     pub fn new() -> Self {
+        let enigo = Enigo::new(&Settings::default()).unwrap();
+        let screen_size = enigo
+            .main_display()
+            .map(|(width, height)| (width.max(0) as u32, height.max(0) as u32))
+            .unwrap_or((0, 0));
         InputSimulator {
-            enigo: Arc::new(Mutex::new(Enigo::new(&Settings::default()).unwrap())),
+            device: SharedDevice::new(enigo),
+            ..Self::detached(screen_size)
+        }
+    }
+
+    /// 创建不连接输入设备的模拟器，只能演练（不需要图形环境）
+    pub fn detached(screen_size: (u32, u32)) -> Self {
+        InputSimulator {
+            device: SharedDevice::default(),
+            running: Arc::default(),
+            held: Arc::default(),
+            last_input: Arc::default(),
+            screen_size,
+            timing: TimingPolicy::default(),
+            policy: Policy::default(),
+            confirmation: None,
+            fail_safe: None,
             keyboard: KeyboardSimulator::new(),
             mouse: MouseSimulator::new(),
         }
@@ -24,48 +149,620 @@ impl InputSimulator {
 
     /// 获取屏幕尺寸
     pub fn get_screen_size(&self) -> (u32, u32) {
-        
-        (0,0)
+        self.screen_size
+    }
+
+    /// 获取时间策略
+    pub fn timing_policy(&self) -> &TimingPolicy {
+        &self.timing
+    }
+
+    /// 设置时间策略
+    pub fn set_timing_policy(&mut self, policy: TimingPolicy) {
+        self.timing = policy;
+    }
+
+    /// 获取执行策略
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// 设置执行策略，每个动作执行前按策略检查
+    pub fn set_policy(&mut self, policy: Policy) {
+        self.policy = policy;
+    }
+
+    /// 设置需要确认的动作使用的确认回调
+    pub fn set_confirmation(&mut self, confirmation: Confirmation) {
+        self.confirmation = Some(confirmation);
+    }
+
+    /// 获取紧急停止开关
+    pub fn fail_safe(&self) -> Option<&FailSafe> {
+        self.fail_safe.as_ref()
+    }
+
+    /// 设置紧急停止开关，触发时中止执行并释放按住的输入
+    pub fn set_fail_safe(&mut self, fail_safe: FailSafe) {
+        self.fail_safe = Some(fail_safe);
     }
 
     /// 执行单个输入动作
-    pub async fn execute_action(&self, action: &InputAction) -> String {
-        let mut enigo = self.enigo.lock().unwrap();
-        self.execute_with_enigo(&mut enigo, action).await
+    pub async fn execute_action(&self, action: &InputAction) -> Result<String, AutomationError> {
+        self.execute_action_timed(action).await.map(|(result, _)| result)
+    }
+
+    /// 执行单个输入动作，并返回每次等待的计划与实际时间
+    pub async fn execute_action_timed(
+        &self,
+        action: &InputAction,
+    ) -> Result<(String, Vec<StepTiming>), AutomationError> {
+        self.execute_timed_in(action, RunState::new(None)).await
+    }
+
+    /// 执行单个输入动作，并把每个动作的开始、完成和失败事件发送到 events
+    pub async fn execute_action_observed(
+        &self,
+        action: &InputAction,
+        events: EventSender,
+    ) -> Result<(String, Vec<StepTiming>), AutomationError> {
+        self.execute_timed_in(action, RunState::new(Some(events))).await
+    }
+
+    async fn execute_timed_in(
+        &self,
+        action: &InputAction,
+        state: RunState,
+    ) -> Result<(String, Vec<StepTiming>), AutomationError> {
+        let Some(fail_safe) = &self.fail_safe else {
+            return self.execute_guarded(action, state).await;
+        };
+        if let Some(reason) = fail_safe.tripped() {
+            return Err(self.abort(reason).await);
+        }
+        tokio::select! {
+            biased;
+            reason = fail_safe.wait() => Err(self.abort(reason).await),
+            result = self.execute_guarded(action, state) => result,
+        }
+    }
+
+    /// 紧急停止后释放按住的输入，返回 Aborted 错误
+    async fn abort(&self, reason: String) -> AutomationError {
+        aborted(reason, self.release_all().await)
+    }
+
+    async fn execute_guarded(
+        &self,
+        action: &InputAction,
+        mut state: RunState,
+    ) -> Result<(String, Vec<StepTiming>), AutomationError> {
+        if !self.device.is_connected() {
+            return Err(no_device());
+        }
+        let _running = self.running.lock().await;
+        let mut device = self.device.clone();
+        let mut tracked = HeldInputsBackend::new(&mut device, self.held.clone());
+        let last_input = *self.last_input.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut scheduler = DeadlineBackend::new(&mut tracked, &self.timing).with_last_input(last_input);
+        let mut backend = PacedBackend::new(&mut scheduler, &self.timing);
+        let result = self.execute_in(&mut backend, action, &mut state).await;
+        *self.last_input.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = scheduler.last_input();
+        Ok((result?, scheduler.into_timings()))
+    }
+
+    /// 释放执行过程中按下后仍未释放的键和鼠标按键，返回被释放输入的描述
+    pub async fn release_all(&self) -> Result<Vec<String>, AutomationError> {
+        if !self.device.is_connected() {
+            return Err(no_device());
+        }
+        let held = std::mem::take(&mut *self.held.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        held.release(&mut self.device.clone())
+    }
+
+    /// 当前仍处于按下状态的输入
+    pub fn held_inputs(&self) -> HeldInputs {
+        self.held.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// 获取鼠标光标的当前位置
+    pub async fn cursor_position(&self) -> Result<(i32, i32), AutomationError> {
+        self.device
+            .with(|enigo| enigo.location())
+            .ok_or_else(no_device)?
+            .map_err(|err| AutomationError::Platform(err.to_string()))
+    }
+
+    /// 演练执行：不触碰真实设备，返回将要发出的事件时间线和预计耗时
+    pub async fn dry_run(&self, action: &InputAction) -> Result<DryRunReport, AutomationError> {
+        let gap = self.timing.scale(Duration::from_millis(self.timing.min_action_gap_ms));
+        let held = Arc::new(std::sync::Mutex::new(self.held_inputs()));
+        let mut dry_run = DryRunBackend::new(self.screen_size)
+            .with_min_action_gap(gap)
+            .with_held(held.clone());
+        let mut backend = PacedBackend::new(&mut dry_run, &self.timing);
+        let mut state = RunState::new(None);
+        state.dry_run = true;
+        state.held = Some(held);
+        let result = self.execute_in(&mut backend, action, &mut state).await?;
+        Ok(dry_run.into_report(result))
+    }
+
+    /// 使用指定的输入后端执行动作
+    ///
+    /// 同样受紧急停止控制：触发后中止执行，并通过该后端释放这次执行中按住的输入
+    pub async fn execute_with_backend<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+    ) -> Result<String, AutomationError> {
+        let held = Arc::new(std::sync::Mutex::new(HeldInputs::default()));
+        let mut tracked = HeldInputsBackend::new(enigo, held.clone());
+        let mut state = RunState::new(None);
+        state.held = Some(held.clone());
+        let Some(fail_safe) = &self.fail_safe else {
+            return self.execute_in(&mut tracked, action, &mut state).await;
+        };
+        let reason = match fail_safe.tripped() {
+            Some(reason) => reason,
+            None => tokio::select! {
+                biased;
+                reason = fail_safe.wait() => reason,
+                result = self.execute_in(&mut tracked, action, &mut state) => return result,
+            },
+        };
+        let held = std::mem::take(&mut *held.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        Err(aborted(reason, held.release(enigo)))
+    }
+
+    /// 执行动作并发出开始、完成或失败事件
+    async fn execute_in<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+        state: &mut RunState,
+    ) -> Result<String, AutomationError> {
+        let path = state.path.clone();
+        state.emit(ExecutionEvent::ActionStarted {
+            path: path.clone(),
+            action: action.name().to_string(),
+        });
+        let started = Instant::now();
+        let result = self.execute_step(enigo, action, state).await;
+        state.emit(match &result {
+            Ok(result) => ExecutionEvent::ActionFinished {
+                path,
+                action: action.name().to_string(),
+                result: result.clone(),
+                duration_ms: started.elapsed().as_millis() as u64,
+            },
+            Err(err) => ExecutionEvent::ActionFailed {
+                path,
+                action: action.name().to_string(),
+                error: err.to_string(),
+            },
+        });
+        result
+    }
+
+    async fn execute_step<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+        state: &mut RunState,
+    ) -> Result<String, AutomationError> {
+        let offset = self.resolve_origin(enigo, action.origin(), state)?;
+        self.enforce_policy(enigo, action, offset, state)?;
+        if let Some(timeout_ms) = action.timeout_ms() {
+            state.log(format!("waiting up to {}ms", timeout_ms));
+        }
+        let result = match action {
+            // 键盘鼠标操作
+            InputAction::MouseClick { .. }
+            | InputAction::MouseDoubleClick { .. }
+            | InputAction::MouseMove { .. }
+            | InputAction::MouseScroll { .. }
+            | InputAction::MousePress { .. }
+            | InputAction::MouseDown { .. }
+            | InputAction::MouseUp { .. }
+            | InputAction::MouseDrag { .. }
+            | InputAction::KeyPress { .. }
+            | InputAction::KeyDown { .. }
+            | InputAction::KeyUp { .. }
+            | InputAction::KeySequence { .. }
+            | InputAction::TypeText { .. }
+            | InputAction::Hotkey { .. } => {
+                enigo.begin_input().await;
+                let result = match action.target() {
+                    Some(target) => self.execute_targeted(enigo, action, offset, target, state).await,
+                    None => self.execute_input(enigo, action, offset).await,
+                };
+                enigo.end_input();
+                result?
+            }
+
+            // 延时操作
+            InputAction::Delay { milliseconds } => {
+                enigo.wait(Duration::from_millis(*milliseconds)).await;
+                format!("Delayed for {}ms", milliseconds)
+            }
+            
+            // 屏幕条件
+            InputAction::WaitForPixel { x, y, color, tolerance, timeout_ms } => {
+                let region = Rect::new(*x, *y, 1, 1);
+                let polled = self
+                    .poll_screen(enigo, region, *timeout_ms, |image| {
+                        Ok(Some(Color::from(*image.get_pixel(0, 0))).filter(|actual| actual.matches(color, *tolerance)))
+                    })
+                    .await?;
+                match polled {
+                    Polled::Found(_) => format!("Pixel ({}, {}) matched {:?}", x, y, color),
+                    Polled::Unobserved => format!("Assumed pixel ({}, {}) matches {:?}", x, y, color),
+                    Polled::TimedOut(image) => {
+                        return Err(AutomationError::Timeout(format!(
+                            "pixel ({}, {}) is {:?}, expected {:?} within {}ms",
+                            x, y, Color::from(*image.get_pixel(0, 0)), color, timeout_ms
+                        )));
+                    }
+                }
+            }
+            InputAction::WaitForScreenStable { region, quiet_ms, timeout_ms } => {
+                let region = region.unwrap_or_else(|| self.screen_rect());
+                let quiet = Duration::from_millis(*quiet_ms);
+                let mut previous: Option<RgbaImage> = None;
+                let mut last_change = Instant::now();
+                let polled = self
+                    .poll_screen(enigo, region, *timeout_ms, |frame| {
+                        let changed = previous
+                            .as_ref()
+                            .is_none_or(|previous| frame_difference(previous, frame, CHANGE_TOLERANCE) > 0.0);
+                        if changed {
+                            last_change = Instant::now();
+                            previous = Some(frame.clone());
+                        }
+                        Ok((last_change.elapsed() >= quiet).then_some(()))
+                    })
+                    .await?;
+                match polled {
+                    Polled::Found(()) => format!("Screen region {:?} stable for {}ms", region, quiet_ms),
+                    Polled::Unobserved => format!("Assumed screen region {:?} is stable", region),
+                    Polled::TimedOut(_) => {
+                        return Err(AutomationError::Timeout(format!(
+                            "screen region {:?} did not stay unchanged for {}ms within {}ms",
+                            region, quiet_ms, timeout_ms
+                        )));
+                    }
+                }
+            }
+            InputAction::WaitForChange { region, timeout_ms } => {
+                let region = region.unwrap_or_else(|| self.screen_rect());
+                let mut baseline: Option<RgbaImage> = None;
+                let polled = self
+                    .poll_screen(enigo, region, *timeout_ms, |frame| match &baseline {
+                        Some(baseline) => {
+                            let difference = frame_difference(baseline, frame, CHANGE_TOLERANCE);
+                            Ok((difference > 0.0).then_some(difference))
+                        }
+                        None => {
+                            baseline = Some(frame.clone());
+                            Ok(None)
+                        }
+                    })
+                    .await?;
+                match polled {
+                    Polled::Found(difference) => {
+                        format!("Screen region {:?} changed ({:.1}% of pixels)", region, difference * 100.0)
+                    }
+                    Polled::Unobserved => format!("Assumed screen region {:?} changed", region),
+                    Polled::TimedOut(_) => {
+                        return Err(AutomationError::Timeout(format!(
+                            "screen region {:?} did not change within {}ms",
+                            region, timeout_ms
+                        )));
+                    }
+                }
+            }
+            InputAction::WaitForText { text, region, timeout_ms, anchor } => {
+                let found = self.wait_for_text(enigo, text, *region, *timeout_ms).await?;
+                let located = found.as_ref().map(|found| found.1);
+                state.locate(anchor, located, region.unwrap_or_else(|| self.screen_rect()));
+                match found {
+                    Some(found) => format!("Found text {:?} at {:?} ({:.2})", found.0, found.1, found.2),
+                    None => format!("Assumed text {:?} is present", text),
+                }
+            }
+            InputAction::ClickText { text, region, button, timeout_ms, anchor } => {
+                let found = self.wait_for_text(enigo, text, *region, *timeout_ms).await?;
+                let located = found.as_ref().map(|found| found.1);
+                state.locate(anchor, located, region.unwrap_or_else(|| self.screen_rect()));
+                match found {
+                    Some((found_text, rect, confidence)) => {
+                        let (x, y) = rect.center();
+                        self.check_policy_point(action, x, y)?;
+                        self.mouse.click(enigo, *button, x, y);
+                        format!("Clicked {:?} on text {:?} at ({}, {}) ({:.2})", button, found_text, x, y, confidence)
+                    }
+                    None => format!("Assumed text {:?} is present, {:?} click skipped", text, button),
+                }
+            }
+            InputAction::FocusWindow { window } => self.window_command(enigo, window, WindowCommand::Focus)?,
+            InputAction::MoveWindow { window, x, y } => {
+                self.window_command(enigo, window, WindowCommand::Move { x: *x, y: *y })?
+            }
+            InputAction::ResizeWindow { window, width, height } => {
+                self.window_command(enigo, window, WindowCommand::Resize { width: *width, height: *height })?
+            }
+            InputAction::MinimizeWindow { window } => self.window_command(enigo, window, WindowCommand::Minimize)?,
+            InputAction::CloseWindow { window } => self.window_command(enigo, window, WindowCommand::Close)?,
+            InputAction::WaitForWindow { window, timeout_ms } => {
+                let timeout = Duration::from_millis(*timeout_ms);
+                let started = Instant::now();
+                loop {
+                    let Some(windows) = enigo.windows()? else {
+                        break format!("Assumed window {:?} exists", window);
+                    };
+                    if let Some(found) = windows.into_iter().find(|info| window.matches(info)) {
+                        break format!("Found window {:?} ({})", found.title, found.id);
+                    }
+                    if started.elapsed() >= timeout {
+                        return Err(AutomationError::Timeout(format!(
+                            "no window matches {:?} within {}ms",
+                            window, timeout_ms
+                        )));
+                    }
+                    enigo.wait(POLL_INTERVAL).await;
+                }
+            }
+            InputAction::WaitForElement { selector, state: element_state, timeout_ms, anchor } => {
+                let found = self.wait_for_element(enigo, selector, element_state.as_deref(), *timeout_ms).await?;
+                if anchor.is_some() {
+                    let located = found.as_ref().and_then(|found| found.bounds);
+                    if found.is_some() && located.is_none() {
+                        return Err(AutomationError::Platform(format!(
+                            "element {:?} has no bounds to save as anchor",
+                            selector
+                        )));
+                    }
+                    state.locate(anchor, located, self.screen_rect());
+                }
+                match found {
+                    Some(found) => format!("Found {} {:?} at {:?} ({})", found.role, found.name, found.bounds, found.path),
+                    None => format!("Assumed element {:?} is present", selector),
+                }
+            }
+            InputAction::ClickElement { selector, timeout_ms } => {
+                let found = self.wait_for_element(enigo, selector, None, *timeout_ms).await?;
+                if let Some(bounds) = found.as_ref().and_then(|found| found.bounds) {
+                    let (x, y) = bounds.center();
+                    self.check_policy_point(action, x, y)?;
+                }
+                match found {
+                    Some(found) if found.click_action().is_none() => {
+                        let Some(bounds) = found.bounds else {
+                            return Err(AutomationError::Platform(format!(
+                                "element {:?} has neither a click action nor bounds",
+                                found.name
+                            )));
+                        };
+                        let (x, y) = bounds.center();
+                        self.mouse.click(enigo, MouseButton::Left, x, y);
+                        format!("Clicked {} {:?} at ({}, {})", found.role, found.name, x, y)
+                    }
+                    _ => match enigo.element_command(selector, ElementCommand::Click).await? {
+                        Some(clicked) => format!("Clicked {} {:?} ({})", clicked.role, clicked.name, clicked.path),
+                        None => format!("Assumed click on element {:?}", selector),
+                    },
+                }
+            }
+            InputAction::SetElementText { selector, text, timeout_ms } => {
+                self.wait_for_element(enigo, selector, None, *timeout_ms).await?;
+                let command = ElementCommand::SetText { text: text.clone() };
+                match enigo.element_command(selector, command).await? {
+                    Some(edited) => format!("Set text of {} {:?} ({})", edited.role, edited.name, edited.path),
+                    None => format!("Assumed text of element {:?} is set", selector),
+                }
+            }
+            InputAction::WaitForImage { image, region, threshold, timeout_ms, anchor } => {
+                let found = self.wait_for_image(enigo, image, *region, *threshold, *timeout_ms).await?;
+                let located = found.map(|found| found.rect);
+                state.locate(anchor, located, region.unwrap_or_else(|| self.screen_rect()));
+                match found {
+                    Some(found) => format!("Found image at {:?} ({:.3})", found.rect, found.confidence),
+                    None => "Assumed image is present".to_string(),
+                }
+            }
+            InputAction::ClickImage { image, region, threshold, button, timeout_ms, anchor } => {
+                let found = self.wait_for_image(enigo, image, *region, *threshold, *timeout_ms).await?;
+                let located = found.map(|found| found.rect);
+                state.locate(anchor, located, region.unwrap_or_else(|| self.screen_rect()));
+                match found {
+                    Some(found) => {
+                        let (x, y) = found.rect.center();
+                        self.check_policy_point(action, x, y)?;
+                        self.mouse.click(enigo, *button, x, y);
+                        format!("Clicked {:?} on image at ({}, {}) ({:.3})", button, x, y, found.confidence)
+                    }
+                    None => format!("Assumed image is present, {:?} click skipped", button),
+                }
+            }
+            
+            // 组合操作
+            InputAction::Sequence { actions } => {
+                let mut results = Vec::new();
+                let parent = state.path.clone();
+                for (i, action) in actions.iter().enumerate() {
+                    state.path = format!("{}.actions[{}]", parent, i);
+                    let result = Box::pin(self.execute_in(enigo, action, state)).await;
+                    state.path = parent.clone();
+                    results.push(format!("Step {}: {}", i + 1, result?));
+                }
+                format!("Sequence completed:\n{}", results.join("\n"))
+            }
+            InputAction::Parallel { actions } => self.execute_parallel(enigo, actions, state).await?,
+        };
+        Ok(result)
+    }
+
+    /// 并行执行：先在演练后端上生成每个分支的事件时间线，再按时间合并后发送到 enigo
+    ///
+    /// 分支的开始和完成事件在生成时间线时发出，早于真正发送输入。每个分支从当前光标位置和
+    /// 按住的键开始执行策略检查，合并后的时间线再整体检查一次
+    async fn execute_parallel<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        actions: &[InputAction],
+        state: &mut RunState,
+    ) -> Result<String, AutomationError> {
+        if let Some(action) = actions.iter().find_map(InputAction::find_unparallelizable) {
+            return Err(AutomationError::Platform(format!(
+                "{} cannot run inside Parallel",
+                action.name()
+            )));
+        }
+        let parent = state.path.clone();
+        let parent_held = state.held.clone();
+        let held = state.held(self);
+        let cursor = enigo.location().ok();
+        let mut results = Vec::new();
+        let mut timeline = Vec::new();
+        let mut total_ms = 0;
+        for (i, action) in actions.iter().enumerate() {
+            // 分支内只保证动作间隔，速度倍率和抖动在回放等待时由 enigo 处理
+            let mut branch = DryRunBackend::new(self.screen_size)
+                .with_min_action_gap(Duration::from_millis(self.timing.min_action_gap_ms));
+            if let Some(cursor) = cursor {
+                branch = branch.with_cursor(cursor);
+            }
+            let branch_held = Arc::new(std::sync::Mutex::new(held.clone()));
+            branch = branch.with_held(branch_held.clone());
+            state.path = format!("{}.actions[{}]", parent, i);
+            state.held = Some(branch_held);
+            let result = Box::pin(self.execute_in(&mut branch, action, state)).await;
+            state.path = parent.clone();
+            state.held = parent_held.clone();
+            results.push(format!("Branch {}: {}", i + 1, result?));
+            let report = branch.into_report(String::new());
+            total_ms = total_ms.max(report.estimated_duration_ms);
+            timeline.extend(report.events.into_iter().map(|event| (event.at_ms, i, event.event)));
+        }
+        // 同一时刻的事件按分支顺序发送，分支内保持原有顺序
+        timeline.sort_by_key(|(at_ms, branch, _)| (*at_ms, *branch));
+        self.policy
+            .check_timeline(timeline.iter().map(|(_, _, event)| event), cursor, &held.keys)
+            .map_err(AutomationError::Policy)?;
+        let mut now_ms = 0;
+        for (at_ms, _, event) in timeline {
+            if at_ms > now_ms {
+                enigo.wait(Duration::from_millis(at_ms - now_ms)).await;
+                now_ms = at_ms;
+            }
+            event.replay(enigo)?;
+        }
+        if total_ms > now_ms {
+            enigo.wait(Duration::from_millis(total_ms - now_ms)).await;
+        }
+        Ok(format!("Parallel completed:\n{}", results.join("\n")))
+    }
+
+    /// 按执行策略判定动作，需要确认时调用确认回调
+    fn enforce_policy<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+        offset: (i32, i32),
+        state: &RunState,
+    ) -> Result<(), AutomationError> {
+        if self.policy.is_empty() {
+            return Ok(());
+        }
+        let cursor = if self.policy.allowed_regions.is_empty() {
+            None
+        } else {
+            enigo.location().ok()
+        };
+        let held = state.held(self).keys;
+        match self.policy.evaluate(action, offset, cursor, &held) {
+            Verdict::Allow => Ok(()),
+            Verdict::Deny(reason) => Err(AutomationError::Policy(reason)),
+            Verdict::Confirm(reason) if state.dry_run => {
+                state.log(format!("{}, assumed confirmed", reason));
+                Ok(())
+            }
+            Verdict::Confirm(reason) => {
+                let request = ConfirmRequest {
+                    path: &state.path,
+                    action,
+                    reason: &reason,
+                };
+                let confirmed = self.confirmation.as_ref().is_some_and(|confirm| confirm(&request));
+                enigo.reset_timeline();
+                if confirmed {
+                    state.log(format!("{}, confirmed", reason));
+                    Ok(())
+                } else {
+                    Err(AutomationError::Policy(format!("{}, not confirmed", reason)))
+                }
+            }
+        }
     }
 
-    /// 使用指定的Enigo实例执行动作
-    pub async fn execute_with_enigo(&self, enigo: &mut Enigo, action: &InputAction) -> String {
-        match action {
+    /// 按执行策略检查运行时才确定的点击位置
+    fn check_policy_point(&self, action: &InputAction, x: i32, y: i32) -> Result<(), AutomationError> {
+        self.policy.check_point(action.name(), x, y).map_err(AutomationError::Policy)
+    }
+
+    /// 执行键盘鼠标操作（不处理目标窗口），offset 为坐标原点在屏幕上的位置
+    async fn execute_input<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+        offset: (i32, i32),
+    ) -> Result<String, AutomationError> {
+        let (dx, dy) = offset;
+        let result = match action {
             // 鼠标操作
-            InputAction::MouseClick { button, x, y } => {
-                self.mouse.click(enigo, *button, *x, *y);
+            InputAction::MouseClick { button, x, y, .. } => {
+                let (x, y) = (x.saturating_add(dx), y.saturating_add(dy));
+                self.mouse.click(enigo, *button, x, y);
                 format!("Clicked {:?} at ({}, {})", button, x, y)
             }
-            InputAction::MouseDoubleClick { button, x, y } => {
-                self.mouse.double_click(enigo, *button, *x, *y);
+            InputAction::MouseDoubleClick { button, x, y, .. } => {
+                let (x, y) = (x.saturating_add(dx), y.saturating_add(dy));
+                self.mouse.double_click(enigo, *button, x, y);
                 format!("Double clicked {:?} at ({}, {})", button, x, y)
             }
-            InputAction::MouseMove { x, y } => {
-                self.mouse.move_to(enigo, *x, *y);
+            InputAction::MouseMove { x, y, .. } => {
+                let (x, y) = (x.saturating_add(dx), y.saturating_add(dy));
+                self.mouse.move_to(enigo, x, y);
                 format!("Moved to ({}, {})", x, y)
             }
-            InputAction::MouseScroll { delta_x, delta_y } => {
+            InputAction::MouseScroll { delta_x, delta_y, .. } => {
                 self.mouse.scroll(enigo, *delta_x, *delta_y);
                 format!("Scrolled: horizontal {}, vertical {}", delta_x, delta_y)
             }
-            InputAction::MousePress { button, x, y, duration_ms } => {
-                self.mouse.press(enigo, *button, *x, *y, *duration_ms).await;
+            InputAction::MousePress { button, x, y, duration_ms, .. } => {
+                let (x, y) = (x.saturating_add(dx), y.saturating_add(dy));
+                self.mouse.press(enigo, *button, x, y, *duration_ms).await;
                 format!("Pressed {:?} at ({}, {}) for {}ms", button, x, y, duration_ms)
             }
-            InputAction::MouseDrag { button, start_x, start_y, end_x, end_y, duration_ms } => {
+            InputAction::MouseDown { button, .. } => {
+                self.mouse.button_down(enigo, *button);
+                format!("Mouse down: {:?}", button)
+            }
+            InputAction::MouseUp { button, .. } => {
+                self.mouse.button_up(enigo, *button);
+                format!("Mouse up: {:?}", button)
+            }
+            InputAction::MouseDrag { button, start_x, start_y, end_x, end_y, duration_ms, .. } => {
+                let (start_x, start_y) = (start_x.saturating_add(dx), start_y.saturating_add(dy));
+                let (end_x, end_y) = (end_x.saturating_add(dx), end_y.saturating_add(dy));
                 self.mouse.drag(
                     enigo, 
                     *button, 
-                    *start_x, 
-                    *start_y, 
-                    *end_x, 
-                    *end_y, 
+                    start_x, 
+                    start_y, 
+                    end_x, 
+                    end_y, 
                     *duration_ms
                 ).await;
                 format!(
@@ -75,92 +772,530 @@ impl InputSimulator {
             }
             
             // 键盘操作
-            InputAction::KeyPress { key, location } => {
+            InputAction::KeyPress { key, location, .. } => {
                 self.keyboard.press_key(enigo, *key, *location);
                 format!("Pressed key {:?}", key)
             }
-            InputAction::KeyDown { key, location } => {
+            InputAction::KeyDown { key, location, .. } => {
                 self.keyboard.key_down(enigo, *key, *location);
                 format!("Key down: {:?}", key)
             }
-            InputAction::KeyUp { key, location } => {
+            InputAction::KeyUp { key, location, .. } => {
                 self.keyboard.key_up(enigo, *key, *location);
                 format!("Key up: {:?}", key)
             }
-            InputAction::KeySequence { keys, key_delay_ms } => {
+            InputAction::KeySequence { keys, key_delay_ms, .. } => {
+                let key_delay_ms = key_delay_ms.or(self.timing.default_key_delay_ms);
                 for (i, key) in keys.iter().enumerate() {
                     self.keyboard.press_key(enigo, *key, None);
-                    if let Some(delay) = key_delay_ms {
-                        if i < keys.len() - 1 {
-                            sleep(Duration::from_millis(*delay)).await;
-                        }
+                    if let Some(delay) = key_delay_ms
+                        && i < keys.len() - 1
+                    {
+                        enigo.wait(Duration::from_millis(delay)).await;
                     }
                 }
                 format!("Pressed sequence of {} keys", keys.len())
             }
-            InputAction::TypeText { text, char_delay_ms } => {
-                if let Some(delay) = char_delay_ms {
+            InputAction::TypeText { text, char_delay_ms, .. } => {
+                if let Some(delay) = char_delay_ms.or(self.timing.default_char_delay_ms) {
                     for c in text.chars() {
-                        let _ = enigo.key(EnigoKey::Unicode(c),Direction::Press);
-                        sleep(Duration::from_millis(*delay)).await;
+                        let _ = enigo.key(EnigoKey::Unicode(c),Direction::Click);
+                        enigo.wait(Duration::from_millis(delay)).await;
                     }
                 } else {
                     let _ = enigo.text(text);
                 }
                 format!("Typed text: {}", text)
             }
-            InputAction::Hotkey { modifiers, key, location } => {
+            InputAction::Hotkey { modifiers, key, location, .. } => {
                 self.keyboard.hotkey(enigo, modifiers, *key, *location);
                 format!("Pressed hotkey: modifiers {:?} + {:?}", modifiers, key)
             }
-            
-            // 延时操作
-            InputAction::Delay { milliseconds } => {
-                sleep(Duration::from_millis(*milliseconds)).await;
-                format!("Delayed for {}ms", milliseconds)
+
+            other => {
+                return Err(AutomationError::Platform(format!(
+                    "{:?} is not a keyboard or mouse action",
+                    other
+                )));
             }
-            
-            // 组合操作
-            InputAction::Sequence { actions } => {
-                let mut results = Vec::new();
-                for (i, action) in actions.iter().enumerate() {
-                    let result = Box::pin(self.execute_with_enigo(enigo, action)).await;
-                    results.push(format!("Step {}: {}", i + 1, result));
+        };
+        Ok(result)
+    }
+
+    /// 将键盘鼠标操作发送给目标窗口
+    async fn execute_targeted<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        action: &InputAction,
+        offset: (i32, i32),
+        target: &InputTarget,
+        state: &RunState,
+    ) -> Result<String, AutomationError> {
+        let Some(windows) = enigo.windows()? else {
+            let result = self.execute_input(enigo, action, offset).await?;
+            return Ok(format!("{} (assumed target {:?})", result, target.window));
+        };
+        let window = windows
+            .iter()
+            .find(|window| target.window.matches(window))
+            .ok_or_else(|| AutomationError::Platform(format!("no window matches {:?}", target.window)))?;
+
+        state.log(format!("delivering to window {:?} ({}) via {:?}", window.title, window.id, target.delivery));
+        let result = match target.delivery {
+            InputDelivery::SendEvent => {
+                let mut routed = WindowEventBackend::new(enigo, window);
+                let result = self.execute_input(&mut routed, action, offset).await;
+                routed.finish()?;
+                result?
+            }
+            InputDelivery::Focus => {
+                let previous = windows.iter().find(|window| window.state.active).map(|window| window.id);
+                enigo.window_command(&WindowSelector::id(window.id), WindowCommand::Focus)?;
+                enigo.wait(FOCUS_SETTLE).await;
+                let result = self.execute_input(enigo, action, offset).await;
+                if let Some(previous) = previous.filter(|previous| *previous != window.id) {
+                    enigo.window_command(&WindowSelector::id(previous), WindowCommand::Focus)?;
                 }
-                format!("Sequence completed:\n{}", results.join("\n"))
+                result?
+            }
+        };
+        Ok(format!("{} in window {:?} ({})", result, window.title, window.id))
+    }
+
+    /// 计算坐标原点在屏幕上的位置；后端无法观察窗口时（演练模式）窗口原点视为屏幕原点
+    fn resolve_origin<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        origin: Option<&Origin>,
+        state: &RunState,
+    ) -> Result<(i32, i32), AutomationError> {
+        match origin {
+            None | Some(Origin::Screen) => Ok((0, 0)),
+            Some(Origin::Window(selector)) => {
+                let Some(windows) = enigo.windows()? else {
+                    return Ok((0, 0));
+                };
+                windows
+                    .iter()
+                    .find(|window| selector.matches(window))
+                    .map(|window| (window.geometry.x, window.geometry.y))
+                    .ok_or_else(|| AutomationError::Platform(format!("no window matches {:?}", selector)))
+            }
+            Some(Origin::Anchor(name)) => state
+                .anchors
+                .get(name)
+                .map(Rect::center)
+                .ok_or_else(|| AutomationError::Platform(format!("anchor {:?} has not been located", name))),
+        }
+    }
+
+    /// 整个屏幕区域
+    fn screen_rect(&self) -> Rect {
+        Rect::new(0, 0, self.screen_size.0, self.screen_size.1)
+    }
+
+    /// 查找区域中实际会被截取的部分（超出屏幕的部分会被裁掉），截图中的位置按它换算为屏幕坐标
+    fn visible(&self, region: Option<Rect>) -> Rect {
+        let screen = self.screen_rect();
+        match region {
+            // 完全在屏幕外时保留原区域，由截图报告错误
+            Some(region) => region.intersect(&screen).unwrap_or(region),
+            None => screen,
+        }
+    }
+
+    /// 轮询屏幕区域直到 check 返回 Some 或超时
+    async fn poll_screen<B: InputBackend, T>(
+        &self,
+        enigo: &mut B,
+        region: Rect,
+        timeout_ms: u64,
+        mut check: impl FnMut(&RgbaImage) -> Result<Option<T>, AutomationError>,
+    ) -> Result<Polled<T>, AutomationError> {
+        let timeout = Duration::from_millis(timeout_ms);
+        let started = Instant::now();
+        loop {
+            let Some(image) = enigo.capture(region)? else {
+                return Ok(Polled::Unobserved);
+            };
+            if let Some(found) = check(&image)? {
+                return Ok(Polled::Found(found));
+            }
+            if started.elapsed() >= timeout {
+                return Ok(Polled::TimedOut(image));
+            }
+            enigo.wait(POLL_INTERVAL).await;
+        }
+    }
+
+    /// 对满足条件的最上层窗口执行操作
+    fn window_command<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<String, AutomationError> {
+        Ok(match enigo.window_command(selector, command)? {
+            Some(window) => format!("{:?} on window {:?} ({})", command, window.title, window.id),
+            None => format!("Assumed {:?} on window {:?}", command, selector),
+        })
+    }
+
+    /// 等待元素出现（并处于指定状态），返回第一个满足条件的元素；无法观察无障碍树时返回 None
+    async fn wait_for_element<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        selector: &ElementSelector,
+        element_state: Option<&str>,
+        timeout_ms: u64,
+    ) -> Result<Option<ElementInfo>, AutomationError> {
+        let timeout = Duration::from_millis(timeout_ms);
+        let started = Instant::now();
+        loop {
+            let Some(elements) = enigo.find_elements(selector).await? else {
+                return Ok(None);
+            };
+            let found = elements
+                .into_iter()
+                .find(|element| element_state.is_none_or(|expected| element.has_state(expected)));
+            if found.is_some() {
+                return Ok(found);
             }
-            InputAction::Parallel { actions } => {
-                //TODO
-                //let mut tasks = Vec::new();
-                //for action in actions.iter() {
-                //    let action_clone = action.clone();
-                //    let enigo_clone = self.enigo.clone();
-                //    let simulator_clone = self.clone();
-                    
-                    // tasks.push(tokio::spawn(async move {
-                    //     let mut enigo = enigo_clone.lock().unwrap();
-                    //     simulator_clone.execute_with_enigo(&mut enigo, &action_clone).await
-                    // }));
-                //}
-                
-                // let mut results = Vec::new();
-                // for (i, task) in tasks.into_iter().enumerate() {
-                //     let result = task.await.unwrap();
-                //     results.push(format!("Task {}: {}", i + 1, result));
-                // }
-                
-                format!("Parallel actions completed")
+            if started.elapsed() >= timeout {
+                return Err(AutomationError::Timeout(format!(
+                    "no element matches {:?} within {}ms",
+                    selector, timeout_ms
+                )));
             }
+            enigo.wait(POLL_INTERVAL).await;
+        }
+    }
+
+    /// 等待文字出现，返回置信度最高的匹配（文字、区域、置信度）；无法观察屏幕时返回 None
+    #[cfg(feature = "ocr")]
+    async fn wait_for_text<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        text: &str,
+        region: Option<Rect>,
+        timeout_ms: u64,
+    ) -> Result<Option<(String, Rect, f32)>, AutomationError> {
+        use crate::ocr::{find_in_lines, offset_lines, recognize};
+
+        let region = self.visible(region);
+        let polled = self
+            .poll_screen(enigo, region, timeout_ms, |screenshot| {
+                let lines = offset_lines(recognize(screenshot)?, region);
+                Ok(find_in_lines(&lines, text).into_iter().next())
+            })
+            .await?;
+        match polled {
+            Polled::Found(found) => Ok(Some((found.text, found.rect, found.confidence))),
+            Polled::Unobserved => Ok(None),
+            Polled::TimedOut(_) => Err(AutomationError::Timeout(format!(
+                "text {:?} not found in {:?} within {}ms",
+                text, region, timeout_ms
+            ))),
         }
     }
+
+    #[cfg(not(feature = "ocr"))]
+    async fn wait_for_text<B: InputBackend>(
+        &self,
+        _enigo: &mut B,
+        _text: &str,
+        _region: Option<Rect>,
+        _timeout_ms: u64,
+    ) -> Result<Option<(String, Rect, f32)>, AutomationError> {
+        Err(AutomationError::Platform(
+            "text recognition requires the `ocr` feature".to_string(),
+        ))
+    }
+
+    /// 等待模板图片出现，返回得分最高的匹配；无法观察屏幕时返回 None
+    async fn wait_for_image<B: InputBackend>(
+        &self,
+        enigo: &mut B,
+        image: &ImageSource,
+        region: Option<Rect>,
+        threshold: f32,
+        timeout_ms: u64,
+    ) -> Result<Option<ImageMatch>, AutomationError> {
+        let template = image.load()?;
+        let region = self.visible(region);
+        let polled = self
+            .poll_screen(enigo, region, timeout_ms, |screenshot| {
+                Ok(match_template(screenshot, &template, threshold).into_iter().next())
+            })
+            .await?;
+        match polled {
+            Polled::Found(found) => Ok(offset_matches(vec![found], region).pop()),
+            Polled::Unobserved => Ok(None),
+            Polled::TimedOut(_) => Err(AutomationError::Timeout(format!(
+                "image not found in {:?} with threshold {} within {}ms",
+                region, threshold, timeout_ms
+            ))),
+        }
+    }
+}
+
+/// 紧急停止的错误，释放按住的输入失败时附带原因
+fn aborted(reason: String, released: Result<Vec<String>, AutomationError>) -> AutomationError {
+    match released {
+        Ok(_) => AutomationError::Aborted(reason),
+        Err(err) => AutomationError::Aborted(format!("{} ({})", reason, err)),
+    }
+}
+
+fn no_device() -> AutomationError {
+    AutomationError::Platform("the simulator has no input device and only supports dry runs".to_string())
+}
+
+impl Default for InputSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Clone for InputSimulator {
     fn clone(&self) -> Self {
         InputSimulator {
-            enigo: self.enigo.clone(),
+            device: self.device.clone(),
+            running: self.running.clone(),
+            held: self.held.clone(),
+            last_input: self.last_input.clone(),
+            screen_size: self.screen_size,
+            timing: self.timing.clone(),
+            policy: self.policy.clone(),
+            confirmation: self.confirmation.clone(),
+            fail_safe: self.fail_safe.clone(),
             keyboard: KeyboardSimulator::new(),
             mouse: MouseSimulator::new(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn running_actions_do_not_block_device_queries() {
+        if !crate::testing::display_available() {
+            return;
+        }
+        let simulator = InputSimulator::new();
+        let action: InputAction = serde_json::from_value(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "KeyDown", "params": {"key": "Shift"}},
+            {"type": "Delay", "params": {"milliseconds": 500}},
+            {"type": "KeyUp", "params": {"key": "Shift"}}
+        ]}}))
+        .unwrap();
+        let running = {
+            let simulator = simulator.clone();
+            tokio::spawn(async move { simulator.execute_action(&action).await })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let started = Instant::now();
+        simulator.cursor_position().await.unwrap();
+        assert_eq!(simulator.release_all().await.unwrap().len(), 1);
+        assert!(started.elapsed() < Duration::from_millis(200));
+        running.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn detached_simulator_has_no_device() {
+        let simulator = InputSimulator::detached((800, 600));
+        assert!(matches!(simulator.cursor_position().await, Err(AutomationError::Platform(_))));
+        assert!(matches!(simulator.release_all().await, Err(AutomationError::Platform(_))));
+    }
+
+    #[tokio::test]
+    async fn fail_safe_aborts_and_releases_held_keys() {
+        use crate::dry_run::InputEvent;
+        use crate::timing::DeadlineBackend;
+        use enigo::Direction;
+
+        let mut simulator = InputSimulator::detached((800, 600));
+        let fail_safe = FailSafe::new();
+        simulator.set_fail_safe(fail_safe.clone());
+        let action: InputAction = serde_json::from_value(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "KeyDown", "params": {"key": "Ctrl"}},
+            {"type": "Delay", "params": {"milliseconds": 10000}}
+        ]}}))
+        .unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            fail_safe.trip("test");
+        });
+
+        // 演练后端只记录事件，等待由 DeadlineBackend 真正计时
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let mut backend = DeadlineBackend::new(&mut dry_run, simulator.timing_policy());
+        let started = Instant::now();
+        let result = simulator.execute_with_backend(&mut backend, &action).await;
+        assert!(matches!(result, Err(AutomationError::Aborted(reason)) if reason == "test"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let keys: Vec<_> = dry_run
+            .events()
+            .iter()
+            .filter_map(|event| match &event.event {
+                InputEvent::Key { key, direction } => Some((*key, *direction)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![(enigo::Key::Control, Direction::Press), (enigo::Key::Control, Direction::Release)]
+        );
+
+        // 触发后的执行直接中止，不发送任何输入
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let result = simulator.execute_with_backend(&mut dry_run, &action).await;
+        assert!(matches!(result, Err(AutomationError::Aborted(_))));
+        assert!(dry_run.events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn waits_for_pixels_within_tolerance() {
+        use crate::dry_run::InputEvent;
+        use crate::timing::DeadlineBackend;
+
+        let simulator = InputSimulator::detached((100, 100));
+        let mut screen = RgbaImage::from_pixel(100, 100, image::Rgba([10, 20, 30, 255]));
+        screen.put_pixel(5, 6, image::Rgba([200, 100, 50, 255]));
+        let wait = |x, tolerance, timeout_ms| InputAction::WaitForPixel {
+            x,
+            y: 6,
+            color: Color::new(205, 98, 50),
+            tolerance,
+            timeout_ms,
+        };
+
+        let mut dry_run = DryRunBackend::new((100, 100)).with_screen(screen.clone());
+        let result = simulator.execute_with_backend(&mut dry_run, &wait(5, 5, 1000)).await.unwrap();
+        assert!(result.starts_with("Pixel (5, 6) matched"), "{}", result);
+        assert_eq!(
+            dry_run.events().iter().map(|event| &event.event).collect::<Vec<_>>(),
+            [&InputEvent::Capture { region: Rect::new(5, 6, 1, 1) }]
+        );
+
+        // 不匹配时按间隔轮询直到超时
+        let mut dry_run = DryRunBackend::new((100, 100)).with_screen(screen.clone());
+        let mut backend = DeadlineBackend::new(&mut dry_run, simulator.timing_policy());
+        let result = simulator.execute_with_backend(&mut backend, &wait(5, 4, 100)).await;
+        assert!(
+            matches!(&result, Err(AutomationError::Timeout(message)) if message.contains("is Color { r: 200, g: 100, b: 50 }")),
+            "{:?}",
+            result
+        );
+        let captures = dry_run
+            .events()
+            .iter()
+            .filter(|event| matches!(event.event, InputEvent::Capture { .. }))
+            .count();
+        assert!(captures >= 2, "{}", captures);
+        let mut dry_run = DryRunBackend::new((100, 100)).with_screen(screen);
+        let result = simulator.execute_with_backend(&mut dry_run, &wait(6, 255, 0)).await;
+        assert!(result.is_ok());
+        let result = simulator.execute_with_backend(&mut dry_run, &wait(6, 5, 0)).await;
+        assert!(matches!(result, Err(AutomationError::Timeout(_))));
+        let result = simulator.execute_with_backend(&mut dry_run, &wait(100, 5, 0)).await;
+        assert!(matches!(result, Err(AutomationError::Platform(_))));
+
+        // 无法观察屏幕时视为满足
+        let mut dry_run = DryRunBackend::new((100, 100));
+        let result = simulator.execute_with_backend(&mut dry_run, &wait(5, 0, 1000)).await.unwrap();
+        assert!(result.starts_with("Assumed pixel (5, 6)"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn window_origins_offset_pointer_actions() {
+        use crate::dry_run::InputEvent;
+        use crate::window::{WindowInfo, WindowState};
+
+        let window = |id, x| WindowInfo {
+            id,
+            title: format!("window {}", id),
+            class: String::new(),
+            instance: String::new(),
+            pid: None,
+            geometry: Rect::new(x, 20, 100, 100),
+            desktop: None,
+            state: WindowState::default(),
+        };
+        let simulator = InputSimulator::detached((800, 600));
+        let moves = |dry_run: &DryRunBackend| -> Vec<(i32, i32)> {
+            dry_run
+                .events()
+                .iter()
+                .filter_map(|event| match event.event {
+                    InputEvent::MouseMove { x, y, .. } => Some((x, y)),
+                    _ => None,
+                })
+                .collect()
+        };
+        let click = |id| -> InputAction {
+            serde_json::from_value(json!({"type": "MouseClick", "params": {
+                "button": "Left", "x": 5, "y": 6, "origin": {"Window": {"id": id}}
+            }}))
+            .unwrap()
+        };
+
+        let mut dry_run = DryRunBackend::new((800, 600)).with_windows(vec![window(1, 10)]);
+        simulator.execute_with_backend(&mut dry_run, &click(1)).await.unwrap();
+        assert_eq!(moves(&dry_run), [(15, 26)]);
+
+        // 超出 i32 范围的坐标停在边界上而不是溢出
+        let mut dry_run = DryRunBackend::new((800, 600)).with_windows(vec![window(2, i32::MAX - 1)]);
+        simulator.execute_with_backend(&mut dry_run, &click(2)).await.unwrap();
+        assert_eq!(moves(&dry_run), [(i32::MAX, 26)]);
+
+        let mut dry_run = DryRunBackend::new((800, 600)).with_windows(vec![window(1, 10)]);
+        let result = simulator.execute_with_backend(&mut dry_run, &click(3)).await;
+        assert!(matches!(result, Err(AutomationError::Platform(message)) if message.contains("no window matches")));
+    }
+
+    #[tokio::test]
+    async fn policy_applies_inside_parallel_branches() {
+        let mut simulator = InputSimulator::detached((800, 600));
+        simulator.set_policy(Policy {
+            forbidden_hotkeys: vec![vec![crate::enums::Key::Ctrl, crate::enums::Key::Alt, crate::enums::Key::Delete]],
+            allowed_regions: vec![Rect::new(50, 50, 100, 100)],
+            ..Policy::default()
+        });
+        let run = |value: serde_json::Value| {
+            let action: InputAction = serde_json::from_value(value).unwrap();
+            let simulator = &simulator;
+            async move { simulator.dry_run(&action).await }
+        };
+        let ctrl = json!({"type": "KeyDown", "params": {"key": "Ctrl"}});
+        let alt = json!({"type": "KeyDown", "params": {"key": "Alt"}});
+        let delete = json!({"type": "KeyPress", "params": {"key": "Delete"}});
+        let press = json!({"type": "MouseDown", "params": {"button": "Left"}});
+
+        // 同一分支内按住的键
+        let result = run(json!({"type": "Parallel", "params": {"actions": [
+            {"type": "Sequence", "params": {"actions": [ctrl, alt, delete]}}
+        ]}}))
+        .await;
+        assert!(matches!(result, Err(AutomationError::Policy(reason)) if reason.contains("forbidden")));
+        // 不同分支按下的键在合并后组成组合键
+        let result = run(json!({"type": "Parallel", "params": {"actions": [
+            {"type": "Sequence", "params": {"actions": [ctrl, alt]}},
+            {"type": "Sequence", "params": {"actions": [{"type": "Delay", "params": {"milliseconds": 50}}, delete]}}
+        ]}}))
+        .await;
+        assert!(matches!(result, Err(AutomationError::Policy(reason)) if reason.contains("forbidden")));
+        // 分支从当前光标位置开始
+        let result = run(json!({"type": "Parallel", "params": {"actions": [press]}})).await;
+        assert!(matches!(result, Err(AutomationError::Policy(reason)) if reason.contains("(0, 0)")));
+        run(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseMove", "params": {"x": 100, "y": 100}},
+            {"type": "Parallel", "params": {"actions": [press, delete]}}
+        ]}}))
+        .await
+        .unwrap();
+    }
+}
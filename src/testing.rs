@@ -0,0 +1,24 @@
+//! 测试辅助函数
+
+/// 是否有可用的 X 显示；没有时打印提示，调用方应跳过测试
+///
+/// 需要 X 的测试可以在 Xvfb 中运行：`xvfb-run cargo test`
+pub(crate) fn display_available() -> bool {
+    if std::env::var_os("DISPLAY").is_some() {
+        return true;
+    }
+    eprintln!("DISPLAY is not set, skipping test that needs an X server");
+    false
+}
+
+/// 是否有可用的 D-Bus 会话总线；没有时打印提示，调用方应跳过测试
+///
+/// 需要无障碍总线的测试可以在 dbus-run-session 中运行：`dbus-run-session -- xvfb-run cargo test`
+#[cfg(feature = "accessibility")]
+pub(crate) fn session_bus_available() -> bool {
+    if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() || std::env::var_os("AT_SPI_BUS_ADDRESS").is_some() {
+        return true;
+    }
+    eprintln!("DBUS_SESSION_BUS_ADDRESS is not set, skipping test that needs a session bus");
+    false
+}
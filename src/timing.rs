@@ -0,0 +1,548 @@
+use std::future::Future;
+use std::time::Instant;
+
+use enigo::{Axis, Button, Coordinate, Direction, InputResult, Key as EnigoKey, Keyboard, Mouse};
+use image::RgbaImage;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep_until, Duration};
+
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::backend::InputBackend;
+use crate::error::AutomationError;
+use crate::screen::Rect;
+use crate::window::{WindowCommand, WindowEvent, WindowInfo, WindowSelector};
+
+/// 执行器级别的时间策略
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct TimingPolicy {
+    /// 速度倍率，2.0 表示两倍速（所有等待减半），0.5 表示半速
+    pub speed: f64,
+    /// 相邻键盘鼠标动作之间的最小间隔（毫秒），从上一个动作结束算起，期间已经过的时间计入间隔；
+    /// 同样作用于 Parallel 分支内和先后提交的执行，与其他等待一样受速度倍率影响
+    pub min_action_gap_ms: u64,
+    /// KeySequence 未指定 key_delay_ms 时使用的默认值
+    pub default_key_delay_ms: Option<u64>,
+    /// TypeText 未指定 char_delay_ms 时使用的默认值
+    pub default_char_delay_ms: Option<u64>,
+    /// 随机抖动幅度（毫秒），每次等待会加上 [-jitter_ms, +jitter_ms] 内的随机偏移
+    pub jitter_ms: u64,
+    /// 抖动的随机种子，设置后每次执行的抖动序列相同
+    pub seed: Option<u64>,
+    /// 是否按绝对时间线调度等待：连续的输入和等待的截止时间累计计算，注入输入本身的耗时不会累积成漂移；
+    /// 截图、查找窗口和元素、等待确认等耗时不可预计的操作之后，时间线从当前时刻重新开始
+    pub absolute_timeline: bool,
+    /// 截止时间前最后多少微秒改为忙等，用于亚毫秒级精度（0 表示不忙等）
+    pub spin_us: u64,
+}
+
+impl Default for TimingPolicy {
+    fn default() -> Self {
+        TimingPolicy {
+            speed: 1.0,
+            min_action_gap_ms: 0,
+            default_key_delay_ms: None,
+            default_char_delay_ms: None,
+            jitter_ms: 0,
+            seed: None,
+            absolute_timeline: true,
+            spin_us: 0,
+        }
+    }
+}
+
+impl TimingPolicy {
+    /// 按速度倍率缩放时长（倍率非正数时视为 1.0）
+    pub fn scale(&self, duration: Duration) -> Duration {
+        if self.speed > 0.0 && self.speed.is_finite() {
+            duration.div_f64(self.speed)
+        } else {
+            duration
+        }
+    }
+
+    /// 为单次执行创建随机数生成器
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        }
+    }
+}
+
+/// 按时间策略处理等待的输入后端包装
+///
+/// 每次执行创建一个新实例，因此同一个种子在每次执行中产生相同的抖动
+pub struct PacedBackend<'a, B: InputBackend> {
+    inner: &'a mut B,
+    policy: &'a TimingPolicy,
+    rng: StdRng,
+}
+
+impl<'a, B: InputBackend> PacedBackend<'a, B> {
+    pub fn new(inner: &'a mut B, policy: &'a TimingPolicy) -> Self {
+        PacedBackend {
+            inner,
+            policy,
+            rng: policy.rng(),
+        }
+    }
+
+    /// 计算缩放并加上抖动后的实际等待时长
+    fn paced(&mut self, duration: Duration) -> Duration {
+        let scaled = self.policy.scale(duration);
+        if self.policy.jitter_ms == 0 {
+            return scaled;
+        }
+        let jitter = self.policy.jitter_ms as i64;
+        let offset = self.rng.random_range(-jitter..=jitter);
+        if offset >= 0 {
+            scaled + Duration::from_millis(offset as u64)
+        } else {
+            scaled.saturating_sub(Duration::from_millis(offset.unsigned_abs()))
+        }
+    }
+}
+
+impl<B: InputBackend> Keyboard for PacedBackend<'_, B> {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.inner.fast_text(text)
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.inner.key(key, direction)
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.inner.raw(keycode, direction)
+    }
+}
+
+impl<B: InputBackend> Mouse for PacedBackend<'_, B> {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.inner.button(button, direction)
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.inner.move_mouse(x, y, coordinate)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.inner.scroll(length, axis)
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        self.inner.main_display()
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.inner.location()
+    }
+}
+
+impl<B: InputBackend> InputBackend for PacedBackend<'_, B> {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        let duration = self.paced(duration);
+        self.inner.wait(duration)
+    }
+
+    fn begin_input(&mut self) -> impl Future<Output = ()> + Send {
+        self.inner.begin_input()
+    }
+
+    fn end_input(&mut self) {
+        self.inner.end_input()
+    }
+
+    fn reset_timeline(&mut self) {
+        self.inner.reset_timeline()
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        self.inner.capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        self.inner.windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        self.inner.window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        self.inner.send_window_event(window, event)
+    }
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        self.inner.find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        self.inner.element_command(selector, command)
+    }
+}
+
+/// 单次等待的计划与实际时间（相对于执行开始，微秒）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct StepTiming {
+    /// 第几次等待（从 0 开始）
+    pub index: usize,
+    /// 计划的等待时长
+    pub requested_us: u64,
+    /// 计划唤醒时刻
+    pub planned_us: u64,
+    /// 实际唤醒时刻
+    pub actual_us: u64,
+    /// 实际与计划的偏差，正数表示晚于计划
+    pub deviation_us: i64,
+}
+
+/// 基于截止时间的输入后端包装
+///
+/// 在真实设备上等待时使用 `sleep_until` 对齐到截止时间，并记录每次等待的计划与实际时间；
+/// 同时保证相邻键盘鼠标动作之间的最小间隔
+pub struct DeadlineBackend<'a, B: InputBackend + Send> {
+    inner: &'a mut B,
+    origin: Instant,
+    /// 计划时间线上的当前位置（相对于 origin）
+    planned: Duration,
+    absolute_timeline: bool,
+    /// 上次等待之后执行过耗时不可预计的操作（截图及随后的图像处理、查找窗口和元素等），
+    /// 下次等待从当前时刻重新计时
+    stale: bool,
+    spin: Duration,
+    /// 已按速度倍率缩放的最小动作间隔
+    min_action_gap: Duration,
+    /// 上一个键盘鼠标动作结束的时刻
+    last_input: Option<Instant>,
+    timings: Vec<StepTiming>,
+}
+
+impl<'a, B: InputBackend + Send> DeadlineBackend<'a, B> {
+    /// 以当前时刻作为时间线起点
+    pub fn new(inner: &'a mut B, policy: &TimingPolicy) -> Self {
+        DeadlineBackend {
+            inner,
+            origin: Instant::now(),
+            planned: Duration::ZERO,
+            absolute_timeline: policy.absolute_timeline,
+            stale: false,
+            spin: Duration::from_micros(policy.spin_us),
+            min_action_gap: policy.scale(Duration::from_millis(policy.min_action_gap_ms)),
+            last_input: None,
+            timings: Vec::new(),
+        }
+    }
+
+    /// 从上一次执行最后一个键盘鼠标动作结束的时刻开始计算最小间隔
+    pub fn with_last_input(mut self, last_input: Option<Instant>) -> Self {
+        self.last_input = last_input;
+        self
+    }
+
+    /// 最后一个键盘鼠标动作结束的时刻
+    pub fn last_input(&self) -> Option<Instant> {
+        self.last_input
+    }
+
+    /// 取出已记录的等待时间
+    pub fn into_timings(self) -> Vec<StepTiming> {
+        self.timings
+    }
+}
+
+impl<B: InputBackend + Send> Keyboard for DeadlineBackend<'_, B> {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.inner.fast_text(text)
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.inner.key(key, direction)
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.inner.raw(keycode, direction)
+    }
+}
+
+impl<B: InputBackend + Send> Mouse for DeadlineBackend<'_, B> {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.inner.button(button, direction)
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.inner.move_mouse(x, y, coordinate)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.inner.scroll(length, axis)
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        self.inner.main_display()
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        self.inner.location()
+    }
+}
+
+impl<B: InputBackend + Send> InputBackend for DeadlineBackend<'_, B> {
+    async fn wait(&mut self, duration: Duration) {
+        if !self.absolute_timeline || self.stale {
+            // 从当前时刻开始计算，之前的偏差不做补偿
+            self.planned = self.origin.elapsed();
+            self.stale = false;
+        }
+        self.planned += duration;
+        let deadline = self.origin + self.planned;
+
+        // 定时器只有毫秒精度：先睡到截止时间前 spin，剩下的时间忙等
+        let coarse = deadline.checked_sub(self.spin).unwrap_or(deadline);
+        if coarse > Instant::now() {
+            sleep_until(coarse.into()).await;
+        }
+        while Instant::now() < deadline {
+            tokio::task::yield_now().await;
+        }
+
+        let actual = self.origin.elapsed();
+        self.timings.push(StepTiming {
+            index: self.timings.len(),
+            requested_us: duration.as_micros() as u64,
+            planned_us: self.planned.as_micros() as u64,
+            actual_us: actual.as_micros() as u64,
+            deviation_us: actual.as_micros() as i64 - self.planned.as_micros() as i64,
+        });
+    }
+
+    async fn begin_input(&mut self) {
+        // 距离上一个动作结束已超过间隔时不再等待，期间的等待和其他耗时都计入间隔
+        if let Some(last_input) = self.last_input {
+            let deadline = last_input + self.min_action_gap;
+            if deadline > Instant::now() {
+                sleep_until(deadline.into()).await;
+                self.stale = true;
+            }
+        }
+        self.inner.begin_input().await
+    }
+
+    fn end_input(&mut self) {
+        self.last_input = Some(Instant::now());
+        self.inner.end_input()
+    }
+
+    fn reset_timeline(&mut self) {
+        self.stale = true;
+        self.inner.reset_timeline()
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        self.stale = true;
+        self.inner.capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        self.stale = true;
+        self.inner.windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        self.stale = true;
+        self.inner.window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        self.inner.send_window_event(window, event)
+    }
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        self.stale = true;
+        self.inner.find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        self.stale = true;
+        self.inner.element_command(selector, command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::dry_run::{DryRunBackend, InputEvent};
+    use crate::enums::InputAction;
+    use crate::simulator::InputSimulator;
+
+    fn waits(backend: &DryRunBackend) -> Vec<u64> {
+        backend
+            .events()
+            .iter()
+            .filter_map(|event| match event.event {
+                InputEvent::Wait { milliseconds } => Some(milliseconds),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scale_divides_by_speed() {
+        let policy = |speed| TimingPolicy {
+            speed,
+            ..TimingPolicy::default()
+        };
+        assert_eq!(policy(2.0).scale(Duration::from_millis(300)), Duration::from_millis(150));
+        assert_eq!(policy(0.5).scale(Duration::from_millis(300)), Duration::from_millis(600));
+        // 无效倍率按原速处理
+        assert_eq!(policy(0.0).scale(Duration::from_millis(300)), Duration::from_millis(300));
+        assert_eq!(policy(-1.0).scale(Duration::from_millis(300)), Duration::from_millis(300));
+        assert_eq!(policy(f64::NAN).scale(Duration::from_millis(300)), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn jitter_stays_in_range_and_is_repeatable_with_seed() {
+        let policy = TimingPolicy {
+            jitter_ms: 10,
+            seed: Some(7),
+            ..TimingPolicy::default()
+        };
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let mut dry_run = DryRunBackend::new((800, 600));
+            let mut backend = PacedBackend::new(&mut dry_run, &policy);
+            for _ in 0..20 {
+                backend.wait(Duration::from_millis(100)).await;
+            }
+            runs.push(waits(&dry_run));
+        }
+        assert!(runs[0].iter().all(|wait| (90..=110).contains(wait)));
+        assert!(runs[0].iter().any(|wait| *wait != 100));
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn gap_counts_time_already_waited() {
+        let mut backend = DryRunBackend::new((800, 600)).with_min_action_gap(Duration::from_millis(50));
+        // 第一个动作之前没有间隔
+        backend.begin_input().await;
+        backend.end_input();
+        backend.begin_input().await;
+        backend.end_input();
+        backend.wait(Duration::from_millis(30)).await;
+        backend.begin_input().await;
+        backend.end_input();
+        backend.wait(Duration::from_millis(80)).await;
+        backend.begin_input().await;
+        backend.end_input();
+        assert_eq!(waits(&backend), vec![50, 30, 20, 80]);
+    }
+
+    fn planned(timings: &[StepTiming]) -> Vec<u64> {
+        timings.iter().map(|timing| timing.planned_us).collect()
+    }
+
+    #[tokio::test]
+    async fn absolute_timeline_absorbs_input_time() {
+        let policy = TimingPolicy::default();
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let mut backend = DeadlineBackend::new(&mut dry_run, &policy);
+        backend.wait(Duration::from_millis(20)).await;
+        // 模拟注入输入的耗时
+        std::thread::sleep(Duration::from_millis(5));
+        backend.wait(Duration::from_millis(20)).await;
+        let timings = backend.into_timings();
+        assert_eq!(planned(&timings), vec![20_000, 40_000]);
+        assert!(timings.iter().all(|timing| timing.deviation_us >= 0));
+    }
+
+    #[tokio::test]
+    async fn spin_hits_the_deadline_within_the_spin_window() {
+        let policy = TimingPolicy {
+            spin_us: 2_000,
+            ..TimingPolicy::default()
+        };
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let mut backend = DeadlineBackend::new(&mut dry_run, &policy);
+        for _ in 0..10 {
+            backend.wait(Duration::from_micros(3_500)).await;
+        }
+        let timings = backend.into_timings();
+        assert_eq!(planned(&timings), (1..=10).map(|step| step * 3_500).collect::<Vec<_>>());
+        // 从不早于截止时间；线程可能被系统调度出去，只要求大多数等待落在忙等窗口内
+        assert!(timings.iter().all(|timing| timing.deviation_us >= 0), "{:?}", timings);
+        let hits = timings.iter().filter(|timing| timing.deviation_us < 2_000).count();
+        assert!(hits >= 8, "{:?}", timings);
+    }
+
+    #[tokio::test]
+    async fn timeline_restarts_after_capture_and_in_relative_mode() {
+        let policy = TimingPolicy::default();
+        let mut dry_run = DryRunBackend::new((800, 600));
+        let mut backend = DeadlineBackend::new(&mut dry_run, &policy);
+        backend.wait(Duration::from_millis(20)).await;
+        backend.capture(Rect::new(0, 0, 1, 1)).unwrap();
+        // 模拟图像匹配的耗时
+        std::thread::sleep(Duration::from_millis(15));
+        backend.wait(Duration::from_millis(20)).await;
+        let timings = backend.into_timings();
+        assert!(timings[1].planned_us >= 55_000);
+
+        let policy = TimingPolicy {
+            absolute_timeline: false,
+            ..TimingPolicy::default()
+        };
+        let mut backend = DeadlineBackend::new(&mut dry_run, &policy);
+        backend.wait(Duration::from_millis(20)).await;
+        std::thread::sleep(Duration::from_millis(15));
+        backend.wait(Duration::from_millis(20)).await;
+        let timings = backend.into_timings();
+        assert!(timings[1].planned_us >= 55_000);
+    }
+
+    #[tokio::test]
+    async fn gap_applies_to_top_level_and_parallel_actions() {
+        let mut simulator = InputSimulator::detached((800, 600));
+        simulator.set_timing_policy(TimingPolicy {
+            min_action_gap_ms: 40,
+            ..TimingPolicy::default()
+        });
+        let press = json!({"type": "KeyPress", "params": {"key": "A"}});
+        let sequence: InputAction = serde_json::from_value(json!({"type": "Sequence", "params": {
+            "actions": [press.clone(), {"type": "Delay", "params": {"milliseconds": 10}}, press.clone()]
+        }}))
+        .unwrap();
+        assert_eq!(simulator.dry_run(&sequence).await.unwrap().estimated_duration_ms, 40);
+
+        let parallel: InputAction = serde_json::from_value(json!({"type": "Parallel", "params": {
+            "actions": [{"type": "Sequence", "params": {"actions": [press.clone(), press.clone(), press]}}]
+        }}))
+        .unwrap();
+        assert_eq!(simulator.dry_run(&parallel).await.unwrap().estimated_duration_ms, 80);
+    }
+}
@@ -0,0 +1,82 @@
+use std::any::Any;
+use std::io;
+use std::sync::Arc;
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::RootCertStore;
+
+use crate::auth::TlsConfig;
+
+/// 客户端出示并通过校验的证书，保存在 actix-web 的连接数据中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// 证书主题的 Common Name，没有时为空
+    pub common_name: Option<String>,
+}
+
+/// 按配置读取证书和私钥，创建 rustls 的服务端配置
+pub fn server_config(config: &TlsConfig) -> io::Result<rustls::ServerConfig> {
+    let provider = Arc::new(ring::default_provider());
+    let certs = CertificateDer::pem_file_iter(&config.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| invalid(format!("invalid tls.cert {}: {}", config.cert.display(), err)))?;
+    let key = PrivateKeyDer::from_pem_file(&config.key)
+        .map_err(|err| invalid(format!("invalid tls.key {}: {}", config.key.display(), err)))?;
+
+    let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| invalid(err.to_string()))?;
+    let builder = match &config.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in CertificateDer::pem_file_iter(path)
+                .map_err(|err| invalid(format!("invalid tls.client_ca {}: {}", path.display(), err)))?
+            {
+                let cert = cert.map_err(|err| invalid(format!("invalid tls.client_ca {}: {}", path.display(), err)))?;
+                roots
+                    .add(cert)
+                    .map_err(|err| invalid(format!("invalid tls.client_ca {}: {}", path.display(), err)))?;
+            }
+            // 客户端证书是可选的，未出示证书的客户端还可以使用 token 认证
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .allow_unauthenticated()
+                .build()
+                .map_err(|err| invalid(err.to_string()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    builder
+        .with_single_cert(certs, key)
+        .map_err(|err| invalid(format!("invalid tls.cert or tls.key: {}", err)))
+}
+
+/// 供 `HttpServer::on_connect` 使用：把客户端证书保存到连接数据中
+pub fn on_connect(connection: &dyn Any, data: &mut Extensions) {
+    let Some(stream) = connection.downcast_ref::<TlsStream<TcpStream>>() else {
+        return;
+    };
+    let (_, session) = stream.get_ref();
+    if let Some(cert) = session.peer_certificates().and_then(|certs| certs.first()) {
+        data.insert(ClientCertificate {
+            common_name: common_name(cert),
+        });
+    }
+}
+
+/// 读取 DER 编码证书主题中的 Common Name
+pub fn common_name(der: &[u8]) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    let name = cert.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(name.to_string())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
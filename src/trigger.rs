@@ -0,0 +1,537 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::InputAction;
+use crate::error::AutomationError;
+use crate::hotkey::Hotkey;
+use crate::jobs::{JobError, JobId, JobInfo, JobQueue};
+
+/// 热键触发器：按下全局热键时把动作提交到任务队列
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TriggerConfig {
+    /// 触发器名称，启用和停用时使用
+    pub name: String,
+    pub hotkey: Hotkey,
+    /// 要执行的动作，与 script 二选一
+    #[serde(default)]
+    pub action: Option<InputAction>,
+    /// 动作脚本文件（InputAction 的 JSON，如录制得到的宏），每次触发时重新读取
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+    /// 启动时是否启用
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 任务优先级
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl TriggerConfig {
+    /// 检查一组触发器：名称不能重复，每个触发器的动作都能读取
+    pub fn check_all(triggers: &[TriggerConfig]) -> Result<(), TriggerError> {
+        for (i, trigger) in triggers.iter().enumerate() {
+            if triggers[..i].iter().any(|other| other.name == trigger.name) {
+                return Err(TriggerError::Invalid(format!("duplicate trigger {:?}", trigger.name)));
+            }
+            trigger.load()?;
+        }
+        Ok(())
+    }
+
+    /// 读取要执行的动作
+    pub fn load(&self) -> Result<InputAction, TriggerError> {
+        match (&self.action, &self.script) {
+            (Some(action), None) => Ok(action.clone()),
+            (None, Some(path)) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|err| TriggerError::Invalid(format!("cannot read {}: {}", path.display(), err)))?;
+                serde_json::from_str(&text)
+                    .map_err(|err| TriggerError::Invalid(format!("invalid script {}: {}", path.display(), err)))
+            }
+            _ => Err(TriggerError::Invalid(format!(
+                "trigger {:?} needs exactly one of action and script",
+                self.name
+            ))),
+        }
+    }
+}
+
+/// 触发器状态
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub hotkey: Hotkey,
+    pub enabled: bool,
+    /// 最近一次触发提交的任务
+    pub last_job: Option<JobId>,
+    /// 最近一次触发失败（读取脚本或提交任务）的原因
+    pub last_error: Option<String>,
+}
+
+/// 触发器错误
+#[derive(Debug, Clone)]
+pub enum TriggerError {
+    /// 触发器不存在
+    NotFound(String),
+    /// 配置或脚本无效
+    Invalid(String),
+    /// 抓取或释放热键失败
+    Platform(AutomationError),
+    /// 提交任务失败
+    Job(JobError),
+}
+
+impl fmt::Display for TriggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerError::NotFound(name) => write!(f, "trigger {:?} not found", name),
+            TriggerError::Invalid(message) => write!(f, "invalid trigger: {}", message),
+            TriggerError::Platform(err) => err.fmt(f),
+            TriggerError::Job(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TriggerError {}
+
+impl From<AutomationError> for TriggerError {
+    fn from(err: AutomationError) -> Self {
+        TriggerError::Platform(err)
+    }
+}
+
+impl From<JobError> for TriggerError {
+    fn from(err: JobError) -> Self {
+        TriggerError::Job(err)
+    }
+}
+
+/// 全局热键触发器
+///
+/// 通过 XGrabKey 抓取已启用触发器的热键，热键松开后把动作提交到任务队列，
+/// 同一触发器上一次提交的任务结束前再次按下不会重复提交
+#[derive(Clone)]
+pub struct TriggerManager {
+    shared: Arc<Shared>,
+    _watcher: Arc<Watcher>,
+}
+
+struct Shared {
+    jobs: JobQueue,
+    triggers: Mutex<Vec<Trigger>>,
+    #[cfg(target_os = "linux")]
+    grabs: x11::Grabs,
+}
+
+struct Trigger {
+    config: TriggerConfig,
+    last_job: Option<JobId>,
+    last_error: Option<String>,
+}
+
+impl Trigger {
+    fn info(&self) -> TriggerInfo {
+        TriggerInfo {
+            name: self.config.name.clone(),
+            hotkey: self.config.hotkey.clone(),
+            enabled: self.config.enabled,
+            last_job: self.last_job,
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// 提交动作；上一次提交的任务还未结束时返回该任务，不重复提交
+    fn fire(&mut self, jobs: &JobQueue) -> Result<JobInfo, TriggerError> {
+        if let Some(id) = self.last_job
+            && let Ok(job) = jobs.get(id)
+            && !job.status.is_finished()
+        {
+            return Ok(job);
+        }
+        let submitted = self
+            .config
+            .load()
+            .and_then(|action| Ok(jobs.submit(action, self.config.priority)?));
+        match &submitted {
+            Ok(job) => {
+                self.last_job = Some(job.id);
+                self.last_error = None;
+            }
+            Err(err) => self.last_error = Some(err.to_string()),
+        }
+        submitted
+    }
+}
+
+/// 最后一个 TriggerManager 被释放时通知监听线程退出，线程退出时关闭连接并释放全部热键
+struct Watcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl TriggerManager {
+    /// 连接到指定显示（None 表示使用 DISPLAY 环境变量），抓取已启用触发器的热键并开始监听
+    #[cfg(target_os = "linux")]
+    pub fn start(triggers: Vec<TriggerConfig>, jobs: JobQueue, display: Option<&str>) -> Result<Self, TriggerError> {
+        TriggerConfig::check_all(&triggers)?;
+        let grabs = x11::Grabs::connect(display)?;
+        for trigger in triggers.iter().filter(|trigger| trigger.enabled) {
+            grabs.grab(&trigger.name, &trigger.hotkey)?;
+        }
+        let shared = Arc::new(Shared {
+            jobs,
+            triggers: Mutex::new(
+                triggers
+                    .into_iter()
+                    .map(|config| Trigger {
+                        config,
+                        last_job: None,
+                        last_error: None,
+                    })
+                    .collect(),
+            ),
+            grabs,
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+        x11::spawn(shared.clone(), stop.clone());
+        Ok(TriggerManager {
+            shared,
+            _watcher: Arc::new(Watcher { stop }),
+        })
+    }
+
+    /// 连接到指定显示，抓取已启用触发器的热键并开始监听
+    #[cfg(not(target_os = "linux"))]
+    pub fn start(_triggers: Vec<TriggerConfig>, _jobs: JobQueue, _display: Option<&str>) -> Result<Self, TriggerError> {
+        Err(TriggerError::Platform(AutomationError::Platform(
+            "global hotkeys are only supported on X11".to_string(),
+        )))
+    }
+
+    /// 列出全部触发器
+    pub fn list(&self) -> Vec<TriggerInfo> {
+        self.shared.lock().iter().map(Trigger::info).collect()
+    }
+
+    /// 查询触发器
+    pub fn get(&self, name: &str) -> Result<TriggerInfo, TriggerError> {
+        self.shared.with_trigger(name, |trigger| Ok(trigger.info()))
+    }
+
+    /// 启用触发器并抓取热键
+    pub fn enable(&self, name: &str) -> Result<TriggerInfo, TriggerError> {
+        self.shared.set_enabled(name, true)
+    }
+
+    /// 停用触发器并释放热键
+    pub fn disable(&self, name: &str) -> Result<TriggerInfo, TriggerError> {
+        self.shared.set_enabled(name, false)
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Vec<Trigger>> {
+        self.triggers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn with_trigger<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut Trigger) -> Result<T, TriggerError>,
+    ) -> Result<T, TriggerError> {
+        let mut triggers = self.lock();
+        let trigger = triggers
+            .iter_mut()
+            .find(|trigger| trigger.config.name == name)
+            .ok_or_else(|| TriggerError::NotFound(name.to_string()))?;
+        f(trigger)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<TriggerInfo, TriggerError> {
+        self.with_trigger(name, |trigger| {
+            if trigger.config.enabled != enabled {
+                if enabled {
+                    self.grabs.grab(name, &trigger.config.hotkey)?;
+                } else {
+                    self.grabs.ungrab(name)?;
+                }
+                trigger.config.enabled = enabled;
+            }
+            Ok(trigger.info())
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_enabled(&self, name: &str, _enabled: bool) -> Result<TriggerInfo, TriggerError> {
+        Err(TriggerError::NotFound(name.to_string()))
+    }
+
+    /// 热键按下后提交触发器的动作
+    #[cfg(target_os = "linux")]
+    fn fire(&self, name: &str) -> Result<JobInfo, TriggerError> {
+        self.with_trigger(name, |trigger| trigger.fire(&self.jobs))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::{Duration, Instant};
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt as _;
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+
+    use super::Shared;
+    use crate::error::AutomationError;
+    use crate::hotkey::{Hotkey, KeyGrabs};
+
+    /// 检查热键事件的间隔
+    const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+    /// 等待热键松开的最长时间，超时后仍然提交动作
+    const RELEASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    pub(super) struct Grabs {
+        conn: RustConnection,
+        grabs: Mutex<KeyGrabs<String>>,
+    }
+
+    impl Grabs {
+        pub(super) fn connect(display: Option<&str>) -> Result<Self, AutomationError> {
+            let (conn, screen_num) = x11rb::connect(display)?;
+            let grabs = KeyGrabs::new(&conn, screen_num)?;
+            Ok(Grabs {
+                conn,
+                grabs: Mutex::new(grabs),
+            })
+        }
+
+        pub(super) fn grab(&self, name: &str, hotkey: &Hotkey) -> Result<(), AutomationError> {
+            self.lock().grab(&self.conn, hotkey, name.to_string())
+        }
+
+        pub(super) fn ungrab(&self, name: &str) -> Result<(), AutomationError> {
+            self.lock().ungrab(&self.conn, &name.to_string())
+        }
+
+        /// 取出已到达的事件，返回被按下热键的触发器名称
+        fn pressed(&self) -> Result<Vec<String>, AutomationError> {
+            let mut names: Vec<String> = Vec::new();
+            while let Some(event) = self.conn.poll_for_event()? {
+                if let Event::KeyPress(event) = event {
+                    for name in self.lock().matches(&event) {
+                        if !names.contains(name) {
+                            names.push(name.clone());
+                        }
+                    }
+                }
+            }
+            Ok(names)
+        }
+
+        /// 等待所有键松开，避免热键的修饰键影响要执行的动作
+        fn wait_for_release(&self) -> Result<(), AutomationError> {
+            let started = Instant::now();
+            while started.elapsed() < RELEASE_TIMEOUT {
+                let keymap = self.conn.query_keymap()?.reply()?;
+                if keymap.keys.iter().all(|keys| *keys == 0) {
+                    break;
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Ok(())
+        }
+
+        fn lock(&self) -> MutexGuard<'_, KeyGrabs<String>> {
+            self.grabs.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+
+    pub(super) fn spawn(shared: Arc<Shared>, stop: Arc<AtomicBool>) {
+        std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let pressed = shared.grabs.pressed().and_then(|names| {
+                    if !names.is_empty() {
+                        shared.grabs.wait_for_release()?;
+                    }
+                    Ok(names)
+                });
+                match pressed {
+                    // 失败原因记录在触发器的 last_error 中
+                    Ok(names) => names.iter().for_each(|name| {
+                        let _ = shared.fire(name);
+                    }),
+                    Err(err) => {
+                        let error = format!("hotkey listener stopped: {}", err);
+                        for trigger in shared.lock().iter_mut() {
+                            trigger.last_error = Some(error.clone());
+                        }
+                        return;
+                    }
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::time::{sleep, timeout, Duration};
+
+    use super::*;
+    use crate::executor::ActionExecutor;
+    use crate::jobs::{JobQueueOptions, JobStatus};
+    use crate::simulator::InputSimulator;
+
+    fn trigger(value: serde_json::Value) -> TriggerConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn greeting(name: &str) -> TriggerConfig {
+        trigger(json!({
+            "name": name,
+            "hotkey": {"modifiers": ["Ctrl", "Alt"], "key": "G"},
+            "action": {"type": "Delay", "params": {"milliseconds": 1}}
+        }))
+    }
+
+    /// 没有输入设备的队列：任务会立即以失败结束
+    fn detached_queue() -> JobQueue {
+        let executor = ActionExecutor::with_simulator(InputSimulator::detached((800, 600)));
+        JobQueue::start(Arc::new(executor), JobQueueOptions::default())
+    }
+
+    async fn until_finished(jobs: &JobQueue, id: JobId) {
+        timeout(Duration::from_secs(5), async {
+            while !jobs.get(id).unwrap().status.is_finished() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    fn invalid<T>(result: Result<T, TriggerError>, expected: &str) {
+        match result {
+            Err(TriggerError::Invalid(message)) => assert!(message.contains(expected), "{}", message),
+            Err(err) => panic!("{}", err),
+            Ok(_) => panic!("expected an invalid trigger"),
+        }
+    }
+
+    #[test]
+    fn checks_names_and_action_sources() {
+        TriggerConfig::check_all(&[greeting("a"), greeting("b")]).unwrap();
+        invalid(TriggerConfig::check_all(&[greeting("a"), greeting("a")]), "duplicate trigger \"a\"");
+
+        let mut both = greeting("both");
+        both.script = Some("macro.json".into());
+        invalid(both.load(), "exactly one of action and script");
+        let mut neither = greeting("neither");
+        neither.action = None;
+        invalid(neither.load(), "exactly one of action and script");
+
+        let dir = std::env::temp_dir().join(format!("automation-trigger-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut scripted = greeting("scripted");
+        scripted.action = None;
+        scripted.script = Some(dir.join("missing.json"));
+        invalid(scripted.load(), "cannot read");
+        std::fs::write(dir.join("broken.json"), "{\"type\": \"Nope\"}").unwrap();
+        scripted.script = Some(dir.join("broken.json"));
+        invalid(scripted.load(), "invalid script");
+        invalid(TriggerConfig::check_all(std::slice::from_ref(&scripted)), "invalid script");
+        std::fs::write(dir.join("macro.json"), json!({"type": "Delay", "params": {"milliseconds": 5}}).to_string()).unwrap();
+        scripted.script = Some(dir.join("macro.json"));
+        assert!(matches!(scripted.load(), Ok(InputAction::Delay { milliseconds: 5 })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn does_not_resubmit_until_the_last_job_finishes() {
+        let jobs = detached_queue();
+        let mut trigger = Trigger {
+            config: greeting("greeting"),
+            last_job: None,
+            last_error: None,
+        };
+        // 后台任务在测试让出执行权之前不会开始，第一个任务仍在排队
+        let first = trigger.fire(&jobs).unwrap();
+        assert_eq!(first.status, JobStatus::Queued);
+        assert_eq!(trigger.fire(&jobs).unwrap().id, first.id);
+        assert_eq!(jobs.list().len(), 1);
+
+        until_finished(&jobs, first.id).await;
+        let second = trigger.fire(&jobs).unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(trigger.info().last_job, Some(second.id));
+
+        until_finished(&jobs, second.id).await;
+        trigger.config.action = None;
+        assert!(trigger.fire(&jobs).is_err());
+        let info = trigger.info();
+        assert_eq!(info.last_job, Some(second.id));
+        assert!(info.last_error.unwrap().contains("exactly one of action and script"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn grabbed_hotkeys_submit_jobs_until_disabled() {
+        use enigo::{Direction, Enigo, Key as EnigoKey, Keyboard, Settings};
+
+        if !crate::testing::display_available() {
+            return;
+        }
+        let jobs = detached_queue();
+        let manager = TriggerManager::start(vec![greeting("greeting")], jobs.clone(), None).unwrap();
+        let press = || {
+            let mut enigo = Enigo::new(&Settings::default()).unwrap();
+            enigo.key(EnigoKey::Control, Direction::Press).unwrap();
+            enigo.key(EnigoKey::Alt, Direction::Press).unwrap();
+            enigo.key(EnigoKey::Unicode('g'), Direction::Click).unwrap();
+            enigo.key(EnigoKey::Alt, Direction::Release).unwrap();
+            enigo.key(EnigoKey::Control, Direction::Release).unwrap();
+        };
+
+        press();
+        let job = timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(job) = manager.get("greeting").unwrap().last_job {
+                    break job;
+                }
+                sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+        until_finished(&jobs, job).await;
+
+        assert!(!manager.disable("greeting").unwrap().enabled);
+        press();
+        sleep(Duration::from_millis(300)).await;
+        assert_eq!(manager.get("greeting").unwrap().last_job, Some(job));
+        assert_eq!(jobs.list().len(), 1);
+        assert!(manager.enable("greeting").unwrap().enabled);
+    }
+}
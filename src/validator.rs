@@ -0,0 +1,570 @@
+use std::collections::{HashMap, HashSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::accessibility::ElementSelector;
+use crate::enums::{InputAction, Key, KeyLocation, MouseButton, Origin};
+use crate::keymap::KeyMapper;
+use crate::screen::Rect;
+use crate::vision::ImageSource;
+use crate::window::WindowSelector;
+
+/// 诊断信息的严重程度
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// 单条校验诊断
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 出问题的节点路径，例如 `$.actions[2].actions[0]`
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} at {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+/// 校验选项
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ValidationOptions {
+    /// 屏幕尺寸，设置后会检查坐标是否越界
+    pub screen_size: Option<(u32, u32)>,
+    /// 单次延时的上限（毫秒），超过时给出警告
+    pub max_delay_ms: u64,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            screen_size: None,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+/// 使用默认选项校验动作树
+pub fn validate(action: &InputAction) -> Vec<Diagnostic> {
+    validate_with(action, &ValidationOptions::default())
+}
+
+/// 使用指定选项校验动作树
+pub fn validate_with(action: &InputAction, options: &ValidationOptions) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        options,
+        key_mapper: KeyMapper::new(),
+        held_keys: HashMap::new(),
+        held_buttons: HashMap::new(),
+        anchors: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    validator.visit(action, "$");
+
+    let mut held: Vec<_> = validator.held_keys.drain().collect();
+    held.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (key, path) in held {
+        validator.push(Severity::Warning, &path, format!("KeyDown {:?} has no matching KeyUp", key));
+    }
+    let mut held: Vec<_> = validator.held_buttons.drain().collect();
+    held.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (button, path) in held {
+        validator.push(Severity::Warning, &path, format!("MouseDown {:?} has no matching MouseUp", button));
+    }
+    validator.diagnostics
+}
+
+/// 判断诊断列表中是否包含错误
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+struct Validator<'a> {
+    options: &'a ValidationOptions,
+    key_mapper: KeyMapper,
+    /// 已按下但尚未释放的键及其 KeyDown 所在路径
+    held_keys: HashMap<Key, String>,
+    /// 已按下但尚未释放的鼠标按键及其 MouseDown 所在路径
+    held_buttons: HashMap<MouseButton, String>,
+    /// 前面的动作已保存的锚点名称
+    anchors: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn push(&mut self, severity: Severity, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    fn visit(&mut self, action: &InputAction, path: &str) {
+        if let Some(target) = action.target() {
+            self.check_window(path, &target.window);
+        }
+        // 相对坐标要到执行时才能确定，不检查是否越界
+        let relative = match action.origin() {
+            None | Some(Origin::Screen) => false,
+            Some(Origin::Window(window)) => {
+                self.check_window(path, window);
+                true
+            }
+            Some(Origin::Anchor(name)) => {
+                if !self.anchors.contains(name) {
+                    self.push(
+                        Severity::Error,
+                        path,
+                        format!("anchor {:?} is not saved by any earlier action", name),
+                    );
+                }
+                true
+            }
+        };
+        match action {
+            // 鼠标操作
+            InputAction::MouseMove { x, y, .. }
+            | InputAction::MouseClick { x, y, .. }
+            | InputAction::MouseDoubleClick { x, y, .. } => {
+                if !relative {
+                    self.check_point(path, *x, *y);
+                }
+            }
+            InputAction::MousePress { x, y, duration_ms, .. } => {
+                if !relative {
+                    self.check_point(path, *x, *y);
+                }
+                if *duration_ms == 0 {
+                    self.push(Severity::Warning, path, "MousePress with zero duration is a plain click".to_string());
+                }
+                self.check_delay(path, "duration_ms", *duration_ms);
+            }
+            InputAction::MouseDrag { start_x, start_y, end_x, end_y, duration_ms, .. } => {
+                if !relative {
+                    self.check_point(path, *start_x, *start_y);
+                    self.check_point(path, *end_x, *end_y);
+                }
+                if *duration_ms == 0 {
+                    self.push(Severity::Error, path, "MouseDrag duration_ms must be greater than 0".to_string());
+                } else if *duration_ms < 20 {
+                    self.push(
+                        Severity::Warning,
+                        path,
+                        format!("MouseDrag duration_ms {} is shorter than one step per move (20 steps)", duration_ms),
+                    );
+                }
+                self.check_delay(path, "duration_ms", *duration_ms);
+            }
+            InputAction::MouseDown { button, .. } => {
+                if self.held_buttons.contains_key(button) {
+                    self.push(Severity::Warning, path, format!("MouseDown {:?} while it is already held", button));
+                } else {
+                    self.held_buttons.insert(*button, path.to_string());
+                }
+            }
+            InputAction::MouseUp { button, .. } => {
+                if self.held_buttons.remove(button).is_none() {
+                    self.push(Severity::Warning, path, format!("MouseUp {:?} without a preceding MouseDown", button));
+                }
+            }
+            InputAction::MouseScroll { delta_x, delta_y, .. } => {
+                if *delta_x == 0 && *delta_y == 0 {
+                    self.push(Severity::Info, path, "MouseScroll has no effect".to_string());
+                }
+            }
+
+            // 键盘操作
+            InputAction::KeyPress { key, location, .. } => {
+                self.check_key(path, *key, *location);
+            }
+            InputAction::KeyDown { key, location, .. } => {
+                self.check_key(path, *key, *location);
+                if self.held_keys.contains_key(key) {
+                    self.push(Severity::Warning, path, format!("KeyDown {:?} while it is already held", key));
+                } else {
+                    self.held_keys.insert(*key, path.to_string());
+                }
+            }
+            InputAction::KeyUp { key, location, .. } => {
+                self.check_key(path, *key, *location);
+                if self.held_keys.remove(key).is_none() {
+                    self.push(Severity::Warning, path, format!("KeyUp {:?} without a preceding KeyDown", key));
+                }
+            }
+            InputAction::KeySequence { keys, key_delay_ms, .. } => {
+                for key in keys {
+                    self.check_key(path, *key, None);
+                }
+                if keys.is_empty() {
+                    self.push(Severity::Info, path, "KeySequence is empty".to_string());
+                }
+                if let Some(delay) = key_delay_ms {
+                    self.check_delay(path, "key_delay_ms", *delay);
+                }
+            }
+            InputAction::TypeText { text, char_delay_ms, .. } => {
+                if text.is_empty() {
+                    self.push(Severity::Info, path, "TypeText is empty".to_string());
+                }
+                if let Some(delay) = char_delay_ms {
+                    self.check_delay(path, "char_delay_ms", *delay);
+                    let total = delay.saturating_mul(text.chars().count() as u64);
+                    self.check_delay(path, "total typing time", total);
+                }
+            }
+            InputAction::Hotkey { modifiers, key, location, .. } => {
+                for modifier in modifiers {
+                    self.check_key(path, *modifier, None);
+                }
+                self.check_key(path, *key, *location);
+            }
+
+            // 延时操作
+            InputAction::Delay { milliseconds } => {
+                self.check_delay(path, "milliseconds", *milliseconds);
+            }
+
+            // 屏幕条件
+            InputAction::WaitForPixel { x, y, timeout_ms, .. } => {
+                self.check_point(path, *x, *y);
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::WaitForScreenStable { region, quiet_ms, timeout_ms } => {
+                if let Some(region) = region {
+                    self.check_region(path, region);
+                }
+                if quiet_ms >= timeout_ms {
+                    self.push(
+                        Severity::Error,
+                        path,
+                        format!("quiet_ms {} must be shorter than timeout_ms {}", quiet_ms, timeout_ms),
+                    );
+                }
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::WaitForChange { region, timeout_ms } => {
+                if let Some(region) = region {
+                    self.check_region(path, region);
+                }
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::WaitForText { text, region, timeout_ms, anchor }
+            | InputAction::ClickText { text, region, timeout_ms, anchor, .. } => {
+                self.save_anchor(anchor);
+                if !cfg!(feature = "ocr") {
+                    self.push(Severity::Error, path, "text recognition requires the `ocr` feature".to_string());
+                }
+                if text.trim().is_empty() {
+                    self.push(Severity::Error, path, "text is empty".to_string());
+                }
+                if let Some(region) = region {
+                    self.check_region(path, region);
+                }
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::FocusWindow { window }
+            | InputAction::MoveWindow { window, .. }
+            | InputAction::MinimizeWindow { window }
+            | InputAction::CloseWindow { window } => {
+                self.check_window(path, window);
+            }
+            InputAction::ResizeWindow { window, width, height } => {
+                self.check_window(path, window);
+                if *width == 0 || *height == 0 {
+                    self.push(Severity::Error, path, format!("window size {}x{} is empty", width, height));
+                }
+            }
+            InputAction::WaitForWindow { window, timeout_ms } => {
+                self.check_window(path, window);
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::WaitForElement { selector, state, timeout_ms, anchor } => {
+                self.save_anchor(anchor);
+                self.check_element(path, selector);
+                if state.as_ref().is_some_and(|state| state.trim().is_empty()) {
+                    self.push(Severity::Error, path, "state is empty".to_string());
+                }
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::ClickElement { selector, timeout_ms }
+            | InputAction::SetElementText { selector, timeout_ms, .. } => {
+                self.check_element(path, selector);
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+            InputAction::WaitForImage { image, region, threshold, timeout_ms, anchor }
+            | InputAction::ClickImage { image, region, threshold, timeout_ms, anchor, .. } => {
+                self.save_anchor(anchor);
+                self.check_image(path, image);
+                if let Some(region) = region {
+                    self.check_region(path, region);
+                }
+                if !(*threshold > 0.0 && *threshold <= 1.0) {
+                    self.push(Severity::Error, path, format!("threshold {} must be in (0, 1]", threshold));
+                }
+                self.check_delay(path, "timeout_ms", *timeout_ms);
+            }
+
+            // 组合操作
+            InputAction::Sequence { actions } => {
+                if actions.is_empty() {
+                    self.push(Severity::Info, path, "Sequence is empty".to_string());
+                }
+                for (i, action) in actions.iter().enumerate() {
+                    self.visit(action, &format!("{}.actions[{}]", path, i));
+                }
+            }
+            InputAction::Parallel { actions } => {
+                if let Some(inner) = actions.iter().find_map(InputAction::find_unparallelizable) {
+                    self.push(
+                        Severity::Error,
+                        path,
+                        format!("{} cannot run inside Parallel", inner.name()),
+                    );
+                }
+                for (i, action) in actions.iter().enumerate() {
+                    self.visit(action, &format!("{}.actions[{}]", path, i));
+                }
+            }
+        }
+    }
+
+    fn check_point(&mut self, path: &str, x: i32, y: i32) {
+        let Some((width, height)) = self.options.screen_size else {
+            return;
+        };
+        if x < 0 || y < 0 || x as i64 >= width as i64 || y as i64 >= height as i64 {
+            self.push(
+                Severity::Error,
+                path,
+                format!("point ({}, {}) is outside the screen {}x{}", x, y, width, height),
+            );
+        }
+    }
+
+    fn check_region(&mut self, path: &str, region: &Rect) {
+        if region.width == 0 || region.height == 0 {
+            self.push(Severity::Error, path, format!("region {:?} is empty", region));
+            return;
+        }
+        let Some((width, height)) = self.options.screen_size else {
+            return;
+        };
+        if region.intersect(&Rect::new(0, 0, width, height)).is_none() {
+            self.push(
+                Severity::Error,
+                path,
+                format!("region {:?} is outside the screen {}x{}", region, width, height),
+            );
+        }
+    }
+
+    fn save_anchor(&mut self, anchor: &Option<String>) {
+        if let Some(name) = anchor {
+            self.anchors.insert(name.clone());
+        }
+    }
+
+    fn check_window(&mut self, path: &str, window: &WindowSelector) {
+        if window.is_empty() {
+            self.push(Severity::Error, path, "window selector has no conditions".to_string());
+        }
+    }
+
+    fn check_element(&mut self, path: &str, selector: &ElementSelector) {
+        if !cfg!(all(target_os = "linux", feature = "accessibility")) {
+            self.push(Severity::Error, path, "element actions require the `accessibility` feature on Linux".to_string());
+        }
+        if selector.is_empty() {
+            self.push(Severity::Error, path, "element selector has no conditions".to_string());
+        }
+        if selector.path.is_some() && selector.path_indices().is_none() {
+            self.push(Severity::Error, path, format!("element path {:?} is not a list of child indices", selector.path));
+        }
+    }
+
+    fn check_image(&mut self, path: &str, image: &ImageSource) {
+        if let Err(err) = image.load() {
+            self.push(Severity::Error, path, format!("image cannot be loaded: {}", err));
+        }
+    }
+
+    fn check_key(&mut self, path: &str, key: Key, location: Option<KeyLocation>) {
+        if self.key_mapper.map_key(key, location).is_none() {
+            self.push(Severity::Error, path, format!("key {:?} is not mapped on this platform", key));
+        }
+    }
+
+    fn check_delay(&mut self, path: &str, field: &str, milliseconds: u64) {
+        if milliseconds > self.options.max_delay_ms {
+            self.push(
+                Severity::Warning,
+                path,
+                format!("{} of {}ms exceeds the limit of {}ms", field, milliseconds, self.options.max_delay_ms),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn action(value: serde_json::Value) -> InputAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<(Severity, &str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.path.as_str(), d.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_sequence_has_no_diagnostics() {
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseMove", "params": {"x": 10, "y": 20}},
+            {"type": "KeyDown", "params": {"key": "Shift"}},
+            {"type": "TypeText", "params": {"text": "abc", "char_delay_ms": 5}},
+            {"type": "KeyUp", "params": {"key": "Shift"}},
+            {"type": "Delay", "params": {"milliseconds": 100}}
+        ]}}));
+        let options = ValidationOptions {
+            screen_size: Some((1920, 1080)),
+            ..ValidationOptions::default()
+        };
+        assert_eq!(validate_with(&script, &options), Vec::new());
+    }
+
+    #[test]
+    fn points_outside_the_screen_are_errors() {
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseClick", "params": {"button": "Left", "x": 1920, "y": 0}},
+            {"type": "MouseMove", "params": {"x": -1, "y": 5}}
+        ]}}));
+        let options = ValidationOptions {
+            screen_size: Some((1920, 1080)),
+            ..ValidationOptions::default()
+        };
+        let diagnostics = validate_with(&script, &options);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Error, "$.actions[0]", "point (1920, 0) is outside the screen 1920x1080"),
+                (Severity::Error, "$.actions[1]", "point (-1, 5) is outside the screen 1920x1080"),
+            ]
+        );
+        assert!(has_errors(&diagnostics));
+        // 不知道屏幕尺寸时不检查坐标
+        assert!(validate(&script).is_empty());
+    }
+
+    #[test]
+    fn unbalanced_keys_and_buttons_are_warnings() {
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "KeyDown", "params": {"key": "Ctrl"}},
+            {"type": "KeyUp", "params": {"key": "Alt"}},
+            {"type": "MouseDown", "params": {"button": "Left"}},
+            {"type": "MouseDown", "params": {"button": "Left"}}
+        ]}}));
+        let diagnostics = validate(&script);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Warning, "$.actions[1]", "KeyUp Alt without a preceding KeyDown"),
+                (Severity::Warning, "$.actions[3]", "MouseDown Left while it is already held"),
+                (Severity::Warning, "$.actions[0]", "KeyDown Ctrl has no matching KeyUp"),
+                (Severity::Warning, "$.actions[2]", "MouseDown Left has no matching MouseUp"),
+            ]
+        );
+        assert!(!has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn delays_over_the_limit_are_warnings() {
+        let script = action(json!({"type": "TypeText", "params": {"text": "abcd", "char_delay_ms": 30}}));
+        let options = ValidationOptions {
+            max_delay_ms: 100,
+            ..ValidationOptions::default()
+        };
+        assert_eq!(
+            messages(&validate_with(&script, &options)),
+            vec![(Severity::Warning, "$", "total typing time of 120ms exceeds the limit of 100ms")]
+        );
+    }
+
+    #[test]
+    fn drag_duration_must_be_positive() {
+        let script = action(json!({"type": "MouseDrag", "params": {
+            "button": "Left", "start_x": 0, "start_y": 0, "end_x": 10, "end_y": 10, "duration_ms": 0
+        }}));
+        assert_eq!(
+            messages(&validate(&script)),
+            vec![(Severity::Error, "$", "MouseDrag duration_ms must be greater than 0")]
+        );
+    }
+
+    #[test]
+    fn anchors_must_be_saved_before_use() {
+        let click = json!({"type": "MouseClick", "params": {
+            "button": "Left", "x": 5, "y": 5, "origin": {"Anchor": "ok"}
+        }});
+        let diagnostics = validate(&action(click.clone()));
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(Severity::Error, "$", "anchor \"ok\" is not saved by any earlier action")]
+        );
+
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "WaitForImage", "params": {
+                "image": {"base64": "!"}, "threshold": 0.9, "timeout_ms": 1000, "anchor": "ok"
+            }},
+            click
+        ]}}));
+        let diagnostics = validate(&script);
+        // 锚点已保存，只剩图片无法加载的错误
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "$.actions[0]");
+        assert!(diagnostics[0].message.starts_with("image cannot be loaded"));
+    }
+
+    #[test]
+    fn relative_points_are_not_checked_against_the_screen() {
+        let origin = json!({"Window": {"title": "Editor"}});
+        let script = action(json!({"type": "Sequence", "params": {"actions": [
+            {"type": "MouseMove", "params": {"x": -50, "y": 5000, "origin": origin}},
+            {"type": "MouseDoubleClick", "params": {"button": "Left", "x": 900, "y": 10, "origin": origin}},
+            {"type": "MousePress", "params": {
+                "button": "Left", "x": 10, "y": 700, "duration_ms": 500, "origin": origin
+            }}
+        ]}}));
+        let options = ValidationOptions {
+            screen_size: Some((800, 600)),
+            ..ValidationOptions::default()
+        };
+        assert!(validate_with(&script, &options).is_empty());
+    }
+
+    #[test]
+    fn parallel_rejects_waits() {
+        let script = action(json!({"type": "Parallel", "params": {"actions": [
+            {"type": "Delay", "params": {"milliseconds": 10}},
+            {"type": "WaitForChange", "params": {"timeout_ms": 1000}}
+        ]}}));
+        assert_eq!(
+            messages(&validate(&script)),
+            vec![(Severity::Error, "$", "WaitForChange cannot run inside Parallel")]
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use base64::Engine;
+use image::{imageops, GrayImage, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AutomationError;
+use crate::screen::{Color, Rect};
+
+/// 模板图片来源
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSource {
+    /// 执行端本地的 PNG 文件路径
+    Path(String),
+    /// Base64 编码的 PNG 数据
+    Base64(String),
+}
+
+impl ImageSource {
+    /// 加载并解码图片
+    pub fn load(&self) -> Result<RgbaImage, AutomationError> {
+        let image = match self {
+            ImageSource::Path(path) => image::open(path),
+            ImageSource::Base64(data) => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(|err| AutomationError::Image(err.to_string()))?;
+                image::load_from_memory(&bytes)
+            }
+        };
+        image
+            .map(|image| image.to_rgba8())
+            .map_err(|err| AutomationError::Image(err.to_string()))
+    }
+}
+
+/// 模板匹配结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ImageMatch {
+    /// 匹配位置（屏幕坐标）
+    pub rect: Rect,
+    /// 归一化互相关得分，1.0 表示完全一致
+    pub confidence: f32,
+}
+
+/// 粗匹配阶段相对于阈值的放宽量
+const COARSE_MARGIN: f32 = 0.15;
+/// 标准差低于该值的图像块视为纯色
+const FLAT_EPSILON: f32 = 1e-3;
+
+/// 在 haystack 中查找 template，返回得分不低于 threshold 的所有不重叠匹配（按得分降序）
+///
+/// 基于灰度图的归一化互相关；先在缩小的图像上找候选位置，再在原始分辨率下精确定位
+pub fn match_template(haystack: &RgbaImage, template: &RgbaImage, threshold: f32) -> Vec<ImageMatch> {
+    let (tw, th) = template.dimensions();
+    let (hw, hh) = haystack.dimensions();
+    if tw == 0 || th == 0 || tw > hw || th > hh {
+        return Vec::new();
+    }
+
+    let haystack = imageops::grayscale(haystack);
+    let template = imageops::grayscale(template);
+
+    // 缩放倍数保证缩小后的模板边长至少 8 像素
+    let scale = (tw.min(th) / 8).clamp(1, 4);
+    let candidates = if scale > 1 {
+        let small_haystack = downscale(&haystack, scale);
+        let small_template = downscale(&template, scale);
+        Correlator::new(&small_haystack, &small_template)
+            .scan(threshold - COARSE_MARGIN)
+            .into_iter()
+            .map(|(x, y, _)| (x * scale, y * scale))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let correlator = Correlator::new(&haystack, &template);
+    let mut matches: Vec<(u32, u32, f32)> = if scale > 1 {
+        candidates
+            .into_iter()
+            .filter_map(|(cx, cy)| correlator.refine(cx, cy, scale))
+            .filter(|&(_, _, score)| score >= threshold)
+            .collect()
+    } else {
+        correlator.scan(threshold)
+    };
+
+    matches.sort_by(|a, b| b.2.total_cmp(&a.2));
+    suppress_overlaps(matches, tw, th)
+        .into_iter()
+        .map(|(x, y, confidence)| ImageMatch {
+            rect: Rect::new(x as i32, y as i32, tw, th),
+            confidence,
+        })
+        .collect()
+}
+
+/// 在屏幕（或指定区域）中查找模板图片
+#[cfg(target_os = "linux")]
+pub fn find_image(
+    template: &RgbaImage,
+    region: Option<Rect>,
+    threshold: f32,
+) -> Result<Vec<ImageMatch>, AutomationError> {
+    let capturer = crate::screen::ScreenCapturer::connect(None)?;
+    let (width, height) = capturer.screen_size();
+    let region = capturer.clip(region.unwrap_or(Rect::new(0, 0, width, height)))?;
+    let screenshot = capturer.capture_region(region)?;
+    Ok(offset_matches(match_template(&screenshot, template, threshold), region))
+}
+
+/// 将相对于截图的匹配位置换算为屏幕坐标，region 为实际截取的区域（已裁掉屏幕以外的部分）
+pub fn offset_matches(matches: Vec<ImageMatch>, region: Rect) -> Vec<ImageMatch> {
+    matches
+        .into_iter()
+        .map(|mut found| {
+            found.rect.x += region.x;
+            found.rect.y += region.y;
+            found
+        })
+        .collect()
+}
+
+/// 两帧之间发生变化的像素比例（0~1），任一通道差值超过 tolerance 视为变化；尺寸不同视为完全变化
+pub fn frame_difference(previous: &RgbaImage, current: &RgbaImage, tolerance: u8) -> f32 {
+    if previous.dimensions() != current.dimensions() {
+        return 1.0;
+    }
+    let total = previous.width() as usize * previous.height() as usize;
+    if total == 0 {
+        return 0.0;
+    }
+    let changed = previous
+        .pixels()
+        .zip(current.pixels())
+        .filter(|(a, b)| !Color::from(**a).matches(&Color::from(**b), tolerance))
+        .count();
+    changed as f32 / total as f32
+}
+
+fn downscale(image: &GrayImage, scale: u32) -> GrayImage {
+    let (width, height) = image.dimensions();
+    imageops::resize(
+        image,
+        (width / scale).max(1),
+        (height / scale).max(1),
+        imageops::FilterType::Triangle,
+    )
+}
+
+/// 按得分从高到低保留互不重叠的匹配
+fn suppress_overlaps(matches: Vec<(u32, u32, f32)>, width: u32, height: u32) -> Vec<(u32, u32, f32)> {
+    let mut kept: Vec<(u32, u32, f32)> = Vec::new();
+    for candidate in matches {
+        let overlaps = kept
+            .iter()
+            .any(|k| k.0.abs_diff(candidate.0) < width && k.1.abs_diff(candidate.1) < height);
+        if !overlaps {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+/// 归一化互相关计算，使用积分图快速求窗口均值和方差
+struct Correlator<'a> {
+    haystack: &'a GrayImage,
+    template: Vec<f32>,
+    template_mean: f32,
+    template_norm: f32,
+    tw: u32,
+    th: u32,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl<'a> Correlator<'a> {
+    fn new(haystack: &'a GrayImage, template: &GrayImage) -> Self {
+        let (tw, th) = template.dimensions();
+        let n = (tw * th) as f32;
+        let mean = template.pixels().map(|p| p.0[0] as f32).sum::<f32>() / n;
+        let centered: Vec<f32> = template.pixels().map(|p| p.0[0] as f32 - mean).collect();
+        let template_norm = centered.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+        let (hw, hh) = haystack.dimensions();
+        let stride = hw as usize + 1;
+        let mut sum = vec![0.0f64; stride * (hh as usize + 1)];
+        let mut sum_sq = vec![0.0f64; stride * (hh as usize + 1)];
+        for y in 0..hh as usize {
+            let mut row = 0.0;
+            let mut row_sq = 0.0;
+            for x in 0..hw as usize {
+                let v = haystack.get_pixel(x as u32, y as u32).0[0] as f64;
+                row += v;
+                row_sq += v * v;
+                sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row;
+                sum_sq[(y + 1) * stride + x + 1] = sum_sq[y * stride + x + 1] + row_sq;
+            }
+        }
+
+        Correlator {
+            haystack,
+            template: centered,
+            template_mean: mean,
+            template_norm,
+            tw,
+            th,
+            sum,
+            sum_sq,
+        }
+    }
+
+    fn window_sum(table: &[f64], stride: usize, x: usize, y: usize, w: usize, h: usize) -> f64 {
+        table[(y + h) * stride + x + w] - table[y * stride + x + w] - table[(y + h) * stride + x]
+            + table[y * stride + x]
+    }
+
+    /// 计算模板左上角位于 (x, y) 时的得分
+    fn score(&self, x: u32, y: u32) -> f32 {
+        let stride = self.haystack.width() as usize + 1;
+        let (w, h) = (self.tw as usize, self.th as usize);
+        let n = (w * h) as f64;
+        let sum = Self::window_sum(&self.sum, stride, x as usize, y as usize, w, h);
+        let sum_sq = Self::window_sum(&self.sum_sq, stride, x as usize, y as usize, w, h);
+        let variance = (sum_sq - sum * sum / n).max(0.0);
+        let window_norm = variance.sqrt() as f32;
+
+        if self.template_norm < FLAT_EPSILON || window_norm < FLAT_EPSILON {
+            // 纯色模板或纯色区域无法归一化：仅当两者都是纯色且亮度接近时视为匹配
+            let window_mean = (sum / n) as f32;
+            let both_flat = self.template_norm < FLAT_EPSILON && window_norm < FLAT_EPSILON;
+            return if both_flat && (window_mean - self.template_mean).abs() <= 2.0 {
+                1.0
+            } else {
+                0.0
+            };
+        }
+
+        let mut dot = 0.0f32;
+        for ty in 0..self.th {
+            let row = &self.template[(ty * self.tw) as usize..((ty + 1) * self.tw) as usize];
+            for (tx, t) in row.iter().enumerate() {
+                dot += t * self.haystack.get_pixel(x + tx as u32, y + ty).0[0] as f32;
+            }
+        }
+        (dot / (self.template_norm * window_norm)).min(1.0)
+    }
+
+    /// 扫描所有位置，返回得分不低于 threshold 的局部结果
+    fn scan(&self, threshold: f32) -> Vec<(u32, u32, f32)> {
+        let (hw, hh) = self.haystack.dimensions();
+        let mut found = Vec::new();
+        for y in 0..=(hh - self.th) {
+            for x in 0..=(hw - self.tw) {
+                let score = self.score(x, y);
+                if score >= threshold {
+                    found.push((x, y, score));
+                }
+            }
+        }
+        found
+    }
+
+    /// 在候选位置附近 ±radius 范围内寻找最高得分
+    fn refine(&self, cx: u32, cy: u32, radius: u32) -> Option<(u32, u32, f32)> {
+        let (hw, hh) = self.haystack.dimensions();
+        let max_x = hw - self.tw;
+        let max_y = hh - self.th;
+        let mut best: Option<(u32, u32, f32)> = None;
+        for y in cy.saturating_sub(radius)..=(cy + radius).min(max_y) {
+            for x in cx.saturating_sub(radius)..=(cx + radius).min(max_x) {
+                let score = self.score(x, y);
+                if best.is_none_or(|b| score > b.2) {
+                    best = Some((x, y, score));
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// 以 cell 像素为单位的伪随机灰度纹理
+    fn noise(width: u32, height: u32, cell: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let seed = (x / cell).wrapping_mul(2_654_435_761) ^ (y / cell).wrapping_mul(40_503);
+            let v = (seed.wrapping_mul(2_246_822_519) >> 24) as u8;
+            Rgba([v, v, v, 255])
+        })
+    }
+
+    #[test]
+    fn finds_exact_template_location() {
+        let haystack = noise(120, 80, 1);
+        let template = imageops::crop_imm(&haystack, 37, 21, 12, 10).to_image();
+        let matches = match_template(&haystack, &template, 0.95);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(37, 21, 12, 10));
+        assert!(matches[0].confidence > 0.99);
+    }
+
+    #[test]
+    fn coarse_to_fine_search_finds_large_templates() {
+        let haystack = noise(200, 150, 4);
+        let template = imageops::crop_imm(&haystack, 61, 45, 40, 32).to_image();
+        let matches = match_template(&haystack, &template, 0.95);
+        assert_eq!(matches.first().map(|m| m.rect), Some(Rect::new(61, 45, 40, 32)));
+    }
+
+    #[test]
+    fn no_match_below_threshold_or_oversized_template() {
+        let haystack = noise(60, 40, 1);
+        let other = noise(80, 60, 3);
+        let template = imageops::crop_imm(&other, 5, 5, 10, 10).to_image();
+        assert!(match_template(&haystack, &template, 0.9).is_empty());
+        assert!(match_template(&haystack, &noise(61, 10, 1), 0.5).is_empty());
+        assert!(match_template(&haystack, &RgbaImage::new(0, 0), 0.5).is_empty());
+    }
+
+    #[test]
+    fn flat_templates_match_only_flat_regions_of_similar_brightness() {
+        let mut haystack = noise(40, 30, 1);
+        for (x, y, pixel) in haystack.enumerate_pixels_mut() {
+            if (10..20).contains(&x) && (5..15).contains(&y) {
+                *pixel = Rgba([200, 200, 200, 255]);
+            }
+        }
+        let bright = RgbaImage::from_pixel(10, 10, Rgba([201, 201, 201, 255]));
+        let dark = RgbaImage::from_pixel(10, 10, Rgba([20, 20, 20, 255]));
+        let matches = match_template(&haystack, &bright, 0.9);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(10, 5, 10, 10));
+        assert!(match_template(&haystack, &dark, 0.9).is_empty());
+    }
+
+    #[test]
+    fn overlapping_matches_are_suppressed() {
+        // 两处相同的图案，相距超过模板尺寸
+        let pattern = noise(8, 8, 1);
+        let mut haystack = RgbaImage::from_pixel(50, 20, Rgba([0, 0, 0, 255]));
+        imageops::replace(&mut haystack, &pattern, 3, 4);
+        imageops::replace(&mut haystack, &pattern, 30, 9);
+        let matches = match_template(&haystack, &pattern, 0.95);
+        let mut rects: Vec<Rect> = matches.iter().map(|m| m.rect).collect();
+        rects.sort_by_key(|r| r.x);
+        assert_eq!(rects, vec![Rect::new(3, 4, 8, 8), Rect::new(30, 9, 8, 8)]);
+
+        let kept = suppress_overlaps(vec![(0, 0, 1.0), (3, 2, 0.9), (8, 0, 0.8)], 8, 8);
+        assert_eq!(kept, vec![(0, 0, 1.0), (8, 0, 0.8)]);
+    }
+
+    #[test]
+    fn offsets_matches_by_captured_region() {
+        let found = ImageMatch { rect: Rect::new(5, 6, 10, 10), confidence: 1.0 };
+        let moved = offset_matches(vec![found], Rect::new(100, -20, 50, 50));
+        assert_eq!(moved[0].rect, Rect::new(105, -14, 10, 10));
+    }
+
+    #[test]
+    fn frame_difference_counts_changed_pixels() {
+        let previous = noise(20, 10, 1);
+        assert_eq!(frame_difference(&previous, &previous, 0), 0.0);
+
+        let mut current = previous.clone();
+        for x in 0..20 {
+            for y in 0..5 {
+                let Rgba([v, _, _, a]) = *current.get_pixel(x, y);
+                let v = if v > 127 { v - 10 } else { v + 10 };
+                current.put_pixel(x, y, Rgba([v, v, v, a]));
+            }
+        }
+        assert_eq!(frame_difference(&previous, &current, 0), 0.5);
+        assert_eq!(frame_difference(&previous, &current, 9), 0.5);
+        assert_eq!(frame_difference(&previous, &current, 10), 0.0);
+    }
+
+    #[test]
+    fn frame_difference_edge_cases() {
+        assert_eq!(frame_difference(&noise(20, 10, 1), &noise(10, 20, 1), 0), 1.0);
+        assert_eq!(frame_difference(&RgbaImage::new(0, 0), &RgbaImage::new(0, 0), 0), 0.0);
+        // 只比较颜色通道，透明度变化不算
+        let opaque = RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 255]));
+        let transparent = RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 0]));
+        assert_eq!(frame_difference(&opaque, &transparent, 0), 0.0);
+    }
+}
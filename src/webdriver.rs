@@ -0,0 +1,1230 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, post, web, HttpResponse, ResponseError};
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::auth::{AuthError, Principal};
+use crate::enums::{InputAction, Key, KeyLocation, MouseButton};
+use crate::error::AutomationError;
+use crate::executor::ActionExecutor;
+use crate::jobs::JobQueue;
+use crate::keymap::KeyMapper;
+use crate::screen::Rect;
+use crate::window::{WindowInfo, WindowSelector};
+
+/// 自定义能力：按条件选择会话的初始窗口，值为 `WindowSelector`
+pub const WINDOW_CAPABILITY: &str = "automation:window";
+
+/// W3C WebDriver 的错误响应，`error` 为规范中的错误码，如 `invalid argument`
+#[derive(Debug)]
+pub struct WebDriverError {
+    pub status: StatusCode,
+    pub error: &'static str,
+    pub message: String,
+}
+
+impl WebDriverError {
+    fn new(status: StatusCode, error: &'static str, message: impl Into<String>) -> Self {
+        WebDriverError {
+            status,
+            error,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_argument(message: impl Into<String>) -> Self {
+        WebDriverError::new(StatusCode::BAD_REQUEST, "invalid argument", message)
+    }
+
+    fn no_such_window(message: impl Into<String>) -> Self {
+        WebDriverError::new(StatusCode::NOT_FOUND, "no such window", message)
+    }
+}
+
+impl From<AutomationError> for WebDriverError {
+    fn from(err: AutomationError) -> Self {
+        let message = err.to_string();
+        match err {
+            AutomationError::Validation(_) => WebDriverError::invalid_argument(message),
+            AutomationError::Timeout(_) => WebDriverError::new(StatusCode::INTERNAL_SERVER_ERROR, "timeout", message),
+            AutomationError::Policy(_) => {
+                WebDriverError::new(StatusCode::INTERNAL_SERVER_ERROR, "unsupported operation", message)
+            }
+            AutomationError::Platform(_) | AutomationError::Image(_) | AutomationError::Aborted(_) => {
+                WebDriverError::new(StatusCode::INTERNAL_SERVER_ERROR, "unknown error", message)
+            }
+        }
+    }
+}
+
+impl From<AuthError> for WebDriverError {
+    fn from(err: AuthError) -> Self {
+        let status = match err {
+            AuthError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AuthError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        };
+        WebDriverError::new(status, "unknown error", err.to_string())
+    }
+}
+
+impl From<actix_web::error::BlockingError> for WebDriverError {
+    fn from(err: actix_web::error::BlockingError) -> Self {
+        WebDriverError::new(StatusCode::INTERNAL_SERVER_ERROR, "unknown error", err.to_string())
+    }
+}
+
+impl std::fmt::Display for WebDriverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.error, self.message)
+    }
+}
+
+impl ResponseError for WebDriverError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(json!({
+            "value": {
+                "error": self.error,
+                "message": self.message,
+                "stacktrace": "",
+            }
+        }))
+    }
+}
+
+/// 会话的超时设置；桌面自动化没有页面和脚本，只保存下来供客户端读取
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeouts {
+    pub script: Option<u64>,
+    pub page_load: u64,
+    pub implicit: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            script: Some(30_000),
+            page_load: 300_000,
+            implicit: 0,
+        }
+    }
+}
+
+/// 单个 WebDriver 会话
+#[derive(Debug, Clone, Default)]
+struct Session {
+    /// 创建会话的调用方，其他调用方（admin 除外）看不到这个会话
+    owner: String,
+    /// 当前窗口，未选择时以整个屏幕为视口
+    window: Option<u32>,
+    timeouts: Timeouts,
+}
+
+/// 所有 WebDriver 会话，注册路由时需要 `web::Data<WebDriverSessions>`
+#[derive(Debug, Default)]
+pub struct WebDriverSessions {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl WebDriverSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// principal 的会话；其他调用方的会话与不存在的会话一样返回 invalid session id
+    fn get(&self, id: &str, principal: &Principal) -> Result<Session, WebDriverError> {
+        self.lock()
+            .get(id)
+            .filter(|session| principal.owns(Some(&session.owner)))
+            .cloned()
+            .ok_or_else(|| invalid_session(id))
+    }
+
+    fn update(&self, id: &str, principal: &Principal, f: impl FnOnce(&mut Session)) -> Result<(), WebDriverError> {
+        match self.lock().get_mut(id) {
+            Some(session) if principal.owns(Some(&session.owner)) => {
+                f(session);
+                Ok(())
+            }
+            _ => Err(invalid_session(id)),
+        }
+    }
+}
+
+fn invalid_session(id: &str) -> WebDriverError {
+    WebDriverError::new(StatusCode::NOT_FOUND, "invalid session id", format!("session {} does not exist", id))
+}
+
+/// 注册 WebDriver 路由（需要 `web::Data<ActionExecutor>`、`web::Data<JobQueue>` 和 `web::Data<WebDriverSessions>`）
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(server_status)
+        .service(new_session)
+        .service(delete_session)
+        .service(get_timeouts)
+        .service(set_timeouts)
+        .service(perform_actions)
+        .service(release_actions)
+        .service(take_screenshot)
+        .service(get_window_handle)
+        .service(get_window_handles)
+        .service(switch_to_window)
+        .service(close_window)
+        .service(get_window_rect)
+        .service(set_window_rect)
+        .service(minimize_window)
+        .service(get_title);
+}
+
+/// `POST /session/{id}/actions` 的请求体
+#[derive(Debug, Clone, Deserialize)]
+pub struct PerformActions {
+    pub actions: Vec<ActionSequence>,
+}
+
+/// 一个输入源的动作序列
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ActionSequence {
+    None { id: String, actions: Vec<NullAction> },
+    Key { id: String, actions: Vec<KeyAction> },
+    Pointer { id: String, actions: Vec<PointerAction> },
+    Wheel { id: String, actions: Vec<WheelAction> },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NullAction {
+    Pause { duration: Option<u64> },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum KeyAction {
+    Pause { duration: Option<u64> },
+    KeyDown { value: String },
+    KeyUp { value: String },
+}
+
+/// 指针动作；pointerType 为 touch 或 pen 时同样按鼠标处理
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PointerAction {
+    Pause { duration: Option<u64> },
+    PointerDown { button: u64 },
+    PointerUp { button: u64 },
+    PointerMove {
+        x: f64,
+        y: f64,
+        duration: Option<u64>,
+        origin: Option<PointerOrigin>,
+    },
+    PointerCancel,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum WheelAction {
+    Pause {
+        duration: Option<u64>,
+    },
+    Scroll {
+        x: i32,
+        y: i32,
+        delta_x: i32,
+        delta_y: i32,
+        duration: Option<u64>,
+        origin: Option<PointerOrigin>,
+    },
+}
+
+/// 坐标原点：`"viewport"`、`"pointer"` 或元素引用（不支持）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PointerOrigin {
+    Named(String),
+    Element(serde_json::Map<String, serde_json::Value>),
+}
+
+/// 每个滚轮刻度对应的像素数
+const PIXELS_PER_NOTCH: i32 = 100;
+
+impl PerformActions {
+    /// 按 W3C 的 tick 模型转换为动作：同一 tick 中各输入源的动作放入 `Parallel`，各 tick 依次执行
+    ///
+    /// cursor 为光标的当前位置，viewport 为视口在屏幕上的区域（当前窗口的客户区或整个屏幕）；
+    /// 没有需要执行的动作时返回 None
+    pub fn to_input_action(&self, cursor: (i32, i32), viewport: Rect) -> Result<Option<InputAction>, WebDriverError> {
+        let key_mapper = KeyMapper::new();
+        let mut pointers = vec![cursor; self.actions.len()];
+        let ticks = self.actions.iter().map(ActionSequence::len).max().unwrap_or(0);
+        let mut steps = Vec::new();
+        for tick in 0..ticks {
+            let mut branches = Vec::new();
+            for (source, sequence) in self.actions.iter().enumerate() {
+                let actions = match sequence {
+                    ActionSequence::None { actions, .. } => match actions.get(tick) {
+                        Some(NullAction::Pause { duration }) => pause(*duration),
+                        None => Vec::new(),
+                    },
+                    ActionSequence::Key { actions, .. } => match actions.get(tick) {
+                        Some(action) => key_action(&key_mapper, action)?,
+                        None => Vec::new(),
+                    },
+                    ActionSequence::Pointer { actions, .. } => match actions.get(tick) {
+                        Some(action) => pointer_action(action, &mut pointers[source], viewport)?,
+                        None => Vec::new(),
+                    },
+                    ActionSequence::Wheel { actions, .. } => match actions.get(tick) {
+                        Some(action) => wheel_action(action, viewport)?,
+                        None => Vec::new(),
+                    },
+                };
+                branches.extend(combine(actions, |actions| InputAction::Sequence { actions }));
+            }
+            steps.extend(combine(branches, |actions| InputAction::Parallel { actions }));
+        }
+        Ok(combine(steps, |actions| InputAction::Sequence { actions }))
+    }
+}
+
+impl ActionSequence {
+    fn len(&self) -> usize {
+        match self {
+            ActionSequence::None { actions, .. } => actions.len(),
+            ActionSequence::Key { actions, .. } => actions.len(),
+            ActionSequence::Pointer { actions, .. } => actions.len(),
+            ActionSequence::Wheel { actions, .. } => actions.len(),
+        }
+    }
+}
+
+/// 没有动作时为 None，只有一个时直接使用，否则用 wrap 组合
+fn combine(mut actions: Vec<InputAction>, wrap: impl FnOnce(Vec<InputAction>) -> InputAction) -> Option<InputAction> {
+    match actions.len() {
+        0 => None,
+        1 => actions.pop(),
+        _ => Some(wrap(actions)),
+    }
+}
+
+fn pause(duration: Option<u64>) -> Vec<InputAction> {
+    match duration {
+        Some(milliseconds) if milliseconds > 0 => vec![InputAction::Delay { milliseconds }],
+        _ => Vec::new(),
+    }
+}
+
+fn key_action(key_mapper: &KeyMapper, action: &KeyAction) -> Result<Vec<InputAction>, WebDriverError> {
+    let (value, down) = match action {
+        KeyAction::Pause { duration } => return Ok(pause(*duration)),
+        KeyAction::KeyDown { value } => (value, true),
+        KeyAction::KeyUp { value } => (value, false),
+    };
+    let mut chars = value.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return Err(WebDriverError::invalid_argument(format!("key value {:?} must be a single character", value)));
+    };
+    let (key, location) = match w3c_key(c) {
+        Some(key) => key,
+        None if ('\u{E000}'..='\u{F8FF}').contains(&c) => {
+            return Err(WebDriverError::invalid_argument(format!("unsupported key \\u{:04X}", c as u32)));
+        }
+        // 大写字母和需要 Shift 的符号没有对应的按键，按下时直接输入字符，释放时忽略
+        None => match key_mapper.key_for_char(c) {
+            Some(key) if !c.is_uppercase() => (key, None),
+            _ if down => {
+                return Ok(vec![InputAction::TypeText {
+                    text: c.to_string(),
+                    char_delay_ms: None,
+                    target: None,
+                }]);
+            }
+            _ => return Ok(Vec::new()),
+        },
+    };
+    Ok(vec![if down {
+        InputAction::KeyDown { key, location, target: None }
+    } else {
+        InputAction::KeyUp { key, location, target: None }
+    }])
+}
+
+/// W3C 规范中 `\u{E000}` 起的特殊键码
+fn w3c_key(c: char) -> Option<(Key, Option<KeyLocation>)> {
+    let key = match c {
+        '\u{E003}' => Key::Backspace,
+        '\u{E004}' => Key::Tab,
+        '\u{E006}' | '\u{E007}' => Key::Enter,
+        '\u{E008}' => Key::Shift,
+        '\u{E009}' => Key::Ctrl,
+        '\u{E00A}' => Key::Alt,
+        '\u{E00C}' => Key::Escape,
+        '\u{E00D}' => Key::Space,
+        '\u{E00E}' => Key::PageUp,
+        '\u{E00F}' => Key::PageDown,
+        '\u{E010}' => Key::End,
+        '\u{E011}' => Key::Home,
+        '\u{E012}' => Key::ArrowLeft,
+        '\u{E013}' => Key::ArrowUp,
+        '\u{E014}' => Key::ArrowRight,
+        '\u{E015}' => Key::ArrowDown,
+        '\u{E016}' => Key::Insert,
+        '\u{E017}' => Key::Delete,
+        '\u{E018}' => Key::Semicolon,
+        '\u{E019}' => Key::Equal,
+        '\u{E01A}' => Key::Numpad0,
+        '\u{E01B}' => Key::Numpad1,
+        '\u{E01C}' => Key::Numpad2,
+        '\u{E01D}' => Key::Numpad3,
+        '\u{E01E}' => Key::Numpad4,
+        '\u{E01F}' => Key::Numpad5,
+        '\u{E020}' => Key::Numpad6,
+        '\u{E021}' => Key::Numpad7,
+        '\u{E022}' => Key::Numpad8,
+        '\u{E023}' => Key::Numpad9,
+        '\u{E024}' => Key::NumpadMultiply,
+        '\u{E025}' => Key::NumpadAdd,
+        '\u{E027}' => Key::NumpadSubtract,
+        '\u{E028}' => Key::NumpadDecimal,
+        '\u{E029}' => Key::NumpadDivide,
+        '\u{E031}' => Key::F1,
+        '\u{E032}' => Key::F2,
+        '\u{E033}' => Key::F3,
+        '\u{E034}' => Key::F4,
+        '\u{E035}' => Key::F5,
+        '\u{E036}' => Key::F6,
+        '\u{E037}' => Key::F7,
+        '\u{E038}' => Key::F8,
+        '\u{E039}' => Key::F9,
+        '\u{E03A}' => Key::F10,
+        '\u{E03B}' => Key::F11,
+        '\u{E03C}' => Key::F12,
+        '\u{E03D}' => Key::Meta,
+        '\u{E050}' => return Some((Key::Shift, Some(KeyLocation::Right))),
+        '\u{E051}' => return Some((Key::Ctrl, Some(KeyLocation::Right))),
+        '\u{E052}' => return Some((Key::Alt, Some(KeyLocation::Right))),
+        '\u{E053}' => return Some((Key::Meta, Some(KeyLocation::Right))),
+        '\u{E054}' => return Some((Key::PageUp, Some(KeyLocation::Numpad))),
+        '\u{E055}' => return Some((Key::PageDown, Some(KeyLocation::Numpad))),
+        '\u{E056}' => return Some((Key::End, Some(KeyLocation::Numpad))),
+        '\u{E057}' => return Some((Key::Home, Some(KeyLocation::Numpad))),
+        '\u{E058}' => return Some((Key::ArrowLeft, Some(KeyLocation::Numpad))),
+        '\u{E059}' => return Some((Key::ArrowUp, Some(KeyLocation::Numpad))),
+        '\u{E05A}' => return Some((Key::ArrowRight, Some(KeyLocation::Numpad))),
+        '\u{E05B}' => return Some((Key::ArrowDown, Some(KeyLocation::Numpad))),
+        '\u{E05C}' => return Some((Key::Insert, Some(KeyLocation::Numpad))),
+        '\u{E05D}' => return Some((Key::Delete, Some(KeyLocation::Numpad))),
+        _ => return None,
+    };
+    Some((key, None))
+}
+
+fn pointer_button(button: u64) -> Result<MouseButton, WebDriverError> {
+    Ok(match button {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        3 => MouseButton::Back,
+        4 => MouseButton::Forward,
+        _ => return Err(WebDriverError::invalid_argument(format!("unsupported pointer button {}", button))),
+    })
+}
+
+fn pointer_action(action: &PointerAction, pointer: &mut (i32, i32), viewport: Rect) -> Result<Vec<InputAction>, WebDriverError> {
+    Ok(match action {
+        PointerAction::Pause { duration } => pause(*duration),
+        PointerAction::PointerDown { button } => vec![InputAction::MouseDown {
+            button: pointer_button(*button)?,
+            target: None,
+        }],
+        PointerAction::PointerUp { button } => vec![InputAction::MouseUp {
+            button: pointer_button(*button)?,
+            target: None,
+        }],
+        PointerAction::PointerMove { x, y, duration, origin } => {
+            let (dx, dy) = (x.round() as i32, y.round() as i32);
+            let (start_x, start_y) = *pointer;
+            let (end_x, end_y) = match origin_name(origin.as_ref())? {
+                "viewport" => offset((viewport.x, viewport.y), dx, dy, viewport)?,
+                "pointer" => offset((start_x, start_y), dx, dy, viewport)?,
+                name => return Err(WebDriverError::invalid_argument(format!("unknown origin {:?}", name))),
+            };
+            *pointer = (end_x, end_y);
+            let duration = duration.unwrap_or(0);
+            if duration == 0 {
+                return Ok(vec![mouse_move(end_x, end_y)]);
+            }
+            // 分成若干步移动，每步之后等待，总耗时等于 duration
+            let steps = (duration / 10).clamp(1, 20) as i64;
+            let mut actions = Vec::new();
+            for step in 1..=steps {
+                let x = start_x as i64 + (end_x - start_x) as i64 * step / steps;
+                let y = start_y as i64 + (end_y - start_y) as i64 * step / steps;
+                actions.push(mouse_move(x as i32, y as i32));
+                let elapsed = duration * step as u64 / steps as u64 - duration * (step - 1) as u64 / steps as u64;
+                actions.extend(pause(Some(elapsed)));
+            }
+            actions
+        }
+        PointerAction::PointerCancel => Vec::new(),
+    })
+}
+
+fn wheel_action(action: &WheelAction, viewport: Rect) -> Result<Vec<InputAction>, WebDriverError> {
+    let (x, y, delta_x, delta_y, duration, origin) = match action {
+        WheelAction::Pause { duration } => return Ok(pause(*duration)),
+        WheelAction::Scroll { x, y, delta_x, delta_y, duration, origin } => (x, y, delta_x, delta_y, duration, origin),
+    };
+    match origin_name(origin.as_ref())? {
+        "viewport" => {}
+        name => return Err(WebDriverError::invalid_argument(format!("wheel origin {:?} is not supported", name))),
+    }
+    let (x, y) = offset((viewport.x, viewport.y), *x, *y, viewport)?;
+    let mut actions = vec![
+        mouse_move(x, y),
+        InputAction::MouseScroll {
+            delta_x: notches(*delta_x),
+            delta_y: notches(*delta_y),
+            target: None,
+        },
+    ];
+    actions.extend(pause(*duration));
+    Ok(actions)
+}
+
+fn origin_name(origin: Option<&PointerOrigin>) -> Result<&str, WebDriverError> {
+    match origin {
+        None => Ok("viewport"),
+        Some(PointerOrigin::Named(name)) => Ok(name),
+        Some(PointerOrigin::Element(_)) => Err(WebDriverError::new(
+            StatusCode::NOT_FOUND,
+            "no such element",
+            "element origins are not supported",
+        )),
+    }
+}
+
+/// 从 origin 偏移 (dx, dy) 后的位置，必须在视口内
+fn offset(origin: (i32, i32), dx: i32, dy: i32, viewport: Rect) -> Result<(i32, i32), WebDriverError> {
+    let out_of_bounds = || {
+        WebDriverError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "move target out of bounds",
+            format!("{:?} offset by ({}, {}) is outside the viewport {:?}", origin, dx, dy, viewport),
+        )
+    };
+    let (Some(x), Some(y)) = (origin.0.checked_add(dx), origin.1.checked_add(dy)) else {
+        return Err(out_of_bounds());
+    };
+    let inside = x >= viewport.x
+        && y >= viewport.y
+        && (x as i64) < viewport.x as i64 + viewport.width as i64
+        && (y as i64) < viewport.y as i64 + viewport.height as i64;
+    if inside {
+        Ok((x, y))
+    } else {
+        Err(out_of_bounds())
+    }
+}
+
+/// 像素转换为滚轮刻度，不足一格的非零值按一格计算
+fn notches(pixels: i32) -> i32 {
+    match pixels / PIXELS_PER_NOTCH {
+        0 => pixels.signum(),
+        n => n,
+    }
+}
+
+fn mouse_move(x: i32, y: i32) -> InputAction {
+    InputAction::MouseMove {
+        x,
+        y,
+        origin: None,
+        target: None,
+    }
+}
+
+/// 窗口句柄为十六进制的 X11 窗口 ID，解析时也接受十进制
+fn format_handle(id: u32) -> String {
+    format!("0x{:08x}", id)
+}
+
+fn parse_handle(handle: &str) -> Result<u32, WebDriverError> {
+    let parsed = match handle.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => handle.parse(),
+    };
+    parsed.map_err(|_| WebDriverError::no_such_window(format!("invalid window handle {:?}", handle)))
+}
+
+/// 解析请求体，格式错误时返回 invalid argument
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, WebDriverError> {
+    serde_json::from_slice(body).map_err(|err| WebDriverError::invalid_argument(err.to_string()))
+}
+
+fn value(value: impl Serialize) -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "value": value }))
+}
+
+fn rect_value(rect: Rect) -> serde_json::Value {
+    json!({
+        "x": rect.x,
+        "y": rect.y,
+        "width": rect.width,
+        "height": rect.height,
+    })
+}
+
+/// 执行动作，事件转发到任务队列的事件中心
+async fn execute(
+    principal: &Principal,
+    executor: &ActionExecutor,
+    jobs: &JobQueue,
+    action: InputAction,
+) -> Result<(), WebDriverError> {
+    info!("WebDriver action from {}: {}", principal.name, action.name());
+    principal.permissions.check(&action)?;
+    executor.execute_observed(&action, jobs.events().forward(None)).await?;
+    Ok(())
+}
+
+/// 会话当前选择的窗口
+async fn current_window(session: &Session) -> Result<WindowInfo, WebDriverError> {
+    let id = session
+        .window
+        .ok_or_else(|| WebDriverError::no_such_window("no window is selected in this session"))?;
+    web::block(move || find_window(id))
+        .await??
+        .ok_or_else(|| WebDriverError::no_such_window(format!("window {} has been closed", format_handle(id))))
+}
+
+/// 视口：当前窗口的客户区，未选择窗口时为整个屏幕
+async fn viewport(session: &Session, executor: &ActionExecutor) -> Result<Rect, WebDriverError> {
+    if session.window.is_some() {
+        return Ok(current_window(session).await?.geometry);
+    }
+    let (width, height) = executor.get_screen_size();
+    Ok(Rect::new(0, 0, width, height))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewSession {
+    #[serde(default)]
+    capabilities: Capabilities,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Capabilities {
+    always_match: serde_json::Map<String, serde_json::Value>,
+    first_match: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwitchWindow {
+    handle: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetTimeouts {
+    script: Option<u64>,
+    page_load: Option<u64>,
+    implicit: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SetRect {
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+// 服务状态
+#[get("/status")]
+async fn server_status() -> HttpResponse {
+    value(json!({
+        "ready": true,
+        "message": "automation WebDriver endpoint",
+    }))
+}
+
+// 新建会话；`automation:window` 能力可以选择初始窗口，否则以整个桌面为视口
+#[post("/session")]
+async fn new_session(
+    principal: Principal,
+    body: web::Bytes,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let request: NewSession = if body.is_empty() { NewSession::default() } else { parse(&body)? };
+    let capabilities = &request.capabilities;
+    let selector = capabilities
+        .always_match
+        .get(WINDOW_CAPABILITY)
+        .or_else(|| capabilities.first_match.iter().find_map(|first| first.get(WINDOW_CAPABILITY)))
+        .map(|selector| serde_json::from_value::<WindowSelector>(selector.clone()))
+        .transpose()
+        .map_err(|err| WebDriverError::invalid_argument(format!("{}: {}", WINDOW_CAPABILITY, err)))?;
+    let window = match selector {
+        Some(selector) => {
+            principal.permissions.check_capture()?;
+            let windows = web::block(move || find_windows(&selector)).await??;
+            let window = windows.first().ok_or_else(|| {
+                WebDriverError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "session not created",
+                    "no window matches automation:window",
+                )
+            })?;
+            Some(window.id)
+        }
+        None => None,
+    };
+
+    let id = format!("{:032x}", rand::random::<u128>());
+    sessions.lock().insert(
+        id.clone(),
+        Session {
+            owner: principal.name.clone(),
+            window,
+            ..Default::default()
+        },
+    );
+    info!("WebDriver session {} created by {}", id, principal.name);
+    Ok(value(json!({
+        "sessionId": id,
+        "capabilities": {
+            "browserName": "desktop",
+            "browserVersion": env!("CARGO_PKG_VERSION"),
+            "platformName": std::env::consts::OS,
+            "setWindowRect": true,
+            "timeouts": Timeouts::default(),
+            WINDOW_CAPABILITY: window.map(format_handle),
+        },
+    })))
+}
+
+// 删除会话（不会关闭窗口）
+#[delete("/session/{id}")]
+async fn delete_session(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    sessions.get(&id, &principal)?;
+    sessions.lock().remove(id.as_str());
+    info!("WebDriver session {} deleted", id);
+    Ok(value(()))
+}
+
+#[get("/session/{id}/timeouts")]
+async fn get_timeouts(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    Ok(value(sessions.get(&id, &principal)?.timeouts))
+}
+
+#[post("/session/{id}/timeouts")]
+async fn set_timeouts(
+    id: web::Path<String>,
+    principal: Principal,
+    body: web::Bytes,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let update: SetTimeouts = parse(&body)?;
+    sessions.update(&id, &principal, |session| {
+        if update.script.is_some() {
+            session.timeouts.script = update.script;
+        }
+        if let Some(page_load) = update.page_load {
+            session.timeouts.page_load = page_load;
+        }
+        if let Some(implicit) = update.implicit {
+            session.timeouts.implicit = implicit;
+        }
+    })?;
+    Ok(value(()))
+}
+
+// 执行 W3C Actions，全部完成后返回
+#[post("/session/{id}/actions")]
+async fn perform_actions(
+    id: web::Path<String>,
+    principal: Principal,
+    body: web::Bytes,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let session = sessions.get(&id, &principal)?;
+    let actions: PerformActions = parse(&body)?;
+    let viewport = viewport(&session, &executor).await?;
+    // 只有指针输入源需要光标位置
+    let cursor = if actions.actions.iter().any(|sequence| matches!(sequence, ActionSequence::Pointer { .. })) {
+        executor.cursor_position().await?
+    } else {
+        (0, 0)
+    };
+    if let Some(action) = actions.to_input_action(cursor, viewport)? {
+        execute(&principal, &executor, &jobs, action).await?;
+    }
+    Ok(value(()))
+}
+
+// 释放仍被按住的键和鼠标按键（与 /release-all 相同，需要 admin 权限）
+#[delete("/session/{id}/actions")]
+async fn release_actions(
+    id: web::Path<String>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    sessions.get(&id, &principal)?;
+    principal.permissions.check_admin()?;
+    let released = executor.release_all().await?;
+    info!("WebDriver released held inputs: {:?}", released);
+    Ok(value(()))
+}
+
+// 截取当前窗口（未选择窗口时为整个屏幕），返回 base64 编码的 PNG
+#[get("/session/{id}/screenshot")]
+async fn take_screenshot(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    principal.permissions.check_capture()?;
+    let session = sessions.get(&id, &principal)?;
+    let region = match session.window {
+        Some(_) => Some(current_window(&session).await?.geometry),
+        None => None,
+    };
+    let png = web::block(move || capture_png(region)).await??;
+    Ok(value(base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+#[get("/session/{id}/window")]
+async fn get_window_handle(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    principal.permissions.check_capture()?;
+    let window = current_window(&sessions.get(&id, &principal)?).await?;
+    Ok(value(format_handle(window.id)))
+}
+
+// 所有顶层窗口的句柄，最上层的窗口在前
+#[get("/session/{id}/window/handles")]
+async fn get_window_handles(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    principal.permissions.check_capture()?;
+    sessions.get(&id, &principal)?;
+    let windows = web::block(list_windows).await??;
+    Ok(value(windows.iter().map(|window| format_handle(window.id)).collect::<Vec<_>>()))
+}
+
+// 切换到指定窗口并激活它
+#[post("/session/{id}/window")]
+async fn switch_to_window(
+    id: web::Path<String>,
+    principal: Principal,
+    body: web::Bytes,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    sessions.get(&id, &principal)?;
+    let request: SwitchWindow = parse(&body)?;
+    let window = parse_handle(&request.handle)?;
+    if web::block(move || find_window(window)).await??.is_none() {
+        return Err(WebDriverError::no_such_window(format!("window {} does not exist", request.handle)));
+    }
+    execute(
+        &principal,
+        &executor,
+        &jobs,
+        InputAction::FocusWindow {
+            window: WindowSelector::id(window),
+        },
+    )
+    .await?;
+    sessions.update(&id, &principal, |session| session.window = Some(window))?;
+    Ok(value(()))
+}
+
+// 请求关闭当前窗口，返回剩余窗口的句柄
+#[delete("/session/{id}/window")]
+async fn close_window(
+    id: web::Path<String>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let window = current_window(&sessions.get(&id, &principal)?).await?;
+    execute(
+        &principal,
+        &executor,
+        &jobs,
+        InputAction::CloseWindow {
+            window: WindowSelector::id(window.id),
+        },
+    )
+    .await?;
+    sessions.update(&id, &principal, |session| session.window = None)?;
+    let windows = web::block(list_windows).await??;
+    Ok(value(
+        windows
+            .iter()
+            .filter(|other| other.id != window.id)
+            .map(|other| format_handle(other.id))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[get("/session/{id}/window/rect")]
+async fn get_window_rect(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    principal.permissions.check_capture()?;
+    let window = current_window(&sessions.get(&id, &principal)?).await?;
+    Ok(value(rect_value(window.geometry)))
+}
+
+// 移动和/或调整当前窗口，返回新的位置和尺寸
+#[post("/session/{id}/window/rect")]
+async fn set_window_rect(
+    id: web::Path<String>,
+    principal: Principal,
+    body: web::Bytes,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let session = sessions.get(&id, &principal)?;
+    let request: SetRect = parse(&body)?;
+    let window = current_window(&session).await?;
+    let selector = WindowSelector::id(window.id);
+    match (request.width, request.height) {
+        (Some(width), Some(height)) => {
+            execute(&principal, &executor, &jobs, InputAction::ResizeWindow { window: selector.clone(), width, height }).await?
+        }
+        (None, None) => {}
+        _ => return Err(WebDriverError::invalid_argument("width and height must be given together")),
+    }
+    match (request.x, request.y) {
+        (Some(x), Some(y)) => execute(&principal, &executor, &jobs, InputAction::MoveWindow { window: selector, x, y }).await?,
+        (None, None) => {}
+        _ => return Err(WebDriverError::invalid_argument("x and y must be given together")),
+    }
+    Ok(value(rect_value(current_window(&session).await?.geometry)))
+}
+
+#[post("/session/{id}/window/minimize")]
+async fn minimize_window(
+    id: web::Path<String>,
+    principal: Principal,
+    executor: web::Data<ActionExecutor>,
+    jobs: web::Data<JobQueue>,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    let session = sessions.get(&id, &principal)?;
+    let window = current_window(&session).await?;
+    execute(
+        &principal,
+        &executor,
+        &jobs,
+        InputAction::MinimizeWindow {
+            window: WindowSelector::id(window.id),
+        },
+    )
+    .await?;
+    Ok(value(rect_value(window.geometry)))
+}
+
+#[get("/session/{id}/title")]
+async fn get_title(
+    id: web::Path<String>,
+    principal: Principal,
+    sessions: web::Data<WebDriverSessions>,
+) -> Result<HttpResponse, WebDriverError> {
+    principal.permissions.check_capture()?;
+    let window = current_window(&sessions.get(&id, &principal)?).await?;
+    Ok(value(window.title))
+}
+
+#[cfg(target_os = "linux")]
+fn list_windows() -> Result<Vec<WindowInfo>, AutomationError> {
+    crate::window::list_windows()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_windows() -> Result<Vec<WindowInfo>, AutomationError> {
+    Err(AutomationError::Platform(
+        "window listing is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn find_windows(selector: &WindowSelector) -> Result<Vec<WindowInfo>, AutomationError> {
+    crate::window::find_windows(selector)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_windows(_selector: &WindowSelector) -> Result<Vec<WindowInfo>, AutomationError> {
+    Err(AutomationError::Platform(
+        "window lookup is only supported on X11".to_string(),
+    ))
+}
+
+fn find_window(id: u32) -> Result<Option<WindowInfo>, AutomationError> {
+    Ok(find_windows(&WindowSelector::id(id))?.into_iter().next())
+}
+
+#[cfg(target_os = "linux")]
+fn capture_png(region: Option<Rect>) -> Result<Vec<u8>, AutomationError> {
+    let image = match region {
+        Some(region) => crate::screen::capture_region(region)?,
+        None => crate::screen::capture_screen()?,
+    };
+    crate::screen::encode_png(&image)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture_png(_region: Option<Rect>) -> Result<Vec<u8>, AutomationError> {
+    Err(AutomationError::Platform(
+        "screen capture is only supported on X11".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::test::{self as http, TestRequest};
+    use actix_web::{App, HttpMessage};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::auth::Permissions;
+    use crate::jobs::JobQueueOptions;
+    use crate::simulator::InputSimulator;
+
+    const SCREEN: Rect = Rect { x: 0, y: 0, width: 800, height: 600 };
+
+    fn translate(actions: Value, cursor: (i32, i32), viewport: Rect) -> Result<Option<Value>, WebDriverError> {
+        let actions: PerformActions = serde_json::from_value(json!({ "actions": actions })).unwrap();
+        let action = actions.to_input_action(cursor, viewport)?;
+        Ok(action.map(|action| serde_json::to_value(action).unwrap()))
+    }
+
+    /// 规范化为序列化后的形式，省略的可选字段变为 null
+    fn expected(value: Value) -> Value {
+        serde_json::to_value(serde_json::from_value::<InputAction>(value).unwrap()).unwrap()
+    }
+
+    fn pointer(actions: Value) -> Value {
+        json!([{"type": "pointer", "id": "mouse", "actions": actions}])
+    }
+
+    #[test]
+    fn groups_each_tick_into_parallel_steps() {
+        let actions = json!([
+            {"type": "key", "id": "keyboard", "actions": [
+                {"type": "keyDown", "value": "a"},
+                {"type": "keyUp", "value": "a"}
+            ]},
+            {"type": "pointer", "id": "mouse", "actions": [
+                {"type": "pointerMove", "x": 10, "y": 20},
+                {"type": "pointerDown", "button": 0},
+                {"type": "pointerUp", "button": 0}
+            ]},
+            {"type": "none", "id": "idle", "actions": [{"type": "pause"}]}
+        ]);
+        assert_eq!(
+            translate(actions, (0, 0), SCREEN).unwrap(),
+            Some(expected(json!({"type": "Sequence", "params": {"actions": [
+                {"type": "Parallel", "params": {"actions": [
+                    {"type": "KeyDown", "params": {"key": "A"}},
+                    {"type": "MouseMove", "params": {"x": 10, "y": 20}}
+                ]}},
+                {"type": "Parallel", "params": {"actions": [
+                    {"type": "KeyUp", "params": {"key": "A"}},
+                    {"type": "MouseDown", "params": {"button": "Left"}}
+                ]}},
+                {"type": "MouseUp", "params": {"button": "Left"}}
+            ]}})))
+        );
+        let idle = json!([{"type": "none", "id": "idle", "actions": [{"type": "pause", "duration": 0}]}]);
+        assert_eq!(translate(idle, (0, 0), SCREEN).unwrap(), None);
+    }
+
+    #[test]
+    fn splits_timed_moves_into_steps() {
+        let actions = pointer(json!([{"type": "pointerMove", "x": 100, "y": 50, "duration": 40}]));
+        let move_to = |x, y| json!({"type": "MouseMove", "params": {"x": x, "y": y}});
+        let delay = json!({"type": "Delay", "params": {"milliseconds": 10}});
+        assert_eq!(
+            translate(actions, (0, 10), SCREEN).unwrap(),
+            Some(expected(json!({"type": "Sequence", "params": {"actions": [
+                move_to(25, 20), delay, move_to(50, 30), delay, move_to(75, 40), delay, move_to(100, 50), delay
+            ]}})))
+        );
+    }
+
+    #[test]
+    fn resolves_viewport_and_pointer_origins() {
+        let viewport = Rect::new(100, 100, 200, 200);
+        let moved = |origin: &str, x: i32, y: i32| {
+            let actions = pointer(json!([{"type": "pointerMove", "x": x, "y": y, "origin": origin}]));
+            translate(actions, (150, 150), viewport)
+        };
+        assert_eq!(
+            moved("viewport", 10, 20).unwrap(),
+            Some(expected(json!({"type": "MouseMove", "params": {"x": 110, "y": 120}})))
+        );
+        assert_eq!(
+            moved("pointer", -10, 20).unwrap(),
+            Some(expected(json!({"type": "MouseMove", "params": {"x": 140, "y": 170}})))
+        );
+        assert_eq!(moved("viewport", 200, 0).unwrap_err().error, "move target out of bounds");
+        assert_eq!(moved("pointer", 0, -51).unwrap_err().error, "move target out of bounds");
+        assert_eq!(moved("window", 0, 0).unwrap_err().error, "invalid argument");
+    }
+
+    #[test]
+    fn huge_offsets_are_out_of_bounds() {
+        let viewport = Rect::new(100, 100, 200, 200);
+        for origin in ["viewport", "pointer"] {
+            let actions = pointer(json!([{"type": "pointerMove", "x": 1e12, "y": 0, "origin": origin}]));
+            assert_eq!(translate(actions, (150, 150), viewport).unwrap_err().error, "move target out of bounds");
+        }
+        let wheel = json!([{"type": "wheel", "id": "wheel", "actions": [
+            {"type": "scroll", "x": i32::MAX, "y": 0, "deltaX": 0, "deltaY": 100}
+        ]}]);
+        assert_eq!(translate(wheel, (0, 0), viewport).unwrap_err().error, "move target out of bounds");
+    }
+
+    #[test]
+    fn uppercase_keys_fall_back_to_typed_text() {
+        let keys = |actions: Value| translate(json!([{"type": "key", "id": "keyboard", "actions": actions}]), (0, 0), SCREEN);
+        assert_eq!(
+            keys(json!([{"type": "keyDown", "value": "A"}, {"type": "keyUp", "value": "A"}])).unwrap(),
+            Some(expected(json!({"type": "TypeText", "params": {"text": "A"}})))
+        );
+        assert_eq!(
+            keys(json!([{"type": "keyDown", "value": "\u{E051}"}])).unwrap(),
+            Some(expected(json!({"type": "KeyDown", "params": {"key": "Ctrl", "location": "Right"}})))
+        );
+        assert_eq!(keys(json!([{"type": "keyDown", "value": "ab"}])).unwrap_err().error, "invalid argument");
+        assert_eq!(keys(json!([{"type": "keyDown", "value": "\u{E001}"}])).unwrap_err().error, "invalid argument");
+    }
+
+    #[test]
+    fn scroll_deltas_round_to_notches() {
+        assert_eq!(notches(0), 0);
+        assert_eq!(notches(30), 1);
+        assert_eq!(notches(-30), -1);
+        assert_eq!(notches(250), 2);
+        assert_eq!(notches(-250), -2);
+        let wheel = json!([{"type": "wheel", "id": "wheel", "actions": [
+            {"type": "scroll", "x": 5, "y": 6, "deltaX": -40, "deltaY": 300}
+        ]}]);
+        assert_eq!(
+            translate(wheel, (0, 0), SCREEN).unwrap(),
+            Some(expected(json!({"type": "Sequence", "params": {"actions": [
+                {"type": "MouseMove", "params": {"x": 5, "y": 6}},
+                {"type": "MouseScroll", "params": {"delta_x": -1, "delta_y": 3}}
+            ]}})))
+        );
+    }
+
+    #[actix_web::test]
+    async fn sessions_round_trip_and_belong_to_their_creator() {
+        let executor = Arc::new(ActionExecutor::with_simulator(InputSimulator::detached((800, 600))));
+        let jobs = JobQueue::start(executor.clone(), JobQueueOptions::default());
+        let app = http::init_service(
+            App::new()
+                .app_data(web::Data::from(executor))
+                .app_data(web::Data::new(jobs))
+                .app_data(web::Data::new(WebDriverSessions::new()))
+                .configure(routes),
+        )
+        .await;
+        let user = |name: &str| {
+            let mut principal = Principal::anonymous(name);
+            principal.permissions = Arc::new(Permissions { admin: false, ..Permissions::default() });
+            principal
+        };
+        let call = |request: TestRequest, principal: Principal| {
+            let app = &app;
+            async move {
+                let request = request.to_request();
+                request.extensions_mut().insert(principal);
+                let response = http::call_service(app, request).await;
+                let status = response.status();
+                let body: Value = http::read_body_json(response).await;
+                (status, body)
+            }
+        };
+
+        let (status, body) = call(TestRequest::post().uri("/session"), user("alice")).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = body["value"]["sessionId"].as_str().unwrap().to_string();
+        let actions = format!("/session/{}/actions", id);
+        let pause = json!({"actions": [{"type": "none", "id": "idle", "actions": [{"type": "pause"}]}]});
+        let (status, _) = call(TestRequest::post().uri(&actions).set_json(&pause), user("alice")).await;
+        assert_eq!(status, StatusCode::OK);
+        // 动作已转换并交给执行器，分离的模拟器没有输入设备
+        let press = json!({"actions": [{"type": "key", "id": "keyboard", "actions": [{"type": "keyDown", "value": "a"}]}]});
+        let (status, body) = call(TestRequest::post().uri(&actions).set_json(&press), user("alice")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["value"]["message"].as_str().unwrap().contains("no input device"), "{}", body);
+
+        let (status, body) = call(TestRequest::post().uri(&actions).set_json(&pause), user("bob")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["value"]["error"], "invalid session id");
+        let (status, _) = call(TestRequest::delete().uri(&format!("/session/{}", id)), user("bob")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(TestRequest::get().uri(&format!("/session/{}/timeouts", id)), Principal::anonymous("root")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let mut blind = user("carol");
+        blind.permissions = Arc::new(Permissions { capture: false, admin: false, ..Permissions::default() });
+        let (status, _) = call(TestRequest::get().uri(&format!("/session/{}/window", id)), blind.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let window = json!({"capabilities": {"alwaysMatch": {WINDOW_CAPABILITY: {"title": "x"}}}});
+        let (status, _) = call(TestRequest::post().uri("/session").set_json(&window), blind).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = call(TestRequest::delete().uri(&format!("/session/{}", id)), user("alice")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(TestRequest::post().uri(&actions).set_json(&pause), user("alice")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,985 @@
+use std::future::Future;
+
+use enigo::{Axis, Button, Coordinate, Direction, InputError, InputResult, Key as EnigoKey, Keyboard, Mouse};
+use image::RgbaImage;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+use crate::accessibility::{ElementCommand, ElementInfo, ElementSelector};
+use crate::backend::InputBackend;
+use crate::error::AutomationError;
+use crate::screen::Rect;
+
+/// 窗口状态
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct WindowState {
+    /// 当前激活（拥有焦点）的窗口
+    pub active: bool,
+    /// 已最小化或被隐藏
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+/// 窗口信息
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct WindowInfo {
+    /// 平台窗口 ID（X11 window）
+    pub id: u32,
+    pub title: String,
+    /// 窗口类名（X11 WM_CLASS 的 class 部分）
+    pub class: String,
+    /// 窗口类实例名（X11 WM_CLASS 的 instance 部分）
+    pub instance: String,
+    pub pid: Option<u32>,
+    /// 客户区在屏幕上的位置和尺寸（不含窗口管理器的边框）
+    pub geometry: Rect,
+    /// 所在的虚拟桌面
+    pub desktop: Option<u32>,
+    pub state: WindowState,
+}
+
+/// 窗口选择条件，所有给出的条件都需满足
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct WindowSelector {
+    /// 精确匹配窗口 ID
+    pub id: Option<u32>,
+    /// 标题包含该文本（忽略大小写）
+    pub title: Option<String>,
+    /// 类名或实例名等于该文本（忽略大小写）
+    pub class: Option<String>,
+    /// 所属进程 ID
+    pub pid: Option<u32>,
+}
+
+impl WindowSelector {
+    /// 按窗口 ID 查找
+    pub fn id(id: u32) -> Self {
+        WindowSelector {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+
+    /// 按标题查找
+    pub fn title(title: impl Into<String>) -> Self {
+        WindowSelector {
+            title: Some(title.into()),
+            ..Default::default()
+        }
+    }
+
+    /// 按类名查找
+    pub fn class(class: impl Into<String>) -> Self {
+        WindowSelector {
+            class: Some(class.into()),
+            ..Default::default()
+        }
+    }
+
+    /// 是否没有给出任何条件
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.title.is_none() && self.class.is_none() && self.pid.is_none()
+    }
+
+    /// 判断窗口是否满足条件
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        self.id.is_none_or(|id| id == window.id)
+            && self.pid.is_none_or(|pid| window.pid == Some(pid))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| window.title.to_lowercase().contains(&title.to_lowercase()))
+            && self.class.as_ref().is_none_or(|class| {
+                window.class.eq_ignore_ascii_case(class) || window.instance.eq_ignore_ascii_case(class)
+            })
+    }
+}
+
+/// 对窗口执行的操作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(tag = "type", content = "params")]
+pub enum WindowCommand {
+    /// 激活窗口并移到最前（最小化的窗口会被恢复）
+    Focus,
+    /// 将窗口左上角移动到屏幕坐标 (x, y)
+    Move { x: i32, y: i32 },
+    /// 调整客户区尺寸
+    Resize { width: u32, height: u32 },
+    Minimize,
+    /// 请求窗口关闭（与点击关闭按钮相同，应用可以拒绝）
+    Close,
+}
+
+/// 键盘鼠标事件投递到目标窗口的方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub enum InputDelivery {
+    /// 通过 XSendEvent 直接发送给窗口，不改变焦点和真实光标（部分应用会忽略合成事件）
+    #[default]
+    SendEvent,
+    /// 临时激活目标窗口，通过 XTest 注入后恢复原来的焦点窗口（兼容性最好，但会短暂抢占焦点）
+    Focus,
+}
+
+/// 键盘鼠标操作的目标窗口
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct InputTarget {
+    pub window: WindowSelector,
+    #[serde(default)]
+    pub delivery: InputDelivery,
+}
+
+/// 直接发送给窗口的底层事件（坐标为屏幕坐标）
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum WindowEvent {
+    Key { key: EnigoKey, direction: Direction },
+    RawKey { keycode: u16, direction: Direction },
+    Button { button: Button, direction: Direction, x: i32, y: i32 },
+    Motion { x: i32, y: i32 },
+}
+
+/// 列出所有顶层窗口，最上层的窗口在前
+#[cfg(target_os = "linux")]
+pub fn list_windows() -> Result<Vec<WindowInfo>, AutomationError> {
+    WindowManager::connect(None)?.list()
+}
+
+/// 查找满足条件的窗口，最上层的窗口在前
+#[cfg(target_os = "linux")]
+pub fn find_windows(selector: &WindowSelector) -> Result<Vec<WindowInfo>, AutomationError> {
+    WindowManager::connect(None)?.find(selector)
+}
+
+/// 进程内共享的事件发送器，修饰键状态在多次调用之间保持
+#[cfg(target_os = "linux")]
+static SENDER: std::sync::Mutex<Option<EventSender>> = std::sync::Mutex::new(None);
+
+/// 通过 XSendEvent 将事件直接发送给窗口
+#[cfg(target_os = "linux")]
+pub fn send_window_event(window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+    let mut guard = SENDER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let sender = match guard.take() {
+        Some(sender) => sender,
+        None => EventSender::connect(None)?,
+    };
+    guard.insert(sender).send(window, event)
+}
+
+/// 将键盘鼠标事件改为直接发送给指定窗口的后端，等待、截图等仍交给内部后端
+///
+/// 键盘鼠标接口无法返回平台错误，发送失败时记录第一个错误，由 `finish` 取回
+pub struct WindowEventBackend<'a, B> {
+    inner: &'a mut B,
+    window: u32,
+    cursor: (i32, i32),
+    error: Option<AutomationError>,
+}
+
+impl<'a, B: InputBackend> WindowEventBackend<'a, B> {
+    /// 虚拟光标初始位于窗口中心
+    pub fn new(inner: &'a mut B, window: &WindowInfo) -> Self {
+        WindowEventBackend {
+            inner,
+            window: window.id,
+            cursor: window.geometry.center(),
+            error: None,
+        }
+    }
+
+    /// 结束发送，返回过程中遇到的第一个错误
+    pub fn finish(self) -> Result<(), AutomationError> {
+        self.error.map_or(Ok(()), Err)
+    }
+
+    fn send(&mut self, event: WindowEvent) -> InputResult<()> {
+        if self.error.is_none()
+            && let Err(err) = self.inner.send_window_event(self.window, event)
+        {
+            self.error = Some(err);
+        }
+        match self.error {
+            Some(_) => Err(InputError::Simulate("failed to send event to window")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<B: InputBackend> Keyboard for WindowEventBackend<'_, B> {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        for c in text.chars() {
+            self.send(WindowEvent::Key {
+                key: EnigoKey::Unicode(c),
+                direction: Direction::Click,
+            })?;
+        }
+        Ok(Some(()))
+    }
+
+    fn key(&mut self, key: EnigoKey, direction: Direction) -> InputResult<()> {
+        self.send(WindowEvent::Key { key, direction })
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+        self.send(WindowEvent::RawKey { keycode, direction })
+    }
+}
+
+impl<B: InputBackend> Mouse for WindowEventBackend<'_, B> {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        let (x, y) = self.cursor;
+        self.send(WindowEvent::Button { button, direction, x, y })
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        let (x, y) = self.cursor;
+        self.send(WindowEvent::Motion { x, y })
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        let button = match (axis, length > 0) {
+            (Axis::Vertical, true) => Button::ScrollDown,
+            (Axis::Vertical, false) => Button::ScrollUp,
+            (Axis::Horizontal, true) => Button::ScrollRight,
+            (Axis::Horizontal, false) => Button::ScrollLeft,
+        };
+        for _ in 0..length.unsigned_abs() {
+            self.button(button, Direction::Click)?;
+        }
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        self.inner.main_display()
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok(self.cursor)
+    }
+}
+
+impl<B: InputBackend> InputBackend for WindowEventBackend<'_, B> {
+    fn wait(&mut self, duration: Duration) -> impl Future<Output = ()> + Send {
+        self.inner.wait(duration)
+    }
+
+    fn begin_input(&mut self) -> impl Future<Output = ()> + Send {
+        self.inner.begin_input()
+    }
+
+    fn end_input(&mut self) {
+        self.inner.end_input()
+    }
+
+    fn reset_timeline(&mut self) {
+        self.inner.reset_timeline()
+    }
+
+    fn capture(&mut self, rect: Rect) -> Result<Option<RgbaImage>, AutomationError> {
+        self.inner.capture(rect)
+    }
+
+    fn windows(&mut self) -> Result<Option<Vec<WindowInfo>>, AutomationError> {
+        self.inner.windows()
+    }
+
+    fn window_command(
+        &mut self,
+        selector: &WindowSelector,
+        command: WindowCommand,
+    ) -> Result<Option<WindowInfo>, AutomationError> {
+        self.inner.window_command(selector, command)
+    }
+
+    fn send_window_event(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+        self.inner.send_window_event(window, event)
+    }
+    fn find_elements(
+        &mut self,
+        selector: &ElementSelector,
+    ) -> impl Future<Output = Result<Option<Vec<ElementInfo>>, AutomationError>> + Send {
+        self.inner.find_elements(selector)
+    }
+
+    fn element_command(
+        &mut self,
+        selector: &ElementSelector,
+        command: ElementCommand,
+    ) -> impl Future<Output = Result<Option<ElementInfo>, AutomationError>> + Send {
+        self.inner.element_command(selector, command)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::x11::{EventSender, WindowManager};
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::connection::Connection;
+    use enigo::{Button, Direction};
+    use x11rb::protocol::xproto::{
+        self, AtomEnum, ButtonPressEvent, ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, EventMask,
+        InputFocus, KeyButMask, KeyPressEvent, MapState, Motion, MotionNotifyEvent, StackMode, Window,
+    };
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::{CURRENT_TIME, NONE};
+    use xkeysym::Keysym;
+
+    use super::{WindowCommand, WindowEvent, WindowInfo, WindowSelector, WindowState};
+    use crate::error::AutomationError;
+    use crate::screen::Rect;
+
+    x11rb::atom_manager! {
+        Atoms: AtomsCookie {
+            _NET_CLIENT_LIST,
+            _NET_CLIENT_LIST_STACKING,
+            _NET_ACTIVE_WINDOW,
+            _NET_WM_NAME,
+            _NET_WM_PID,
+            _NET_WM_DESKTOP,
+            _NET_WM_STATE,
+            _NET_WM_STATE_HIDDEN,
+            _NET_WM_STATE_MAXIMIZED_VERT,
+            _NET_WM_STATE_MAXIMIZED_HORZ,
+            _NET_WM_STATE_FULLSCREEN,
+            _NET_MOVERESIZE_WINDOW,
+            _NET_CLOSE_WINDOW,
+            WM_CHANGE_STATE,
+            WM_PROTOCOLS,
+            WM_DELETE_WINDOW,
+            UTF8_STRING,
+        }
+    }
+
+    /// ICCCM 的 IconicState
+    const ICONIC_STATE: u32 = 3;
+    /// EWMH 消息来源：2 表示来自分页器/自动化工具，窗口管理器会无条件执行
+    const SOURCE_PAGER: u32 = 2;
+    /// _NET_MOVERESIZE_WINDOW 中表示 x、y、width、height 有效的标志位
+    const MOVERESIZE_X: u32 = 1 << 8;
+    const MOVERESIZE_Y: u32 = 1 << 9;
+    const MOVERESIZE_WIDTH: u32 = 1 << 10;
+    const MOVERESIZE_HEIGHT: u32 = 1 << 11;
+
+    /// 基于 EWMH 的窗口管理器客户端
+    ///
+    /// 没有运行兼容 EWMH 的窗口管理器时，退化为直接操作 root 的子窗口
+    pub struct WindowManager {
+        conn: RustConnection,
+        root: Window,
+        atoms: Atoms,
+    }
+
+    impl WindowManager {
+        /// 连接到指定显示（None 表示使用 DISPLAY 环境变量）
+        pub fn connect(display: Option<&str>) -> Result<Self, AutomationError> {
+            let (conn, screen_num) = x11rb::connect(display)?;
+            let root = conn.setup().roots[screen_num].root;
+            let atoms = Atoms::new(&conn)?.reply()?;
+            Ok(WindowManager { conn, root, atoms })
+        }
+
+        /// 列出所有顶层窗口，最上层的窗口在前
+        pub fn list(&self) -> Result<Vec<WindowInfo>, AutomationError> {
+            let active = self
+                .property32(self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW.into())?
+                .first()
+                .copied();
+            let mut windows = Vec::new();
+            for window in self.client_windows()?.into_iter().rev() {
+                // 窗口可能在枚举过程中被销毁，跳过即可
+                if let Ok(info) = self.info(window, active) {
+                    windows.push(info);
+                }
+            }
+            Ok(windows)
+        }
+
+        /// 查找满足条件的窗口，最上层的窗口在前
+        pub fn find(&self, selector: &WindowSelector) -> Result<Vec<WindowInfo>, AutomationError> {
+            Ok(self
+                .list()?
+                .into_iter()
+                .filter(|window| selector.matches(window))
+                .collect())
+        }
+
+        /// 当前激活的窗口
+        pub fn active(&self) -> Result<Option<WindowInfo>, AutomationError> {
+            Ok(self.list()?.into_iter().find(|window| window.state.active))
+        }
+
+        /// 对窗口执行操作
+        pub fn apply(&self, window: u32, command: WindowCommand) -> Result<(), AutomationError> {
+            let ewmh = self.has_window_manager()?;
+            match command {
+                WindowCommand::Focus if ewmh => {
+                    self.send_root_message(window, self.atoms._NET_ACTIVE_WINDOW, [SOURCE_PAGER, CURRENT_TIME, 0, 0, 0])?;
+                }
+                WindowCommand::Focus => {
+                    self.conn.map_window(window)?;
+                    self.conn
+                        .configure_window(window, &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE))?;
+                    self.conn.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+                }
+                WindowCommand::Move { x, y } if ewmh => {
+                    let flags = MOVERESIZE_X | MOVERESIZE_Y | (SOURCE_PAGER << 12);
+                    self.send_root_message(window, self.atoms._NET_MOVERESIZE_WINDOW, [flags, x as u32, y as u32, 0, 0])?;
+                }
+                WindowCommand::Move { x, y } => {
+                    self.conn.configure_window(window, &ConfigureWindowAux::new().x(x).y(y))?;
+                }
+                WindowCommand::Resize { width, height } if ewmh => {
+                    let flags = MOVERESIZE_WIDTH | MOVERESIZE_HEIGHT | (SOURCE_PAGER << 12);
+                    self.send_root_message(window, self.atoms._NET_MOVERESIZE_WINDOW, [flags, 0, 0, width, height])?;
+                }
+                WindowCommand::Resize { width, height } => {
+                    self.conn
+                        .configure_window(window, &ConfigureWindowAux::new().width(width).height(height))?;
+                }
+                WindowCommand::Minimize if ewmh => {
+                    self.send_root_message(window, self.atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0])?;
+                }
+                WindowCommand::Minimize => {
+                    self.conn.unmap_window(window)?;
+                }
+                WindowCommand::Close if ewmh => {
+                    self.send_root_message(window, self.atoms._NET_CLOSE_WINDOW, [CURRENT_TIME, SOURCE_PAGER, 0, 0, 0])?;
+                }
+                WindowCommand::Close => {
+                    // 按 ICCCM 请求应用自行关闭窗口
+                    let event = ClientMessageEvent::new(
+                        32,
+                        window,
+                        self.atoms.WM_PROTOCOLS,
+                        [self.atoms.WM_DELETE_WINDOW, CURRENT_TIME, 0, 0, 0],
+                    );
+                    self.conn.send_event(false, window, EventMask::NO_EVENT, event)?;
+                }
+            }
+            self.conn.flush()?;
+            Ok(())
+        }
+
+        /// 查找满足条件的最上层窗口并执行操作，返回被操作的窗口
+        pub fn apply_to(&self, selector: &WindowSelector, command: WindowCommand) -> Result<WindowInfo, AutomationError> {
+            let window = self
+                .find(selector)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| AutomationError::Platform(format!("no window matches {:?}", selector)))?;
+            self.apply(window.id, command)?;
+            Ok(window)
+        }
+
+        fn has_window_manager(&self) -> Result<bool, AutomationError> {
+            Ok(!self
+                .property32(self.root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW.into())?
+                .is_empty())
+        }
+
+        /// 按从下到上的叠放顺序返回顶层窗口
+        fn client_windows(&self) -> Result<Vec<Window>, AutomationError> {
+            let stacking =
+                self.property32(self.root, self.atoms._NET_CLIENT_LIST_STACKING, AtomEnum::WINDOW.into())?;
+            if !stacking.is_empty() {
+                return Ok(stacking);
+            }
+            let clients = self.property32(self.root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW.into())?;
+            if !clients.is_empty() {
+                return Ok(clients);
+            }
+
+            // 没有窗口管理器：root 的已映射子窗口就是顶层窗口
+            let tree = self.conn.query_tree(self.root)?.reply()?;
+            let mut windows = Vec::new();
+            for child in tree.children {
+                let attributes = self.conn.get_window_attributes(child)?.reply()?;
+                if attributes.map_state == MapState::VIEWABLE && !attributes.override_redirect {
+                    windows.push(child);
+                }
+            }
+            Ok(windows)
+        }
+
+        fn info(&self, window: Window, active: Option<Window>) -> Result<WindowInfo, AutomationError> {
+            let title = match self.property_string(window, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING)? {
+                title if !title.is_empty() => title,
+                _ => self.property_string(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())?,
+            };
+            let class = self.property_string(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?;
+            let mut class_parts = class.split('\0');
+            let instance = class_parts.next().unwrap_or_default().to_string();
+            let class = class_parts.next().unwrap_or_default().to_string();
+
+            let pid = self
+                .property32(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL.into())?
+                .first()
+                .copied();
+            let desktop = self
+                .property32(window, self.atoms._NET_WM_DESKTOP, AtomEnum::CARDINAL.into())?
+                .first()
+                .copied();
+
+            let states = self.property32(window, self.atoms._NET_WM_STATE, AtomEnum::ATOM.into())?;
+            let mapped = self.conn.get_window_attributes(window)?.reply()?.map_state == MapState::VIEWABLE;
+            let state = WindowState {
+                active: active == Some(window),
+                minimized: states.contains(&self.atoms._NET_WM_STATE_HIDDEN) || !mapped,
+                maximized: states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_VERT)
+                    && states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_HORZ),
+                fullscreen: states.contains(&self.atoms._NET_WM_STATE_FULLSCREEN),
+            };
+
+            let geometry = self.conn.get_geometry(window)?.reply()?;
+            let origin = self.conn.translate_coordinates(window, self.root, 0, 0)?.reply()?;
+            Ok(WindowInfo {
+                id: window,
+                title,
+                class,
+                instance,
+                pid,
+                geometry: Rect::new(
+                    origin.dst_x as i32,
+                    origin.dst_y as i32,
+                    geometry.width as u32,
+                    geometry.height as u32,
+                ),
+                desktop,
+                state,
+            })
+        }
+
+        fn property32(&self, window: Window, property: u32, kind: u32) -> Result<Vec<u32>, AutomationError> {
+            let reply = self
+                .conn
+                .get_property(false, window, property, kind, 0, u32::MAX)?
+                .reply()?;
+            Ok(reply.value32().map(|values| values.collect()).unwrap_or_default())
+        }
+
+        fn property_string(&self, window: Window, property: u32, kind: u32) -> Result<String, AutomationError> {
+            let reply = self
+                .conn
+                .get_property(false, window, property, kind, 0, u32::MAX)?
+                .reply()?;
+            Ok(String::from_utf8_lossy(&reply.value).trim_end_matches('\0').to_string())
+        }
+
+        fn send_root_message(&self, window: Window, kind: u32, data: [u32; 5]) -> Result<(), AutomationError> {
+            let event = ClientMessageEvent::new(32, window, kind, data);
+            self.conn.send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// 基于 XSendEvent 的事件发送器，事件带有 send_event 标记，不经过真实输入设备
+    pub struct EventSender {
+        conn: RustConnection,
+        root: Window,
+        min_keycode: u8,
+        per_keycode: usize,
+        keysyms: Vec<u32>,
+        /// 当前按下的修饰键和鼠标按键
+        state: u16,
+    }
+
+    impl EventSender {
+        /// 连接到指定显示（None 表示使用 DISPLAY 环境变量）
+        pub fn connect(display: Option<&str>) -> Result<Self, AutomationError> {
+            let (conn, screen_num) = x11rb::connect(display)?;
+            let root = conn.setup().roots[screen_num].root;
+            let min_keycode = conn.setup().min_keycode;
+            let count = conn.setup().max_keycode - min_keycode + 1;
+            let mapping = conn.get_keyboard_mapping(min_keycode, count)?.reply()?;
+            Ok(EventSender {
+                conn,
+                root,
+                min_keycode,
+                per_keycode: mapping.keysyms_per_keycode as usize,
+                keysyms: mapping.keysyms,
+                state: 0,
+            })
+        }
+
+        /// 将事件发送给窗口（鼠标事件发送给光标位置下最深层的子窗口）
+        pub fn send(&mut self, window: u32, event: WindowEvent) -> Result<(), AutomationError> {
+            match event {
+                WindowEvent::Key { key, direction } => {
+                    let keysym = Keysym::from(key).raw();
+                    let (keycode, shifted) = self.keycode_for(keysym).ok_or_else(|| {
+                        AutomationError::Platform(format!("key {:?} is not on the current keyboard layout", key))
+                    })?;
+                    let state = if shifted { self.state | u16::from(KeyButMask::SHIFT) } else { self.state };
+                    self.key(window, keycode, state, modifier_mask(keysym), direction)?;
+                }
+                WindowEvent::RawKey { keycode, direction } => {
+                    let keycode = u8::try_from(keycode)
+                        .map_err(|_| AutomationError::Platform(format!("keycode {} is out of range", keycode)))?;
+                    self.key(window, keycode, self.state, 0, direction)?;
+                }
+                WindowEvent::Button { button, direction, x, y } => {
+                    let detail = button_number(button);
+                    let mask = button_mask(detail);
+                    if matches!(direction, Direction::Press | Direction::Click) {
+                        self.button(window, xproto::BUTTON_PRESS_EVENT, detail, x, y)?;
+                        self.state |= mask;
+                    }
+                    if matches!(direction, Direction::Release | Direction::Click) {
+                        self.button(window, xproto::BUTTON_RELEASE_EVENT, detail, x, y)?;
+                        self.state &= !mask;
+                    }
+                }
+                WindowEvent::Motion { x, y } => {
+                    let (target, event_x, event_y) = self.child_at(window, x, y)?;
+                    let event = MotionNotifyEvent {
+                        response_type: xproto::MOTION_NOTIFY_EVENT,
+                        detail: Motion::NORMAL,
+                        sequence: 0,
+                        time: CURRENT_TIME,
+                        root: self.root,
+                        event: target,
+                        child: NONE,
+                        root_x: x as i16,
+                        root_y: y as i16,
+                        event_x,
+                        event_y,
+                        state: self.state.into(),
+                        same_screen: true,
+                    };
+                    self.conn.send_event(
+                        true,
+                        target,
+                        EventMask::POINTER_MOTION | EventMask::BUTTON_MOTION,
+                        event,
+                    )?;
+                }
+            }
+            self.conn.flush()?;
+            Ok(())
+        }
+
+        /// 查找能产生该 keysym 的键码，第二个值表示是否需要按住 Shift
+        fn keycode_for(&self, keysym: u32) -> Option<(u8, bool)> {
+            if self.per_keycode == 0 {
+                return None;
+            }
+            for column in 0..self.per_keycode.min(2) {
+                let found = self
+                    .keysyms
+                    .chunks(self.per_keycode)
+                    .position(|syms| syms[column] == keysym);
+                if let Some(index) = found {
+                    return Some((self.min_keycode + index as u8, column == 1));
+                }
+            }
+            None
+        }
+
+        fn key(
+            &mut self,
+            window: Window,
+            keycode: u8,
+            state: u16,
+            modifier: u16,
+            direction: Direction,
+        ) -> Result<(), AutomationError> {
+            let mut event = KeyPressEvent {
+                response_type: xproto::KEY_PRESS_EVENT,
+                detail: keycode,
+                sequence: 0,
+                time: CURRENT_TIME,
+                root: self.root,
+                event: window,
+                child: NONE,
+                root_x: 0,
+                root_y: 0,
+                event_x: 0,
+                event_y: 0,
+                state: state.into(),
+                same_screen: true,
+            };
+            // X 事件中的 state 是事件发生之前的状态
+            if matches!(direction, Direction::Press | Direction::Click) {
+                self.conn.send_event(true, window, EventMask::KEY_PRESS, event)?;
+                self.state |= modifier;
+                event.state = (state | modifier).into();
+            }
+            if matches!(direction, Direction::Release | Direction::Click) {
+                event.response_type = xproto::KEY_RELEASE_EVENT;
+                self.conn.send_event(true, window, EventMask::KEY_RELEASE, event)?;
+                self.state &= !modifier;
+            }
+            Ok(())
+        }
+
+        fn button(&self, window: Window, kind: u8, detail: u8, x: i32, y: i32) -> Result<(), AutomationError> {
+            let (target, event_x, event_y) = self.child_at(window, x, y)?;
+            let event = ButtonPressEvent {
+                response_type: kind,
+                detail,
+                sequence: 0,
+                time: CURRENT_TIME,
+                root: self.root,
+                event: target,
+                child: NONE,
+                root_x: x as i16,
+                root_y: y as i16,
+                event_x,
+                event_y,
+                state: self.state.into(),
+                same_screen: true,
+            };
+            let mask = if kind == xproto::BUTTON_PRESS_EVENT {
+                EventMask::BUTTON_PRESS
+            } else {
+                EventMask::BUTTON_RELEASE
+            };
+            self.conn.send_event(true, target, mask, event)?;
+            Ok(())
+        }
+
+        /// 屏幕坐标下最深层的子窗口及其局部坐标
+        fn child_at(&self, window: Window, x: i32, y: i32) -> Result<(Window, i16, i16), AutomationError> {
+            let mut target = window;
+            loop {
+                let reply = self
+                    .conn
+                    .translate_coordinates(self.root, target, x as i16, y as i16)?
+                    .reply()?;
+                if reply.child == NONE {
+                    return Ok((target, reply.dst_x, reply.dst_y));
+                }
+                target = reply.child;
+            }
+        }
+    }
+
+    /// 修饰键对应的状态位，非修饰键返回 0
+    fn modifier_mask(keysym: u32) -> u16 {
+        let mask = match Keysym::from(keysym) {
+            Keysym::Shift_L | Keysym::Shift_R => KeyButMask::SHIFT,
+            Keysym::Control_L | Keysym::Control_R => KeyButMask::CONTROL,
+            Keysym::Alt_L | Keysym::Alt_R | Keysym::Meta_L | Keysym::Meta_R => KeyButMask::MOD1,
+            Keysym::Super_L | Keysym::Super_R => KeyButMask::MOD4,
+            _ => return 0,
+        };
+        mask.into()
+    }
+
+    fn button_number(button: Button) -> u8 {
+        match button {
+            Button::Left => 1,
+            Button::Middle => 2,
+            Button::Right => 3,
+            Button::ScrollUp => 4,
+            Button::ScrollDown => 5,
+            Button::ScrollLeft => 6,
+            Button::ScrollRight => 7,
+            Button::Back => 8,
+            Button::Forward => 9,
+        }
+    }
+
+    /// 按键 1~5 在 state 中的状态位，其余按键没有状态位
+    fn button_mask(button: u8) -> u16 {
+        match button {
+            1..=5 => 1 << (7 + button),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, title: &str, class: &str, instance: &str, pid: Option<u32>) -> WindowInfo {
+        WindowInfo {
+            id,
+            title: title.to_string(),
+            class: class.to_string(),
+            instance: instance.to_string(),
+            pid,
+            geometry: Rect::new(0, 0, 100, 100),
+            desktop: None,
+            state: WindowState::default(),
+        }
+    }
+
+    #[test]
+    fn selector_matches_all_given_conditions() {
+        let editor = window(7, "notes.txt - Text Editor", "Gedit", "gedit", Some(42));
+        assert!(WindowSelector::default().is_empty());
+        assert!(WindowSelector::default().matches(&editor));
+        assert!(WindowSelector::id(7).matches(&editor));
+        assert!(!WindowSelector::id(8).matches(&editor));
+        assert!(WindowSelector::title("TEXT editor").matches(&editor));
+        assert!(!WindowSelector::title("terminal").matches(&editor));
+        assert!(WindowSelector::class("gedit").matches(&editor));
+        assert!(WindowSelector::class("GEDIT").matches(&window(1, "", "Other", "gedit", None)));
+        assert!(!WindowSelector::class("edit").matches(&editor));
+
+        let selector = WindowSelector {
+            title: Some("notes".into()),
+            pid: Some(43),
+            ..Default::default()
+        };
+        assert!(!selector.is_empty());
+        assert!(!selector.matches(&editor));
+        assert!(!selector.matches(&window(7, "notes", "", "", None)));
+    }
+
+    fn targeted(action: serde_json::Value) -> crate::enums::InputAction {
+        serde_json::from_value(action).unwrap()
+    }
+
+    #[tokio::test]
+    async fn targeted_input_goes_to_the_window() {
+        use crate::dry_run::{DryRunBackend, InputEvent};
+        use crate::simulator::InputSimulator;
+        use serde_json::json;
+
+        let mut editor = window(42, "notes.txt - Text Editor", "Gedit", "gedit", None);
+        editor.geometry = Rect::new(100, 100, 200, 100);
+        let mut terminal = window(7, "Terminal", "XTerm", "xterm", None);
+        terminal.state.active = true;
+        let windows = vec![terminal, editor];
+        let simulator = InputSimulator::detached((800, 600));
+
+        // SendEvent：事件直接发给窗口，不产生全局输入
+        let target = json!({"window": {"title": "text editor"}, "delivery": "SendEvent"});
+        let mut dry_run = DryRunBackend::new((800, 600)).with_windows(windows.clone());
+        let key = targeted(json!({"type": "KeyPress", "params": {"key": "A", "target": target}}));
+        let click = targeted(json!({"type": "MouseClick", "params": {"button": "Left", "x": 150, "y": 120, "target": target}}));
+        simulator.execute_with_backend(&mut dry_run, &key).await.unwrap();
+        simulator.execute_with_backend(&mut dry_run, &click).await.unwrap();
+        let events: Vec<_> = dry_run.events().iter().map(|event| &event.event).collect();
+        assert!(!events.iter().any(|event| matches!(
+            event,
+            InputEvent::Key { .. } | InputEvent::MouseButton { .. } | InputEvent::MouseMove { .. }
+        )));
+        let sent: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                InputEvent::WindowEvent { window, event } => Some((*window, *event)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                (42, WindowEvent::Key { key: EnigoKey::Unicode('a'), direction: Direction::Press }),
+                (42, WindowEvent::Key { key: EnigoKey::Unicode('a'), direction: Direction::Release }),
+                (42, WindowEvent::Motion { x: 150, y: 120 }),
+                (42, WindowEvent::Button { button: Button::Left, direction: Direction::Click, x: 150, y: 120 }),
+            ]
+        );
+
+        // Focus：先激活目标窗口，再注入全局输入，最后恢复原来的焦点窗口
+        let target = json!({"window": {"id": 42}, "delivery": "Focus"});
+        let mut dry_run = DryRunBackend::new((800, 600)).with_windows(windows);
+        let key = targeted(json!({"type": "KeyPress", "params": {"key": "A", "target": target}}));
+        simulator.execute_with_backend(&mut dry_run, &key).await.unwrap();
+        let events: Vec<_> = dry_run
+            .events()
+            .iter()
+            .map(|event| &event.event)
+            .filter(|event| !matches!(event, InputEvent::Wait { .. }))
+            .collect();
+        assert!(matches!(
+            events.first(),
+            Some(InputEvent::Window { window, command: WindowCommand::Focus }) if *window == WindowSelector::id(42)
+        ));
+        assert!(matches!(
+            events.last(),
+            Some(InputEvent::Window { window, command: WindowCommand::Focus }) if *window == WindowSelector::id(7)
+        ));
+        assert!(events[1..events.len() - 1].iter().all(|event| matches!(event, InputEvent::Key { .. })));
+        assert!(events.len() > 2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lists_resizes_and_minimizes_windows() {
+        use std::time::{Duration, Instant};
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, CreateWindowAux, PropMode, WindowClass};
+        use x11rb::wrapper::ConnectionExt as _;
+
+        if !crate::testing::display_available() {
+            return;
+        }
+
+        // 在单独的连接上创建测试窗口，模拟其他应用
+        let (conn, screen_num) = x11rb::connect(None).unwrap();
+        let screen = &conn.setup().roots[screen_num];
+        let id = conn.generate_id().unwrap();
+        conn.create_window(
+            screen.root_depth,
+            id,
+            screen.root,
+            10,
+            20,
+            120,
+            80,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        let title = format!("automation window test {}", std::process::id());
+        conn.change_property8(PropMode::REPLACE, id, AtomEnum::WM_NAME, AtomEnum::STRING, title.as_bytes())
+            .unwrap();
+        conn.change_property8(PropMode::REPLACE, id, AtomEnum::WM_CLASS, AtomEnum::STRING, b"automation-test\0AutomationTest\0")
+            .unwrap();
+        conn.map_window(id).unwrap();
+        conn.sync().unwrap();
+
+        // 有窗口管理器时操作是异步完成的
+        let manager = WindowManager::connect(None).unwrap();
+        let selector = WindowSelector::title(title.clone());
+        let wait_for = |check: &dyn Fn(&[WindowInfo]) -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            loop {
+                let found = manager.find(&selector).unwrap();
+                if check(&found) || Instant::now() > deadline {
+                    return found;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        };
+
+        let found = wait_for(&|found| !found.is_empty());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title, title);
+        assert_eq!(found[0].class, "AutomationTest");
+        assert_eq!(found[0].instance, "automation-test");
+        assert_eq!((found[0].geometry.width, found[0].geometry.height), (120, 80));
+        let id = found[0].id;
+        assert!(manager.find(&WindowSelector::class("AutomationTest")).unwrap().iter().any(|w| w.id == id));
+
+        manager.apply(id, WindowCommand::Resize { width: 150, height: 90 }).unwrap();
+        let found = wait_for(&|found| found.first().is_some_and(|w| w.geometry.width == 150));
+        assert_eq!((found[0].geometry.width, found[0].geometry.height), (150, 90));
+
+        manager.apply_to(&selector, WindowCommand::Minimize).unwrap();
+        let found = wait_for(&|found| found.first().is_none_or(|w| w.state.minimized));
+        assert!(found.first().is_none_or(|w| w.state.minimized));
+
+        assert!(manager.apply_to(&WindowSelector::title(format!("{title} missing")), WindowCommand::Focus).is_err());
+        conn.destroy_window(id).unwrap();
+        conn.sync().unwrap();
+    }
+}